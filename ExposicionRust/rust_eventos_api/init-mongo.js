@@ -12,6 +12,8 @@ db.events.insertMany([
     fecha: "2024-12-15T20:00:00Z",
     lugar: "Estadio Nacional",
     capacidad: 1000,
+    capacidad_total: 1000,
+    publicado: true,
     precio: "75.00",
     created_at: new Date(),
     updated_at: new Date()
@@ -22,6 +24,8 @@ db.events.insertMany([
     fecha: "2024-12-20T19:00:00Z",
     lugar: "Teatro Municipal",
     capacidad: 500,
+    capacidad_total: 500,
+    publicado: true,
     precio: "50.00",
     created_at: new Date(),
     updated_at: new Date()
@@ -32,8 +36,15 @@ db.events.insertMany([
     fecha: "2024-12-25T21:00:00Z",
     lugar: "Teatro Nacional",
     capacidad: 200,
+    capacidad_total: 200,
+    publicado: true,
     precio: "30.00",
     created_at: new Date(),
     updated_at: new Date()
   }
 ]);
+
+// Las compras guardan en evento_id el campo `id` del evento (no el _id), que debe ser único
+db.events.createIndex({ id: 1 }, { unique: true });
+db.purchases.createIndex({ usuario_id: 1 });
+db.purchases.createIndex({ evento_id: 1 });
@@ -1,5 +1,7 @@
 use actix_web::{
-    delete, get, post, put,
+    delete, get,
+    middleware::from_fn,
+    post, put,
     web::{self, Json, Path},
     HttpRequest, HttpResponse,
 };
@@ -7,12 +9,15 @@ use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use actix_web::HttpMessage;
-use std::env;
 
 use crate::{
-    error::AppError,
-    model::{CreatePurchaseDto, UpdatePurchaseDto},
-    repository::mongodb_repo::MongoRepo,
+    auth::{require_support, SUPPORT_ROLES},
+    error::{AppError, ErrorResponse},
+    metrics,
+    telemetry::RequestId,
+    notifications::{Notification, Notifier},
+    model::{CreatePurchaseDto, Event, Purchase, PurchaseStatus},
+    repository::purchase_repository::PurchaseRepository,
 };
 
 // Estructura para los claims del JWT actualizada
@@ -26,50 +31,95 @@ pub struct Claims {
     pub exp: usize,   // expiration time
 }
 
-// Endpoint para obtener todos los eventos disponibles (público)
+impl Claims {
+    fn is_support(&self) -> bool {
+        SUPPORT_ROLES.contains(&self.rol.as_str())
+    }
+}
+
+/// Endpoint para obtener todos los eventos disponibles (público)
+#[utoipa::path(
+    tag = "eventos",
+    responses(
+        (status = 200, description = "Eventos disponibles", body = Vec<Event>),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    )
+)]
 #[get("/eventos")]
-pub async fn get_all_events(db: web::Data<MongoRepo>) -> Result<HttpResponse, AppError> {
+pub async fn get_all_events(db: web::Data<dyn PurchaseRepository>) -> Result<HttpResponse, AppError> {
     let events = db.get_all_events().await?;
     Ok(HttpResponse::Ok().json(events))
 }
 
-// Endpoint para crear una compra de entradas (protegido)
+/// Endpoint para crear una compra de entradas (protegido)
+#[utoipa::path(
+    tag = "compras",
+    request_body = CreatePurchaseDto,
+    responses(
+        (status = 201, description = "Compra registrada; sus entradas quedan reservadas hasta `reservada_hasta`", body = Purchase),
+        (status = 400, description = "Cantidad inválida", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 404, description = "Evento no encontrado", body = ErrorResponse),
+        (status = 409, description = "No quedan entradas suficientes", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[post("/compras")]
 pub async fn create_purchase(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn PurchaseRepository>,
     usuario_id: web::ReqData<String>,
     purchase_dto: Json<CreatePurchaseDto>,
 ) -> Result<HttpResponse, AppError> {
     let dto = purchase_dto.into_inner();
+    if dto.cantidad <= 0 {
+        return Err(AppError::InvalidQuantity("debe ser mayor a 0".to_string()));
+    }
     let usuario_id = usuario_id.into_inner();
     let created_purchase = db.create_purchase(usuario_id, dto).await?;
+    metrics::record_tickets_purchased(created_purchase.cantidad);
     Ok(HttpResponse::Created().json(created_purchase))
 }
 
-// Endpoint para consultar las compras de un usuario (protegido)
+/// Endpoint para consultar las compras de un usuario (protegido)
+#[utoipa::path(
+    tag = "compras",
+    responses(
+        (status = 200, description = "Compras del usuario autenticado", body = Vec<Purchase>),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[get("/compras")]
 pub async fn get_user_purchases(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn PurchaseRepository>,
     usuario_id: web::ReqData<String>,
-    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
-    // Imprimir el token recibido (Authorization header)
-    if let Some(auth_header) = req.headers().get("Authorization") {
-        if let Ok(token) = auth_header.to_str() {
-            println!("Token recibido: {}", token);
-        }
-    }
-
     let usuario_id = usuario_id.into_inner();
     let purchases = db.get_purchases_by_user(usuario_id).await?;
     Ok(HttpResponse::Ok().json(purchases))
 }
 
-// Endpoint para marcar una compra como pagada (protegido)
+/// Endpoint para marcar una compra como pagada (protegido)
+#[utoipa::path(
+    tag = "compras",
+    params(("id" = String, Path, description = "ID de la compra")),
+    responses(
+        (status = 200, description = "Compra pagada", body = Purchase),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token inválido o la compra es de otro usuario", body = ErrorResponse),
+        (status = 404, description = "Compra no encontrada", body = ErrorResponse),
+        (status = 409, description = "La compra no está pendiente o su reserva venció", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[put("/compras/{id}/pagar")]
 pub async fn pay_purchase(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn PurchaseRepository>,
+    notifier: web::Data<Notifier>,
     purchase_id: Path<String>,
+    request_id: RequestId,
     req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
     // 1. Obtener el ID y validar
@@ -77,8 +127,7 @@ pub async fn pay_purchase(
         .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
 
     // 2. Extraer Claims del token JWT
-    let extensions = req.extensions();
-    let claims = extensions.get::<Claims>().ok_or_else(|| {
+    let claims = req.extensions().get::<Claims>().cloned().ok_or_else(|| {
         AppError::Unauthorized("No se pudieron extraer los claims del token".to_string())
     })?;
     let usuario_id = &claims.sub;
@@ -91,88 +140,88 @@ pub async fn pay_purchase(
         return Err(AppError::Unauthorized("No tienes permisos para pagar esta compra".to_string()));
     }
 
-    // 4. Marcar la compra como pagada
-    let updated_purchase = db.update_purchase(id, UpdatePurchaseDto { pagado: Some(true) }).await?;
+    // 4. Marcar la compra como pagada (solo desde pendiente)
+    let updated_purchase = db.transition_purchase(id, PurchaseStatus::Pagada).await?;
 
-    // 5. Enviar mensaje a RabbitMQ
-    if let Err(e) = send_notification_to_rabbitmq(&updated_purchase, usuario_id, correo, nombre).await {
-        log::error!("Error al enviar notificación a RabbitMQ: {:?}", e);
+    // 5. Encolar el mensaje para RabbitMQ (se publica en segundo plano y se envía antes de apagar el servicio)
+    if let Err(e) = send_notification_to_rabbitmq(&notifier, &updated_purchase, usuario_id, correo, nombre, request_id) {
+        tracing::error!(error = %e, compra_id = %id, "Error al encolar la notificación para RabbitMQ");
     }
 
     Ok(HttpResponse::Ok().json(updated_purchase))
 }
 
+/// Endpoint para cancelar una compra pendiente y liberar sus entradas (protegido)
+#[utoipa::path(
+    tag = "compras",
+    params(("id" = String, Path, description = "ID de la compra")),
+    responses(
+        (status = 200, description = "Compra cancelada", body = Purchase),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token inválido o la compra es de otro usuario", body = ErrorResponse),
+        (status = 404, description = "Compra no encontrada", body = ErrorResponse),
+        (status = 409, description = "La compra no está pendiente", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/compras/{id}/cancelar")]
+pub async fn cancel_purchase(
+    db: web::Data<dyn PurchaseRepository>,
+    purchase_id: Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let id = ObjectId::parse_str(purchase_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    let claims = req.extensions().get::<Claims>().cloned().ok_or_else(|| {
+        AppError::Unauthorized("No se pudieron extraer los claims del token".to_string())
+    })?;
+
+    // La cancela su dueño o el personal de soporte
+    let purchase = db.get_purchase(id).await?;
+    if purchase.usuario_id != claims.sub && !claims.is_support() {
+        return Err(AppError::Unauthorized("No tienes permisos para cancelar esta compra".to_string()));
+    }
+
+    let cancelled_purchase = db.transition_purchase(id, PurchaseStatus::Cancelada).await?;
+    Ok(HttpResponse::Ok().json(cancelled_purchase))
+}
+
+/// Endpoint para reembolsar una compra pagada y liberar sus entradas (solo personal de soporte)
+#[utoipa::path(
+    tag = "compras",
+    params(("id" = String, Path, description = "ID de la compra")),
+    responses(
+        (status = 200, description = "Compra reembolsada", body = Purchase),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 403, description = "El usuario no es personal de soporte", body = ErrorResponse),
+        (status = 404, description = "Compra no encontrada", body = ErrorResponse),
+        (status = 409, description = "La compra no está pagada", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/compras/{id}/reembolsar", wrap = "from_fn(require_support)")]
+pub async fn refund_purchase(
+    db: web::Data<dyn PurchaseRepository>,
+    purchase_id: Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let id = ObjectId::parse_str(purchase_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    let refunded_purchase = db.transition_purchase(id, PurchaseStatus::Reembolsada).await?;
+    Ok(HttpResponse::Ok().json(refunded_purchase))
+}
+
 // Función auxiliar para enviar notificación a RabbitMQ
-// Función auxiliar para enviar notificación a RabbitMQ
-async fn send_notification_to_rabbitmq(
+fn send_notification_to_rabbitmq(
+    notifier: &Notifier,
     purchase: &crate::model::Purchase,
     usuario_id: &str,
     correo: &str,
     nombre: &str,
+    request_id: RequestId,
 ) -> Result<(), AppError> {
-    use lapin::{
-        options::{BasicPublishOptions, QueueDeclareOptions}, 
-        types::FieldTable, 
-        BasicProperties, 
-        Connection, 
-        ConnectionProperties
-    };
-
-    // Obtener configuración de RabbitMQ desde variables de entorno
-    // Usar localhost como fallback para desarrollo local
-    let rabbitmq_host = env::var("RABBITMQ_HOST")
-        .unwrap_or_else(|_| "localhost".to_string());  // Cambiar fallback
-    let rabbitmq_port = env::var("RABBITMQ_PORT")
-        .unwrap_or_else(|_| "5672".to_string());
-    let rabbitmq_user = env::var("RABBITMQ_USER")
-        .unwrap_or_else(|_| "guest".to_string());
-    let rabbitmq_pass = env::var("RABBITMQ_PASS")
-        .unwrap_or_else(|_| "guest".to_string());
-    let queue_name = env::var("RABBITMQ_QUEUE")
-        .unwrap_or_else(|_| "notifications_queue".to_string());
-    
-    let rabbitmq_url = format!(
-        "amqp://{}:{}@{}:{}/%2f",
-        rabbitmq_user, rabbitmq_pass, rabbitmq_host, rabbitmq_port
-    );
-
-    log::info!("Intentando conectar a RabbitMQ en: {}:{}", rabbitmq_host, rabbitmq_port);
-    log::info!("URL de conexión: {}", rabbitmq_url);
-
-    let connection = Connection::connect(&rabbitmq_url, ConnectionProperties::default())
-        .await
-        .map_err(|e| {
-            log::error!("Error al conectar con RabbitMQ en {}:{}: {:?}", rabbitmq_host, rabbitmq_port, e);
-            log::error!("Posibles causas:");
-            log::error!("1. RabbitMQ no está ejecutándose");
-            log::error!("2. Puerto {} no está disponible", rabbitmq_port);
-            log::error!("3. Firewall bloqueando la conexión");
-            AppError::InternalError("Error al conectar con RabbitMQ".into())
-        })?;
-
-    let channel = connection.create_channel().await.map_err(|e| {
-        log::error!("Error al crear el canal RabbitMQ: {:?}", e);
-        AppError::InternalError("Error al crear el canal RabbitMQ".into())
-    })?;
-
-    // Configurar la cola como durable
-    let queue_options = QueueDeclareOptions {
-        durable: true,
-        ..Default::default()
-    };
-
-    channel
-        .queue_declare(
-            &queue_name,
-            queue_options,
-            FieldTable::default(),
-        )
-        .await
-        .map_err(|e| {
-            log::error!("Error al declarar la cola '{}': {:?}", queue_name, e);
-            AppError::InternalError("Error al declarar la cola".into())
-        })?;
-
     // Preparar el payload para la notificación
     let compra_id = purchase.id
         .map(|id| id.to_hex())
@@ -190,33 +239,25 @@ async fn send_notification_to_rabbitmq(
     })
     .to_string();
 
-    channel
-        .basic_publish(
-            "",
-            &queue_name,
-            BasicPublishOptions::default(),
-            payload.as_bytes(),
-            BasicProperties::default(),
-        )
-        .await
-        .map_err(|e| {
-            log::error!("Error al publicar en RabbitMQ: {:?}", e);
-            AppError::InternalError("Error al publicar en RabbitMQ".into())
-        })?
-        .await
-        .map_err(|e| {
-            log::error!("Publicación no confirmada: {:?}", e);
-            AppError::InternalError("Publicación no confirmada".into())
-        })?;
-
-    log::info!("Notificación enviada exitosamente a RabbitMQ para compra: {}", compra_id);
-    Ok(())
+    notifier.send(Notification { compra_id, request_id, payload })
 }
 
-// Endpoint para eliminar una compra (protegido)
+/// Endpoint para eliminar una compra (protegido)
+#[utoipa::path(
+    tag = "compras",
+    params(("id" = String, Path, description = "ID de la compra")),
+    responses(
+        (status = 204, description = "Compra eliminada"),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token inválido o la compra es de otro usuario", body = ErrorResponse),
+        (status = 404, description = "Compra no encontrada", body = ErrorResponse),
+        (status = 409, description = "La compra está pagada o reembolsada", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[delete("/compras/{id}")]
 pub async fn delete_purchase(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn PurchaseRepository>,
     purchase_id: Path<String>,
     req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
@@ -224,8 +265,7 @@ pub async fn delete_purchase(
         .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
 
     // Verificar que la compra pertenece al usuario autenticado
-    let extensions = req.extensions();
-    let claims = extensions.get::<Claims>().ok_or_else(|| {
+    let claims = req.extensions().get::<Claims>().cloned().ok_or_else(|| {
         AppError::Unauthorized("No se pudieron extraer los claims del token".to_string())
     })?;
     
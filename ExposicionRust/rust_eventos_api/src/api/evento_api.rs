@@ -0,0 +1,161 @@
+use actix_web::{
+    delete,
+    middleware::from_fn,
+    post, put,
+    web::{self, Json, Path},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    auth::require_organizer,
+    error::{AppError, ErrorResponse},
+    model::{Event, EventDto},
+    repository::purchase_repository::PurchaseRepository,
+};
+
+// Revisa todos los campos y reporta juntos los problemas encontrados
+fn validate_event(dto: &EventDto) -> Result<(), AppError> {
+    let mut problems = Vec::new();
+
+    if dto.nombre.trim().is_empty() {
+        problems.push("nombre: no puede estar vacío".to_string());
+    }
+    if dto.lugar.trim().is_empty() {
+        problems.push("lugar: no puede estar vacío".to_string());
+    }
+    match DateTime::parse_from_rfc3339(&dto.fecha) {
+        Ok(fecha) if fecha.with_timezone(&Utc) <= Utc::now() => {
+            problems.push("fecha: debe ser posterior a la fecha actual".to_string())
+        }
+        Ok(_) => {}
+        Err(_) => problems.push("fecha: debe tener formato RFC 3339, por ejemplo 2025-12-15T20:00:00Z".to_string()),
+    }
+    if dto.capacidad <= 0 {
+        problems.push("capacidad: debe ser mayor que cero".to_string());
+    }
+    if !is_valid_price(&dto.precio) {
+        problems.push("precio: debe ser un número no negativo con hasta dos decimales".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidEvent(problems.join("; ")))
+    }
+}
+
+// Precio en el formato que guarda la colección: dígitos y, opcionalmente, uno o dos decimales
+fn is_valid_price(precio: &str) -> bool {
+    let (entero, decimales) = precio.split_once('.').unwrap_or((precio, ""));
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    !entero.is_empty()
+        && all_digits(entero)
+        && decimales.len() <= 2
+        && all_digits(decimales)
+        && !(precio.contains('.') && decimales.is_empty())
+}
+
+/// Endpoint para crear un evento; queda sin publicar (organizadores y administradores)
+#[utoipa::path(
+    tag = "eventos",
+    request_body = EventDto,
+    responses(
+        (status = 201, description = "Evento creado sin publicar", body = Event),
+        (status = 400, description = "Datos del evento inválidos", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 403, description = "El usuario no es organizador ni administrador", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/eventos", wrap = "from_fn(require_organizer)")]
+pub async fn create_event(
+    db: web::Data<dyn PurchaseRepository>,
+    event_dto: Json<EventDto>,
+) -> Result<HttpResponse, AppError> {
+    let dto = event_dto.into_inner();
+    validate_event(&dto)?;
+
+    let event = db.create_event(dto).await?;
+    tracing::info!(evento_id = event.id, "Evento creado");
+    Ok(HttpResponse::Created().json(event))
+}
+
+/// Endpoint para actualizar un evento; las entradas disponibles cambian tanto como la capacidad
+#[utoipa::path(
+    tag = "eventos",
+    params(("id" = i32, Path, description = "ID del evento")),
+    request_body = EventDto,
+    responses(
+        (status = 200, description = "Evento actualizado", body = Event),
+        (status = 400, description = "ID o datos del evento inválidos, o capacidad menor que las entradas ocupadas", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 403, description = "El usuario no es organizador ni administrador", body = ErrorResponse),
+        (status = 404, description = "Evento no encontrado", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/eventos/{id}", wrap = "from_fn(require_organizer)")]
+pub async fn update_event(
+    db: web::Data<dyn PurchaseRepository>,
+    evento_id: Path<i32>,
+    event_dto: Json<EventDto>,
+) -> Result<HttpResponse, AppError> {
+    let dto = event_dto.into_inner();
+    validate_event(&dto)?;
+
+    let event = db.update_event(evento_id.into_inner(), dto).await?;
+    Ok(HttpResponse::Ok().json(event))
+}
+
+/// Endpoint para publicar un evento; desde entonces aparece en el listado y acepta compras
+#[utoipa::path(
+    tag = "eventos",
+    params(("id" = i32, Path, description = "ID del evento")),
+    responses(
+        (status = 200, description = "Evento publicado", body = Event),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 403, description = "El usuario no es organizador ni administrador", body = ErrorResponse),
+        (status = 404, description = "Evento no encontrado", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/eventos/{id}/publicar", wrap = "from_fn(require_organizer)")]
+pub async fn publish_event(
+    db: web::Data<dyn PurchaseRepository>,
+    evento_id: Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let event = db.publish_event(evento_id.into_inner()).await?;
+    tracing::info!(evento_id = event.id, "Evento publicado");
+    Ok(HttpResponse::Ok().json(event))
+}
+
+/// Endpoint para eliminar un evento sin compras pendientes ni pagadas
+#[utoipa::path(
+    tag = "eventos",
+    params(("id" = i32, Path, description = "ID del evento")),
+    responses(
+        (status = 204, description = "Evento eliminado"),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 403, description = "El usuario no es organizador ni administrador", body = ErrorResponse),
+        (status = 404, description = "Evento no encontrado", body = ErrorResponse),
+        (status = 409, description = "El evento tiene compras activas", body = ErrorResponse),
+        (status = 500, description = "Error interno", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/eventos/{id}", wrap = "from_fn(require_organizer)")]
+pub async fn delete_event(
+    db: web::Data<dyn PurchaseRepository>,
+    evento_id: Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let id = evento_id.into_inner();
+    db.delete_event(id).await?;
+    tracing::info!(evento_id = id, "Evento eliminado");
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, web, HttpResponse};
+use lapin::{Connection, ConnectionProperties};
+use serde_json::json;
+
+use crate::{
+    config::{Config, RabbitMqConfig},
+    health::{run_check, CheckStatus, ReadinessReport},
+    repository::purchase_repository::PurchaseRepository,
+};
+
+// Endpoint de vida: responde mientras el proceso pueda atender solicitudes (público)
+#[get("/health/live")]
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "up" }))
+}
+
+// Endpoint de disponibilidad: verifica MongoDB y RabbitMQ (público)
+#[get("/health/ready")]
+pub async fn ready(db: web::Data<dyn PurchaseRepository>, config: web::Data<Config>) -> HttpResponse {
+    let (mongodb, rabbitmq) = tokio::join!(
+        run_check("mongodb", async { db.ping().await.map_err(|e| e.to_string()) }),
+        run_check("rabbitmq", ping_rabbitmq(&config.rabbitmq)),
+    );
+
+    let report = ReadinessReport::new(BTreeMap::from([("mongodb", mongodb), ("rabbitmq", rabbitmq)]));
+    match report.status {
+        CheckStatus::Up => HttpResponse::Ok().json(report),
+        CheckStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
+
+// Abre y cierra una conexión con RabbitMQ
+async fn ping_rabbitmq(config: &RabbitMqConfig) -> Result<(), String> {
+    let connection = Connection::connect(&config.url(), ConnectionProperties::default())
+        .await
+        .map_err(|e| e.to_string())?;
+    connection.close(200, "health check").await.map_err(|e| e.to_string())
+}
@@ -1,2 +1,36 @@
+use actix_web::web;
+use actix_web_httpauth::middleware::HttpAuthentication;
 
-pub mod compra_api;
\ No newline at end of file
+use crate::{auth::jwt_validator, error::AppError};
+
+pub mod compra_api;
+pub mod evento_api;
+pub mod health_api;
+
+// Rutas de la API; el servidor las monta bajo /api (las pruebas de integración también)
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    // Configuración de autenticación Bearer con JWT
+    let auth = HttpAuthentication::bearer(jwt_validator);
+
+    cfg
+        // Un ID de evento que no es numérico responde como los demás IDs inválidos
+        .app_data(web::PathConfig::default().error_handler(|err, _| AppError::InvalidIDError(err.to_string()).into()))
+        // Endpoint público para obtener eventos
+        .service(compra_api::get_all_events)
+        // Endpoints protegidos que requieren autenticación
+        .service(
+            web::scope("")
+                .wrap(auth)
+                .service(compra_api::create_purchase)
+                .service(compra_api::get_user_purchases)
+                .service(compra_api::pay_purchase)
+                .service(compra_api::cancel_purchase)
+                .service(compra_api::refund_purchase)
+                .service(compra_api::delete_purchase)
+                // Administración de eventos: además del token, exigen rol de organizador o administrador
+                .service(evento_api::create_event)
+                .service(evento_api::update_event)
+                .service(evento_api::publish_event)
+                .service(evento_api::delete_event),
+        );
+}
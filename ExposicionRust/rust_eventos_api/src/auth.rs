@@ -0,0 +1,88 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error, HttpMessage,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+
+use crate::{api::compra_api::Claims, error::AppError};
+
+// Roles que pueden crear, modificar, publicar y eliminar eventos
+pub const ORGANIZER_ROLES: &[&str] = &["organizador", "admin"];
+// Roles del personal de soporte: pueden cancelar compras ajenas y procesar reembolsos
+pub const SUPPORT_ROLES: &[&str] = &["admin", "soporte"];
+
+// Llave con la que se validan los tokens JWT; se crea una sola vez al iniciar
+pub struct JwtKey(DecodingKey);
+
+impl JwtKey {
+    pub fn from_secret(secret: &str) -> Self {
+        JwtKey(DecodingKey::from_secret(secret.as_bytes()))
+    }
+}
+
+// Función de validación JWT para el middleware de autenticación
+pub async fn jwt_validator(req: ServiceRequest, credentials: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+    let token = credentials.token();
+    let Some(key) = req.app_data::<web::Data<JwtKey>>().cloned() else {
+        tracing::error!("La llave JWT no está registrada en la aplicación");
+        return Err((actix_web::error::ErrorInternalServerError("Configuración no disponible"), req));
+    };
+
+    // Configuración para validar el JWT
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = true;
+
+    match decode::<Claims>(token, &key.0, &validation) {
+        Ok(token_data) => {
+            let claims = token_data.claims;
+
+            // Extraer el usuario_id del sub (ahora es String)
+            let user_id = claims.sub.clone();
+            tracing::debug!(usuario_id = %user_id, "Token JWT válido");
+
+            // Añadir tanto el String como los Claims completos a las extensiones
+            req.extensions_mut().insert(user_id);
+            req.extensions_mut().insert(claims);
+            Ok(req)
+        },
+        Err(err) => {
+            tracing::warn!(error = %err, "Token JWT inválido");
+            Err((actix_web::error::ErrorUnauthorized("Token JWT inválido"), req))
+        }
+    }
+}
+
+// Deja pasar la solicitud solo si el rol del token es uno de `roles`; va dentro del alcance de `jwt_validator`
+pub async fn require_role(
+    roles: &[&str],
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(rol) = req.extensions().get::<Claims>().map(|claims| claims.rol.clone()) else {
+        return Err(AppError::Unauthorized("No se pudieron extraer los claims del token".to_string()).into());
+    };
+    if !roles.contains(&rol.as_str()) {
+        tracing::warn!(rol = %rol, path = %req.path(), "Rol sin permiso para la ruta");
+        return Err(AppError::Forbidden(format!("se requiere uno de los roles: {}", roles.join(", "))).into());
+    }
+    next.call(req).await
+}
+
+// Guardia de las rutas de administración de eventos (`wrap = "from_fn(require_organizer)"`)
+pub async fn require_organizer(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    require_role(ORGANIZER_ROLES, req, next).await
+}
+
+// Guardia de las rutas reservadas al personal de soporte
+pub async fn require_support(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    require_role(SUPPORT_ROLES, req, next).await
+}
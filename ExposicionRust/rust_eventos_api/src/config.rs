@@ -0,0 +1,387 @@
+use std::{
+    collections::HashSet,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{Serialize, Serializer};
+
+// Archivo TOML que se usa si existe y no se indicó otro con --config o CONFIG_FILE
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+// Texto que reemplaza a los secretos en los registros y en --print-config
+const REDACTED: &str = "****";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json,
+    Text,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(LogFormat::Json),
+            "text" => Ok(LogFormat::Text),
+            _ => Err("valores permitidos: json, text".to_string()),
+        }
+    }
+}
+
+// Valor secreto (llave JWT, contraseñas) que nunca aparece en los registros
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl FromStr for Secret {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.is_empty() {
+            Err("no puede estar vacía".to_string())
+        } else {
+            Ok(Secret(value.to_string()))
+        }
+    }
+}
+
+// URI de MongoDB; la contraseña se oculta al mostrarla o serializarla
+#[derive(Clone)]
+pub struct MongoUri(String);
+
+impl MongoUri {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    fn redacted(&self) -> String {
+        let Some((scheme, rest)) = self.0.split_once("://") else {
+            return self.0.clone();
+        };
+        match rest.split_once('@') {
+            Some((credentials, host)) => {
+                let user = credentials.split(':').next().unwrap_or_default();
+                format!("{}://{}:{}@{}", scheme, user, REDACTED, host)
+            }
+            None => self.0.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for MongoUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.redacted())
+    }
+}
+
+impl Serialize for MongoUri {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.redacted())
+    }
+}
+
+impl FromStr for MongoUri {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.starts_with("mongodb://") || value.starts_with("mongodb+srv://") {
+            Ok(MongoUri(value.to_string()))
+        } else {
+            Err("debe empezar con mongodb:// o mongodb+srv://".to_string())
+        }
+    }
+}
+
+// Configuración de RabbitMQ (la usan las notificaciones y la verificación de /health/ready)
+#[derive(Debug, Clone, Serialize)]
+pub struct RabbitMqConfig {
+    #[serde(rename = "rabbitmq_host")]
+    pub host: String,
+    #[serde(rename = "rabbitmq_port")]
+    pub port: u16,
+    #[serde(rename = "rabbitmq_user")]
+    pub user: String,
+    #[serde(rename = "rabbitmq_pass")]
+    pub pass: Secret,
+    #[serde(rename = "rabbitmq_queue")]
+    pub queue: String,
+}
+
+impl RabbitMqConfig {
+    // URL de conexión AMQP (incluye la contraseña, no se debe registrar)
+    pub fn url(&self) -> String {
+        format!("amqp://{}:{}@{}:{}/%2f", self.user, self.pass.expose(), self.host, self.port)
+    }
+}
+
+// Configuración del servicio, cargada una sola vez al iniciar.
+// Cada clave del archivo TOML es el nombre de la variable de entorno en minúsculas;
+// las variables de entorno (incluido .env) tienen prioridad sobre el archivo.
+#[derive(Debug, Clone, Serialize)]
+pub struct Config {
+    pub server_host: String,
+    pub server_port: u16,
+    pub mongo_uri: MongoUri,
+    pub mongo_db_name: String,
+    // Llave con la que se validan los tokens JWT (variable LlaveJWT)
+    #[serde(rename = "llavejwt")]
+    pub jwt_secret: Secret,
+    #[serde(flatten)]
+    pub rabbitmq: RabbitMqConfig,
+    // Tiempo que el apagado espera a las solicitudes en curso y a las notificaciones pendientes
+    pub shutdown_timeout_secs: u64,
+    pub log_format: LogFormat,
+    // Minutos que una compra sin pagar reserva sus entradas
+    pub seat_hold_minutes: u64,
+    // Cada cuánto se buscan y expiran las reservas vencidas
+    pub hold_sweep_interval_secs: u64,
+    // Avisar por RabbitMQ cuando una reserva expira
+    pub expiry_notifications: bool,
+}
+
+impl Config {
+    // Carga la configuración y reúne todos los valores faltantes o inválidos en un solo error
+    pub fn load(config_file: Option<&Path>) -> Result<Config, ConfigError> {
+        let mut sources = Sources::new(config_file)?;
+
+        let server_host = sources.or("SERVER_HOST", "127.0.0.1".to_string());
+        let server_port = sources.or("SERVER_PORT", 8081);
+        let mongo_uri = sources.required("MONGO_URI");
+        let mongo_db_name = sources.required("MONGO_DB_NAME");
+        let jwt_secret = sources.required("LlaveJWT");
+        // Valores de desarrollo local; en Docker se definen en docker-compose.yml
+        let rabbitmq_host = sources.or("RABBITMQ_HOST", "localhost".to_string());
+        let rabbitmq_port = sources.or("RABBITMQ_PORT", 5672);
+        let rabbitmq_user = sources.or("RABBITMQ_USER", "guest".to_string());
+        let rabbitmq_pass = sources.or("RABBITMQ_PASS", Secret("guest".to_string()));
+        let rabbitmq_queue = sources.or("RABBITMQ_QUEUE", "notifications_queue".to_string());
+        let shutdown_timeout_secs = sources.or("SHUTDOWN_TIMEOUT_SECS", 30);
+        let log_format = sources.or("LOG_FORMAT", LogFormat::Json);
+        let seat_hold_minutes = sources.or("SEAT_HOLD_MINUTES", 15);
+        let hold_sweep_interval_secs = sources.or("HOLD_SWEEP_INTERVAL_SECS", 30);
+        let expiry_notifications = sources.or("EXPIRY_NOTIFICATIONS", false);
+        if seat_hold_minutes == 0 {
+            sources.invalid("SEAT_HOLD_MINUTES", "debe ser mayor a 0");
+        }
+        if hold_sweep_interval_secs == 0 {
+            sources.invalid("HOLD_SWEEP_INTERVAL_SECS", "debe ser mayor a 0");
+        }
+
+        sources.finish()?;
+        // `finish` ya falló si faltaba alguno de los valores obligatorios
+        match (mongo_uri, mongo_db_name, jwt_secret) {
+            (Some(mongo_uri), Some(mongo_db_name), Some(jwt_secret)) => Ok(Config {
+                server_host,
+                server_port,
+                mongo_uri,
+                mongo_db_name,
+                jwt_secret,
+                rabbitmq: RabbitMqConfig {
+                    host: rabbitmq_host,
+                    port: rabbitmq_port,
+                    user: rabbitmq_user,
+                    pass: rabbitmq_pass,
+                    queue: rabbitmq_queue,
+                },
+                shutdown_timeout_secs,
+                log_format,
+                seat_hold_minutes,
+                hold_sweep_interval_secs,
+                expiry_notifications,
+            }),
+            _ => unreachable!("los valores obligatorios se validan en Sources::finish"),
+        }
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    pub fn seat_hold(&self) -> Duration {
+        Duration::from_secs(self.seat_hold_minutes * 60)
+    }
+
+    pub fn hold_sweep_interval(&self) -> Duration {
+        Duration::from_secs(self.hold_sweep_interval_secs)
+    }
+
+    // Configuración efectiva en TOML, sin secretos, para --print-config
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("La configuración siempre se puede serializar")
+    }
+}
+
+// Opciones de la línea de comandos: --print-config, --reconcile-capacity y --config <ruta>
+#[derive(Debug, Default)]
+pub struct CliOptions {
+    pub print_config: bool,
+    // Recalcular las entradas disponibles de los eventos a partir de las compras y terminar
+    pub reconcile_capacity: bool,
+    pub config_file: Option<PathBuf>,
+}
+
+impl CliOptions {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliOptions, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--print-config" => options.print_config = true,
+                "--reconcile-capacity" => options.reconcile_capacity = true,
+                "--config" => {
+                    let path = args.next().ok_or("--config requiere la ruta de un archivo")?;
+                    options.config_file = Some(PathBuf::from(path));
+                }
+                other => {
+                    return Err(format!(
+                        "Argumento desconocido: {} (opciones: --print-config, --reconcile-capacity, --config <ruta>)",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+// Todos los problemas encontrados al cargar la configuración
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Configuración inválida:")?;
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+// Valores de las variables de entorno y del archivo TOML, con los errores acumulados
+struct Sources {
+    file: toml::Table,
+    file_name: Option<String>,
+    used_keys: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Sources {
+    // Lee el archivo indicado (--config o CONFIG_FILE) o config.toml si existe
+    fn new(config_file: Option<&Path>) -> Result<Sources, ConfigError> {
+        let explicit = config_file
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from));
+        let path = match explicit {
+            Some(path) => Some(path),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+
+        let mut sources = Sources {
+            file: toml::Table::new(),
+            file_name: None,
+            used_keys: HashSet::new(),
+            errors: Vec::new(),
+        };
+        if let Some(path) = path {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(&path)
+                .map_err(|err| ConfigError(vec![format!("No se pudo leer {}: {}", name, err)]))?;
+            sources.file = content
+                .parse()
+                .map_err(|err| ConfigError(vec![format!("{} no es un TOML válido: {}", name, err)]))?;
+            sources.file_name = Some(name);
+        }
+        Ok(sources)
+    }
+
+    // Valor sin interpretar: primero la variable de entorno, luego la clave del archivo
+    fn raw(&mut self, name: &str) -> Option<String> {
+        let key = name.to_lowercase();
+        self.used_keys.insert(key.clone());
+        if let Ok(value) = env::var(name) {
+            return Some(value);
+        }
+        self.file.get(&key).map(|value| match value {
+            toml::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    fn optional<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let raw = self.raw(name)?;
+        match raw.trim().parse() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                // No se repite el valor: puede ser un secreto
+                self.invalid(name, &format!("valor inválido: {}", err));
+                None
+            }
+        }
+    }
+
+    fn or<T>(&mut self, name: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.optional(name).unwrap_or(default)
+    }
+
+    fn required<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let present = self.raw(name).is_some();
+        if !present {
+            self.invalid(name, "es obligatoria y no está definida");
+        }
+        self.optional(name)
+    }
+
+    fn invalid(&mut self, name: &str, problem: &str) {
+        self.errors.push(format!("{}: {}", name, problem));
+    }
+
+    // Las claves desconocidas del archivo suelen ser errores de escritura
+    fn finish(mut self) -> Result<(), ConfigError> {
+        if let Some(file_name) = &self.file_name {
+            let mut unknown: Vec<_> = self.file.keys().filter(|key| !self.used_keys.contains(*key)).collect();
+            unknown.sort();
+            for key in unknown {
+                self.errors.push(format!("{}: clave desconocida `{}`", file_name, key));
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(self.errors))
+        }
+    }
+}
@@ -2,6 +2,9 @@ use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use mongodb::bson;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::{model::PurchaseStatus, telemetry::RequestId};
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -20,20 +23,45 @@ pub enum AppError {
     #[error("Recurso no encontrado")]
     NotFoundError,
 
-    #[error("Compra ya pagada")]
-    AlreadyPaid,
+    #[error("La compra está {from} y no puede pasar a {to}")]
+    InvalidTransition { from: PurchaseStatus, to: PurchaseStatus },
+
+    #[error("No se puede eliminar una compra {0}; solo las pendientes, canceladas o expiradas")]
+    CannotDelete(PurchaseStatus),
+
+    #[error("Cantidad inválida: {0}")]
+    InvalidQuantity(String),
+
+    #[error("Evento inválido: {0}")]
+    InvalidEvent(String),
+
+    #[error("El evento tiene compras activas; cancélelas o reembólselas antes de eliminarlo")]
+    EventHasPurchases,
+
+    #[error("No quedan entradas suficientes para este evento")]
+    SoldOut,
+
+    #[error("La reserva de la compra venció; sus entradas volvieron a estar disponibles")]
+    HoldExpired,
 
     #[error("Error interno del servidor: {0}")]
     InternalError(String),
 
     #[error("Error de autenticación: {0}")]
     Unauthorized(String),
+
+    #[error("Permiso denegado: {0}")]
+    Forbidden(String),
 }
 
-#[derive(Serialize, Deserialize)]
-struct ErrorResponse {
+// Cuerpo JSON de todas las respuestas de error
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
     status: String,
     message: String,
+    // ID de la solicitud (X-Request-Id) para buscarla en los registros
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl ResponseError for AppError {
@@ -42,6 +70,7 @@ impl ResponseError for AppError {
         HttpResponse::build(status_code).json(ErrorResponse {
             status: status_code.to_string(),
             message: self.to_string(),
+            request_id: RequestId::current().map(|id| id.to_string()),
         })
     }
 
@@ -49,8 +78,15 @@ impl ResponseError for AppError {
         match self {
             AppError::NotFoundError => StatusCode::NOT_FOUND,
             AppError::InvalidIDError(_) => StatusCode::BAD_REQUEST,
-            AppError::AlreadyPaid => StatusCode::BAD_REQUEST,
+            AppError::InvalidTransition { .. } => StatusCode::CONFLICT,
+            AppError::CannotDelete(_) => StatusCode::CONFLICT,
+            AppError::InvalidQuantity(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidEvent(_) => StatusCode::BAD_REQUEST,
+            AppError::EventHasPurchases => StatusCode::CONFLICT,
+            AppError::SoldOut => StatusCode::CONFLICT,
+            AppError::HoldExpired => StatusCode::CONFLICT,
             AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
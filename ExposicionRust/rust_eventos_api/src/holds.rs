@@ -0,0 +1,75 @@
+use std::{sync::Arc, time::Duration};
+
+use serde_json::json;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    metrics,
+    model::Purchase,
+    notifications::{Notification, Notifier},
+    repository::purchase_repository::PurchaseRepository,
+    telemetry::RequestId,
+};
+
+// Tarea en segundo plano que cada `interval` expira las reservas vencidas y devuelve sus entradas;
+// con `notifier` también avisa de cada expiración por RabbitMQ
+pub fn start(repo: Arc<dyn PurchaseRepository>, notifier: Option<Notifier>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // Si un barrido tarda más que el intervalo, el siguiente espera en lugar de acumularse
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            sweep(repo.as_ref(), notifier.as_ref()).await;
+        }
+    })
+}
+
+// Un barrido: expira las reservas vencidas y devuelve cuántas expiraron
+pub async fn sweep(repo: &dyn PurchaseRepository, notifier: Option<&Notifier>) -> usize {
+    let expired = match repo.expire_holds().await {
+        Ok(expired) => expired,
+        Err(err) => {
+            // Se vuelve a intentar en el siguiente barrido
+            tracing::error!(error = %err, "Error al expirar las reservas vencidas");
+            return 0;
+        }
+    };
+
+    metrics::record_holds_expired(expired.len());
+    for purchase in &expired {
+        tracing::info!(
+            compra_id = %purchase_id(purchase),
+            evento_id = purchase.evento_id,
+            cantidad = purchase.cantidad,
+            "Reserva expirada; las entradas volvieron al evento"
+        );
+        if let Some(notifier) = notifier {
+            if let Err(e) = notifier.send(expiry_notification(purchase)) {
+                tracing::error!(error = %e, compra_id = %purchase_id(purchase), "Error al encolar la notificación de expiración");
+            }
+        }
+    }
+    expired.len()
+}
+
+fn purchase_id(purchase: &Purchase) -> String {
+    purchase.id.map(|id| id.to_hex()).unwrap_or_else(|| "unknown".to_string())
+}
+
+// Aviso para el usuario de que su compra expiró sin pagarse
+fn expiry_notification(purchase: &Purchase) -> Notification {
+    let compra_id = purchase_id(purchase);
+    let payload = json!({
+        "tipo": "reserva_expirada",
+        "usuario_id": purchase.usuario_id,
+        "compra_id": compra_id,
+        "cantidad": purchase.cantidad,
+        "evento_id": purchase.evento_id,
+        "fecha_expiracion": chrono::Utc::now().to_rfc3339(),
+    })
+    .to_string();
+
+    // Sin una solicitud de origen, cada aviso lleva su propio ID para seguirlo en los registros
+    Notification { compra_id, request_id: RequestId::generate(), payload }
+}
@@ -1,120 +1,180 @@
-mod api;
-mod error;
-mod model;
-mod repository;
-
 use actix_cors::Cors;
-use actix_web::{
-    middleware::Logger, 
-    web, 
-    App, 
-    HttpServer, 
-    dev::ServiceRequest, 
-    Error, 
-    HttpMessage
-};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
-use actix_web_httpauth::middleware::HttpAuthentication;
-use api::compra_api::{
-    get_all_events, create_purchase, get_user_purchases, pay_purchase, delete_purchase, Claims
-};
+use actix_web::{middleware::from_fn, web, App, HttpServer};
 use dotenv::dotenv;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use mongodb::{options::ClientOptions, Client};
-use repository::mongodb_repo::MongoRepo;
-use std::env;
-
-// Función de validación JWT para el middleware de autenticación
-async fn jwt_validator(req: ServiceRequest, credentials: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let token = credentials.token();
-    println!("Tocken (secret): {}", token);
-    let jwt_secret = env::var("LlaveJWT").expect("LlaveJWT no está establecida en .env");
-    println!("LlaveJWT (secret): {}", jwt_secret);
-
-    
-    // Configuración para validar el JWT
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.validate_exp = true;
-    
-    match decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &validation,
-    ) {
-        Ok(token_data) => {
-            let claims = token_data.claims;
-            
-            // Extraer el usuario_id del sub (ahora es String)
-            let user_id = claims.sub.clone();
-            
-            // Añadir tanto el String como los Claims completos a las extensiones
-            req.extensions_mut().insert(user_id);
-            req.extensions_mut().insert(claims);
-            Ok(req)
-        },
-        Err(err) => {
-            log::error!("Error al validar JWT: {:?}", err);
-            Err((actix_web::error::ErrorUnauthorized("Token JWT inválido").into(), req))
-        }
-    }
-}
+use rust_mongodb_crud::{
+    api::{self, health_api::{live, ready}},
+    auth::JwtKey,
+    config::{CliOptions, Config},
+    holds,
+    model::CapacityReconciliation,
+    metrics,
+    notifications,
+    openapi::ApiDoc,
+    repository::{mongodb_repo::MongoRepo, purchase_repository::PurchaseRepository},
+    shutdown,
+    telemetry,
+};
+use std::{env, process, sync::Arc};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
-    // Validar que las variables de entorno necesarias estén presentes
-    let mongo_uri = env::var("MONGO_URI").expect("MONGO_URI no está establecida en .env");
-    let mongo_db_name = env::var("MONGO_DB_NAME").expect("MONGO_DB_NAME no está establecida en .env");
-    let _jwt_secret = env::var("LlaveJWT").expect("LlaveJWT no está establecida en .env");
+    // Opciones de la línea de comandos: --print-config, --reconcile-capacity y --config <ruta>
+    let options = CliOptions::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+    // Cargar y validar toda la configuración una sola vez; si algo falta o es inválido se listan todos los problemas
+    let config = Config::load(options.config_file.as_deref()).unwrap_or_else(|err| {
+        eprint!("{}", err);
+        process::exit(1);
+    });
+    if options.print_config {
+        // Configuración efectiva sin secretos
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
 
-    let client_options = ClientOptions::parse(&mongo_uri)
+    // Registros estructurados (JSON salvo log_format = "text") con el X-Request-Id de cada solicitud
+    telemetry::init(config.log_format);
+    // Los secretos y la contraseña de MongoDB no se muestran
+    tracing::info!(?config, "Configuración cargada");
+
+    let client_options = ClientOptions::parse(config.mongo_uri.expose())
         .await
         .expect("Error al analizar la URI de MongoDB");
     let client = Client::with_options(client_options).expect("Error al crear el cliente de MongoDB");
-    let db = client.database(&mongo_db_name);
+    let mongo_repo = MongoRepo::new(client.clone(), &config.mongo_db_name).with_seat_hold(config.seat_hold());
 
-    let mongo_repo = MongoRepo::new(db);
-    let mongo_data = web::Data::new(mongo_repo);
+    // Las compras anteriores a `estado` usan `pagado`/`expirada`; se convierten una sola vez
+    match mongo_repo.migrate_purchase_status().await {
+        Ok(0) => {}
+        Ok(migrated) => tracing::info!(migrated, "Compras convertidas al campo estado"),
+        Err(err) => tracing::warn!(error = %err, "No se pudieron convertir las compras al campo estado"),
+    }
+    // Los eventos anteriores a `capacidad_total` la reciben antes de reconciliar o editar su capacidad
+    match mongo_repo.migrate_capacity_total().await {
+        Ok(0) => {}
+        Ok(migrated) => tracing::info!(migrated, "Eventos con capacidad_total calculada"),
+        Err(err) => tracing::warn!(error = %err, "No se pudo calcular la capacidad_total de los eventos"),
+    }
 
-    let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let server_port = env::var("SERVER_PORT")
-        .unwrap_or_else(|_| "8081".to_string())
-        .parse::<u16>()
-        .expect("SERVER_PORT debe ser un número");
+    if options.reconcile_capacity {
+        // Recalcula las entradas disponibles de cada evento y termina sin iniciar el servidor
+        let result = mongo_repo.reconcile_capacity().await;
+        client.shutdown().await;
+        match result {
+            Ok(report) => print_reconciliation(&report),
+            Err(err) => {
+                eprintln!("No se pudo reconciliar la capacidad de los eventos: {}", err);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // create_event depende del índice único de `id`; sin él dos eventos podrían compartirlo y el servidor no arranca
+    if let Err(err) = mongo_repo.ensure_indexes().await {
+        tracing::error!(error = %err, "No se pudo crear el índice único de los eventos");
+        client.shutdown().await;
+        process::exit(1);
+    }
+    let mongo_repo: Arc<dyn PurchaseRepository> = Arc::new(mongo_repo);
+    let mongo_data = web::Data::from(mongo_repo.clone());
+    // La llave JWT se prepara una sola vez para todas las solicitudes
+    let jwt_key = web::Data::new(JwtKey::from_secret(config.jwt_secret.expose()));
+
+    // Publicador de notificaciones en el runtime principal: sigue activo mientras se detienen los workers
+    let (notifier, publisher) = notifications::start(config.rabbitmq.clone());
+    // Barrido de las reservas vencidas; sus avisos usan la misma cola que los pagos
+    let expiry_notifier = config.expiry_notifications.then(|| notifier.clone());
+    let sweeper = holds::start(mongo_repo, expiry_notifier, config.hold_sweep_interval());
+    let notifier_data = web::Data::new(notifier);
+
+    let (server_host, server_port) = (config.server_host.clone(), config.server_port);
+    let shutdown_timeout = config.shutdown_timeout();
+    let config_data = web::Data::new(config);
 
     log::info!("Iniciando servidor en http://{}:{}", server_host, server_port);
 
-    HttpServer::new(move || {
+    // Registrar las métricas de Prometheus antes de atender solicitudes
+    metrics::init();
+
+    // Documento OpenAPI, generado una sola vez para todos los workers
+    let openapi = ApiDoc::openapi();
+
+    let server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header();
 
-        // Configuración de autenticación Bearer con JWT
-        let auth = HttpAuthentication::bearer(jwt_validator);
-
         App::new()
             .wrap(cors)
-            .wrap(Logger::default())
+            // Cuenta las solicitudes y mide su latencia por ruta
+            .wrap(from_fn(metrics::track_requests))
+            // Cuenta las solicitudes en curso para el resumen del apagado
+            .wrap(from_fn(shutdown::track_in_flight))
+            // Asigna el X-Request-Id y registra cada solicitud (debe ser el más externo)
+            .wrap(from_fn(telemetry::request_context))
             .app_data(mongo_data.clone())
-            .service(
-                web::scope("/api")
-                    // Endpoint público para obtener eventos
-                    .service(get_all_events)
-                    // Endpoints protegidos que requieren autenticación
-                    .service(
-                        web::scope("")
-                            .wrap(auth)
-                            .service(create_purchase)
-                            .service(get_user_purchases)
-                            .service(pay_purchase)
-                            .service(delete_purchase)
-                    ),
-            )
+            // Configuración compartida (RabbitMQ para /health/ready)
+            .app_data(config_data.clone())
+            // Llave con la que jwt_validator valida los tokens
+            .app_data(jwt_key.clone())
+            // Cola de notificaciones para RabbitMQ
+            .app_data(notifier_data.clone())
+            // Endpoints de salud para Docker y el proxy (públicos, fuera de /api)
+            .service(live)
+            .service(ready)
+            // Métricas para Prometheus (público, fuera de /api)
+            .service(metrics::metrics)
+            // Swagger UI en /api/docs y el documento en /api/openapi.json (públicos)
+            .service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", openapi.clone()))
+            // Endpoints de eventos (públicos) y de compras (protegidos con JWT)
+            .service(web::scope("/api").configure(api::routes))
     })
+    // Las señales se manejan en shutdown para esperar las solicitudes, enviar las notificaciones y cerrar MongoDB
+    .disable_signals()
     .bind((server_host, server_port))?
-    .run()
-    .await
-}
\ No newline at end of file
+    .run();
+
+    // Atiende solicitudes hasta recibir SIGTERM o Ctrl+C y espera las que estén en curso
+    let summary = shutdown::serve_until_signal(server, shutdown_timeout).await;
+    // Las reservas que no alcanzó a expirar se expiran en el siguiente arranque
+    sweeper.abort();
+
+    // Publicar las notificaciones que quedaron en la cola (incluidas las de las últimas solicitudes)
+    let notifications = publisher.flush(shutdown_timeout).await;
+    tracing::info!(
+        published = notifications.published,
+        failed = notifications.failed,
+        "Notificaciones enviadas durante la ejecución"
+    );
+
+    client.shutdown().await;
+    tracing::info!("Conexión con MongoDB cerrada");
+
+    summary?.log();
+    Ok(())
+}
+
+// Resumen de --reconcile-capacity, un evento por línea
+fn print_reconciliation(report: &[CapacityReconciliation]) {
+    for entry in report {
+        match entry.capacidad {
+            Some(capacidad) => println!(
+                "Evento {} ({}): {} vendidas, disponibles {} -> {}",
+                entry.evento_id, entry.nombre, entry.vendidas, entry.capacidad_anterior, capacidad
+            ),
+            None => println!(
+                "Evento {} ({}): sin capacidad_total, se mantienen {} disponibles",
+                entry.evento_id, entry.nombre, entry.capacidad_anterior
+            ),
+        }
+    }
+}
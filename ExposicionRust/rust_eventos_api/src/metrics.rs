@@ -0,0 +1,137 @@
+use std::{sync::LazyLock, time::Instant};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    middleware::Next,
+    Error, HttpResponse,
+};
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, Encoder, HistogramTimer,
+    HistogramVec, IntCounter, IntCounterVec, TextEncoder,
+};
+
+// Etiqueta de ruta para las solicitudes que no coinciden con ningún endpoint (evita una serie por URL)
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+// Solicitudes HTTP atendidas por método, patrón de ruta y código de estado
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "Solicitudes HTTP atendidas",
+        &["method", "route", "status"]
+    )
+    .expect("No se pudo registrar la métrica http_requests_total")
+});
+
+// Latencia de las solicitudes HTTP por método y patrón de ruta
+static HTTP_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Latencia de las solicitudes HTTP en segundos",
+        &["method", "route"]
+    )
+    .expect("No se pudo registrar la métrica http_request_duration_seconds")
+});
+
+// Duración de cada método del repositorio de MongoDB
+static MONGO_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "mongo_operation_duration_seconds",
+        "Duración de las operaciones del repositorio de MongoDB en segundos",
+        &["operation"],
+        vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    )
+    .expect("No se pudo registrar la métrica mongo_operation_duration_seconds")
+});
+
+// Publicaciones en RabbitMQ por resultado (`success` o `failure`)
+static RABBITMQ_PUBLISH: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "rabbitmq_publish_total",
+        "Notificaciones publicadas en RabbitMQ",
+        &["result"]
+    )
+    .expect("No se pudo registrar la métrica rabbitmq_publish_total")
+});
+
+// Entradas vendidas (se suma la cantidad de cada compra registrada)
+static TICKETS_PURCHASED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("tickets_purchased_total", "Entradas compradas")
+        .expect("No se pudo registrar la métrica tickets_purchased_total")
+});
+
+// Reservas de compras sin pagar que vencieron y devolvieron sus entradas
+static HOLDS_EXPIRED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("seat_holds_expired_total", "Reservas de entradas expiradas sin pagar")
+        .expect("No se pudo registrar la métrica seat_holds_expired_total")
+});
+
+// Registra las métricas al iniciar, para que `/metrics` las exponga desde el principio (en cero)
+pub fn init() {
+    LazyLock::force(&HTTP_REQUESTS);
+    LazyLock::force(&HTTP_DURATION);
+    LazyLock::force(&MONGO_DURATION);
+    LazyLock::force(&TICKETS_PURCHASED);
+    LazyLock::force(&HOLDS_EXPIRED);
+    for result in ["success", "failure"] {
+        RABBITMQ_PUBLISH.with_label_values(&[result]);
+    }
+}
+
+// Inicia el cronómetro de una operación de MongoDB; la duración se registra al soltarlo
+pub fn mongo_timer(operation: &str) -> HistogramTimer {
+    MONGO_DURATION.with_label_values(&[operation]).start_timer()
+}
+
+// Registra el resultado de una publicación en RabbitMQ
+pub fn record_rabbitmq_publish(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    RABBITMQ_PUBLISH.with_label_values(&[result]).inc();
+}
+
+// Suma las entradas de una compra registrada
+pub fn record_tickets_purchased(cantidad: i32) {
+    TICKETS_PURCHASED.inc_by(cantidad.max(0) as u64);
+}
+
+// Suma las reservas expiradas en un barrido
+pub fn record_holds_expired(count: usize) {
+    HOLDS_EXPIRED.inc_by(count as u64);
+}
+
+// Middleware que cuenta las solicitudes y mide su latencia por patrón de ruta (`/api/compras/{id}`)
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+    let started = Instant::now();
+
+    let result = next.call(req).await;
+
+    let status = match &result {
+        Ok(res) => res.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    HTTP_REQUESTS
+        .with_label_values(&[&method, &route, status.as_str()])
+        .inc();
+    HTTP_DURATION
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+// Endpoint con las métricas en el formato de texto de Prometheus
+#[get("/metrics")]
+pub async fn metrics() -> HttpResponse {
+    let mut buffer = Vec::new();
+    if let Err(err) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        log::error!("No se pudieron codificar las métricas: {}", err);
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok().content_type(prometheus::TEXT_FORMAT).body(buffer)
+}
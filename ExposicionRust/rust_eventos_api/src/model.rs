@@ -1,37 +1,159 @@
+use std::fmt;
+
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use mongodb::bson::DateTime;
+use utoipa::ToSchema;
+
+use crate::openapi::{DateTimeJson, ObjectIdJson};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Event {
-    pub id: i32,
+    pub id: i32, // Identificador del evento; Purchase.evento_id y todas las consultas usan este campo (no `_id`)
     pub nombre: String,
     pub fecha: String,
     pub lugar: String,
-    pub capacidad: i32,
+    pub capacidad: i32, // Entradas disponibles; cada compra las descuenta
+    // Entradas totales del evento; la reconciliación calcula con ellas las disponibles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacidad_total: Option<i32>,
     pub precio: String,
+    // Solo los eventos publicados se listan y se pueden comprar; los creados por la API empiezan sin publicar.
+    // Los eventos anteriores a este campo se consideran publicados
+    #[serde(default = "published_by_default")]
+    pub publicado: bool,
+    #[schema(value_type = DateTimeJson)]
     pub created_at: DateTime,  // Cambio de String a DateTime
+    #[schema(value_type = DateTimeJson)]
     pub updated_at: DateTime,  // Cambio de String a DateTime
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn published_by_default() -> bool {
+    true
+}
+
+// Datos de un evento que envía el organizador al crearlo o actualizarlo
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EventDto {
+    pub nombre: String,
+    pub fecha: String, // RFC 3339, por ejemplo "2025-12-15T20:00:00Z"
+    pub lugar: String,
+    pub capacidad: i32, // Entradas totales (capacidad_total)
+    pub precio: String, // Decimal con hasta dos decimales, por ejemplo "75.00"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Purchase {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
     pub id: Option<ObjectId>,
     pub usuario_id: String,
     pub evento_id: i32, // Cambiado a i32
     pub cantidad: i32,
-    pub pagado: bool,
+    pub estado: PurchaseStatus,
     pub fecha_compra: String,
+    // Hasta cuándo se reservan las entradas; si no se paga antes, la compra expira
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub reservada_hasta: Option<DateTime>,
+    // Momento de cada cambio de estado (solo los que ocurrieron)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub pagada_en: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub cancelada_en: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub reembolsada_en: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub expirada_en: Option<DateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Purchase {
+    // Aplica un cambio de estado ya validado y registra cuándo ocurrió
+    pub fn record_transition(&mut self, estado: PurchaseStatus, at: DateTime) {
+        self.estado = estado;
+        match estado {
+            PurchaseStatus::Pendiente => {}
+            PurchaseStatus::Pagada => self.pagada_en = Some(at),
+            PurchaseStatus::Cancelada => self.cancelada_en = Some(at),
+            PurchaseStatus::Reembolsada => self.reembolsada_en = Some(at),
+            PurchaseStatus::Expirada => self.expirada_en = Some(at),
+        }
+    }
+}
+
+// Estado de una compra. Transiciones permitidas:
+//   pendiente -> pagada | cancelada | expirada
+//   pagada    -> reembolsada
+// cancelada, reembolsada y expirada son finales
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PurchaseStatus {
+    Pendiente,
+    Pagada,
+    Cancelada,
+    Reembolsada,
+    Expirada,
+}
+
+impl PurchaseStatus {
+    pub fn can_transition_to(self, next: PurchaseStatus) -> bool {
+        use PurchaseStatus::*;
+        matches!(
+            (self, next),
+            (Pendiente, Pagada) | (Pendiente, Cancelada) | (Pendiente, Expirada) | (Pagada, Reembolsada)
+        )
+    }
+
+    // Las compras pendientes y pagadas ocupan entradas del evento; las demás ya las devolvieron
+    pub fn holds_seats(self) -> bool {
+        matches!(self, PurchaseStatus::Pendiente | PurchaseStatus::Pagada)
+    }
+
+    // Nombre del campo con el momento en que la compra llegó a este estado
+    pub fn timestamp_field(self) -> Option<&'static str> {
+        match self {
+            PurchaseStatus::Pendiente => None,
+            PurchaseStatus::Pagada => Some("pagada_en"),
+            PurchaseStatus::Cancelada => Some("cancelada_en"),
+            PurchaseStatus::Reembolsada => Some("reembolsada_en"),
+            PurchaseStatus::Expirada => Some("expirada_en"),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PurchaseStatus::Pendiente => "pendiente",
+            PurchaseStatus::Pagada => "pagada",
+            PurchaseStatus::Cancelada => "cancelada",
+            PurchaseStatus::Reembolsada => "reembolsada",
+            PurchaseStatus::Expirada => "expirada",
+        }
+    }
+}
+
+impl fmt::Display for PurchaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreatePurchaseDto {
     pub evento_id: i32, // Cambiado a i32
     pub cantidad: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdatePurchaseDto {
-    pub pagado: Option<bool>,
-}
\ No newline at end of file
+// Resultado de recalcular las entradas disponibles de un evento a partir de sus compras
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapacityReconciliation {
+    pub evento_id: i32,
+    pub nombre: String,
+    pub vendidas: i32,
+    pub capacidad_anterior: i32,
+    // None si el evento no tiene capacidad_total y no se pudo recalcular
+    pub capacidad: Option<i32>,
+}
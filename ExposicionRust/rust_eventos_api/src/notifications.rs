@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use lapin::{
+    options::{BasicPublishOptions, ConfirmSelectOptions, QueueDeclareOptions},
+    types::{AMQPValue, FieldTable, ShortString},
+    BasicProperties, Channel, Connection, ConnectionProperties,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{config::RabbitMqConfig, error::AppError, metrics, telemetry::RequestId};
+
+// Notificación lista para publicarse en la cola de RabbitMQ
+pub struct Notification {
+    pub compra_id: String,
+    pub request_id: RequestId,
+    pub payload: String,
+}
+
+// Los handlers solo encolan; un único publicador en segundo plano envía las notificaciones
+#[derive(Clone)]
+pub struct Notifier {
+    tx: mpsc::UnboundedSender<Notification>,
+}
+
+impl Notifier {
+    // Cola sin publicador: quien tenga el receptor decide qué hacer con las notificaciones
+    pub fn channel() -> (Notifier, mpsc::UnboundedReceiver<Notification>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Notifier { tx }, rx)
+    }
+
+    pub fn send(&self, notification: Notification) -> Result<(), AppError> {
+        self.tx
+            .send(notification)
+            .map_err(|_| AppError::InternalError("El publicador de notificaciones está detenido".into()))
+    }
+}
+
+// Tarea que publica las notificaciones encoladas con una sola conexión a RabbitMQ
+pub struct Publisher {
+    close_tx: oneshot::Sender<()>,
+    task: JoinHandle<PublishSummary>,
+}
+
+#[derive(Debug, Default)]
+pub struct PublishSummary {
+    pub published: u64,
+    pub failed: u64,
+}
+
+// Inicia el publicador; debe llamarse desde el runtime principal para que siga vivo
+// mientras los workers HTTP se detienen
+pub fn start(config: RabbitMqConfig) -> (Notifier, Publisher) {
+    let (notifier, rx) = Notifier::channel();
+    let (close_tx, close_rx) = oneshot::channel();
+    let task = tokio::spawn(run(config, rx, close_rx));
+    (notifier, Publisher { close_tx, task })
+}
+
+impl Publisher {
+    // Deja de aceptar notificaciones, publica las pendientes (como máximo `timeout`) y cierra la conexión
+    pub async fn flush(self, timeout: Duration) -> PublishSummary {
+        let _ = self.close_tx.send(());
+        let abort = self.task.abort_handle();
+        match tokio::time::timeout(timeout, self.task).await {
+            Ok(Ok(summary)) => summary,
+            Ok(Err(err)) => {
+                tracing::error!(error = %err, "El publicador de notificaciones terminó con un error");
+                PublishSummary::default()
+            }
+            Err(_) => {
+                tracing::error!("Se venció el plazo para enviar las notificaciones pendientes");
+                abort.abort();
+                PublishSummary::default()
+            }
+        }
+    }
+}
+
+async fn run(
+    config: RabbitMqConfig,
+    mut rx: mpsc::UnboundedReceiver<Notification>,
+    mut close_rx: oneshot::Receiver<()>,
+) -> PublishSummary {
+    let mut summary = PublishSummary::default();
+    let mut connection: Option<(Connection, Channel)> = None;
+    let mut closing = false;
+
+    loop {
+        let notification = tokio::select! {
+            notification = rx.recv() => notification,
+            _ = &mut close_rx, if !closing => {
+                // Se rechazan las nuevas y recv() entrega las que ya estaban en la cola
+                rx.close();
+                closing = true;
+                continue;
+            }
+        };
+        let Some(notification) = notification else { break };
+
+        // Si falla (por ejemplo, RabbitMQ se reinició) se reintenta una vez con una conexión nueva
+        let mut result = publish(&config, &mut connection, &notification).await;
+        if result.is_err() {
+            connection = None;
+            result = publish(&config, &mut connection, &notification).await;
+        }
+
+        metrics::record_rabbitmq_publish(result.is_ok());
+        match result {
+            Ok(()) => {
+                summary.published += 1;
+                tracing::info!(
+                    compra_id = %notification.compra_id,
+                    request_id = %notification.request_id,
+                    "Notificación enviada a RabbitMQ"
+                );
+            }
+            Err(e) => {
+                summary.failed += 1;
+                connection = None;
+                tracing::error!(
+                    compra_id = %notification.compra_id,
+                    request_id = %notification.request_id,
+                    error = %e,
+                    "Error al enviar notificación a RabbitMQ"
+                );
+            }
+        }
+    }
+
+    if let Some((connection, _)) = connection {
+        if let Err(e) = connection.close(200, "apagado").await {
+            tracing::warn!(error = %e, "Error al cerrar la conexión con RabbitMQ");
+        }
+    }
+    summary
+}
+
+// Abre la conexión y el canal con confirmaciones del broker, y declara la cola como durable
+async fn connect(config: &RabbitMqConfig) -> Result<(Connection, Channel), AppError> {
+    tracing::info!(host = %config.host, port = config.port, "Conectando a RabbitMQ");
+
+    let connection = Connection::connect(&config.url(), ConnectionProperties::default())
+        .await
+        .map_err(|e| {
+            // Posibles causas: RabbitMQ no está ejecutándose, el puerto no está disponible o un firewall bloquea la conexión
+            tracing::error!(host = %config.host, port = config.port, error = %e, "Error al conectar con RabbitMQ");
+            AppError::InternalError("Error al conectar con RabbitMQ".into())
+        })?;
+
+    let channel = connection.create_channel().await.map_err(|e| {
+        tracing::error!(error = %e, "Error al crear el canal RabbitMQ");
+        AppError::InternalError("Error al crear el canal RabbitMQ".into())
+    })?;
+
+    // Con confirmaciones, una publicación termina solo cuando el broker la aceptó
+    channel
+        .confirm_select(ConfirmSelectOptions::default())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Error al activar las confirmaciones");
+            AppError::InternalError("Error al activar las confirmaciones".into())
+        })?;
+
+    let queue_options = QueueDeclareOptions {
+        durable: true,
+        ..Default::default()
+    };
+    channel
+        .queue_declare(&config.queue, queue_options, FieldTable::default())
+        .await
+        .map_err(|e| {
+            tracing::error!(cola = %config.queue, error = %e, "Error al declarar la cola");
+            AppError::InternalError("Error al declarar la cola".into())
+        })?;
+
+    Ok((connection, channel))
+}
+
+async fn publish(
+    config: &RabbitMqConfig,
+    connection: &mut Option<(Connection, Channel)>,
+    notification: &Notification,
+) -> Result<(), AppError> {
+    // La conexión se reutiliza entre publicaciones y se vuelve a abrir si se cerró
+    if !matches!(connection, Some((_, channel)) if channel.status().connected()) {
+        *connection = Some(connect(config).await?);
+    }
+    let Some((_, channel)) = connection.as_ref() else {
+        unreachable!("la conexión se acaba de abrir");
+    };
+
+    // El X-Request-Id viaja como encabezado del mensaje para correlacionar la notificación con la compra
+    let mut headers = FieldTable::default();
+    headers.insert(
+        ShortString::from("x-request-id"),
+        AMQPValue::LongString(notification.request_id.as_str().into()),
+    );
+    let properties = BasicProperties::default()
+        .with_content_type(ShortString::from("application/json"))
+        .with_headers(headers);
+
+    let confirmation = channel
+        .basic_publish(
+            "",
+            &config.queue,
+            BasicPublishOptions::default(),
+            notification.payload.as_bytes(),
+            properties,
+        )
+        .await
+        .map_err(|e| AppError::InternalError(format!("Error al publicar en RabbitMQ: {}", e)))?
+        .await
+        .map_err(|e| AppError::InternalError(format!("Publicación no confirmada: {}", e)))?;
+
+    if confirmation.is_nack() {
+        return Err(AppError::InternalError("RabbitMQ rechazó la publicación".into()));
+    }
+    Ok(())
+}
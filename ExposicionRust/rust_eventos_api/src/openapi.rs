@@ -0,0 +1,74 @@
+use serde::Serialize;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+
+use crate::{
+    api::{compra_api, evento_api},
+    error::ErrorResponse,
+};
+
+// Documento OpenAPI generado a partir de los endpoints y los modelos
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "API de eventos", description = "Eventos disponibles y compra de entradas."),
+    servers((url = "/api")),
+    paths(
+        compra_api::get_all_events,
+        compra_api::create_purchase,
+        compra_api::get_user_purchases,
+        compra_api::pay_purchase,
+        compra_api::cancel_purchase,
+        compra_api::refund_purchase,
+        compra_api::delete_purchase,
+        evento_api::create_event,
+        evento_api::update_event,
+        evento_api::publish_event,
+        evento_api::delete_event,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "eventos", description = "Consulta pública de eventos publicados y su administración por organizadores (JWT)"),
+        (name = "compras", description = "Compras de entradas del usuario autenticado (JWT)"),
+    )
+)]
+pub struct ApiDoc;
+
+// Registra el esquema `bearer_auth` que usan las rutas protegidas por `jwt_validator`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+// Forma JSON de un ObjectId de MongoDB (`{"$oid": "..."}`)
+#[derive(Serialize, ToSchema)]
+#[schema(as = ObjectId)]
+pub struct ObjectIdJson {
+    #[serde(rename = "$oid")]
+    pub oid: String,
+}
+
+// Forma JSON de una fecha de MongoDB (`{"$date": {"$numberLong": "..."}}`)
+#[derive(Serialize, ToSchema)]
+#[schema(as = DateTime)]
+pub struct DateTimeJson {
+    #[serde(rename = "$date")]
+    pub date: NumberLongJson,
+}
+
+// Milisegundos desde la época Unix, como texto
+#[derive(Serialize, ToSchema)]
+#[schema(as = NumberLong)]
+pub struct NumberLongJson {
+    #[serde(rename = "$numberLong")]
+    pub number_long: String,
+}
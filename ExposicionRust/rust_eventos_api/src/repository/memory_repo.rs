@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    model::{CapacityReconciliation, CreatePurchaseDto, Event, EventDto, Purchase, PurchaseStatus},
+    repository::purchase_repository::{
+        capacity_change, check_deletable, check_transition, hold_deadline, hold_expired, new_event, reconcile_event,
+        PurchaseRepository, DEFAULT_SEAT_HOLD,
+    },
+};
+
+// Repositorio en memoria para las pruebas de integración (sin MongoDB)
+// Los bloqueos se toman siempre en el orden eventos, compras para evitar interbloqueos
+pub struct MemoryRepo {
+    events: RwLock<Vec<Event>>,
+    // Los ObjectId crecen con el tiempo, así que el BTreeMap conserva el orden de inserción
+    purchases: RwLock<BTreeMap<ObjectId, Purchase>>,
+    seat_hold: Duration,
+}
+
+impl Default for MemoryRepo {
+    fn default() -> Self {
+        MemoryRepo {
+            events: RwLock::default(),
+            purchases: RwLock::default(),
+            seat_hold: DEFAULT_SEAT_HOLD,
+        }
+    }
+}
+
+impl MemoryRepo {
+    pub fn new() -> Self {
+        MemoryRepo::default()
+    }
+
+    // Carga los eventos iniciales (en MongoDB los inserta init-mongo.js)
+    pub fn with_events(events: Vec<Event>) -> Self {
+        MemoryRepo {
+            events: RwLock::new(events),
+            ..Default::default()
+        }
+    }
+
+    // Tiempo que una compra sin pagar reserva sus entradas
+    pub fn with_seat_hold(mut self, seat_hold: Duration) -> Self {
+        self.seat_hold = seat_hold;
+        self
+    }
+}
+
+// Un bloqueo envenenado solo ocurre si otra solicitud falló a la mitad
+fn poisoned<T>(_: T) -> AppError {
+    AppError::InternalError("El repositorio en memoria quedó en un estado inconsistente".to_string())
+}
+
+// Devuelve al evento las entradas de la compra; falla si el evento no existe
+fn release_seats(events: &mut [Event], purchase: &Purchase) -> Result<(), AppError> {
+    let event = events
+        .iter_mut()
+        .find(|event| event.id == purchase.evento_id)
+        .ok_or_else(|| {
+            AppError::InternalError(format!(
+                "La compra {} pertenece al evento {}, que no existe",
+                purchase.id.map(|id| id.to_hex()).unwrap_or_default(),
+                purchase.evento_id
+            ))
+        })?;
+    event.capacidad += purchase.cantidad;
+    Ok(())
+}
+
+#[async_trait]
+impl PurchaseRepository for MemoryRepo {
+    async fn ping(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn get_all_events(&self) -> Result<Vec<Event>, AppError> {
+        let events = self.events.read().map_err(poisoned)?;
+        Ok(events.iter().filter(|event| event.publicado).cloned().collect())
+    }
+
+    async fn create_event(&self, dto: EventDto) -> Result<Event, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let id = events.iter().map(|event| event.id).max().unwrap_or(0) + 1;
+        let event = new_event(id, dto);
+        events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn update_event(&self, id: i32, dto: EventDto) -> Result<Event, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let event = events.iter_mut().find(|event| event.id == id).ok_or(AppError::NotFoundError)?;
+
+        let delta = capacity_change(event, dto.capacidad)?;
+        event.nombre = dto.nombre;
+        event.fecha = dto.fecha;
+        event.lugar = dto.lugar;
+        event.precio = dto.precio;
+        event.capacidad += delta;
+        event.capacidad_total = Some(dto.capacidad);
+        event.updated_at = DateTime::now();
+        Ok(event.clone())
+    }
+
+    async fn publish_event(&self, id: i32) -> Result<Event, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let event = events.iter_mut().find(|event| event.id == id).ok_or(AppError::NotFoundError)?;
+        event.publicado = true;
+        event.updated_at = DateTime::now();
+        Ok(event.clone())
+    }
+
+    async fn delete_event(&self, id: i32) -> Result<(), AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let purchases = self.purchases.read().map_err(poisoned)?;
+        let index = events.iter().position(|event| event.id == id).ok_or(AppError::NotFoundError)?;
+
+        let active = purchases
+            .values()
+            .any(|purchase| purchase.evento_id == id && purchase.estado.holds_seats());
+        if active {
+            return Err(AppError::EventHasPurchases);
+        }
+        events.remove(index);
+        Ok(())
+    }
+
+    async fn get_purchases_by_user(&self, usuario_id: String) -> Result<Vec<Purchase>, AppError> {
+        let purchases = self.purchases.read().map_err(poisoned)?;
+        Ok(purchases
+            .values()
+            .filter(|purchase| purchase.usuario_id == usuario_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_purchase(&self, usuario_id: String, dto: CreatePurchaseDto) -> Result<Purchase, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let mut purchases = self.purchases.write().map_err(poisoned)?;
+
+        // Con el bloqueo de los eventos tomado, verificar y descontar es atómico como en MongoDB
+        let event = events
+            .iter_mut()
+            .find(|event| event.id == dto.evento_id && event.publicado)
+            .ok_or(AppError::NotFoundError)?;
+        if event.capacidad < dto.cantidad {
+            return Err(AppError::SoldOut);
+        }
+        event.capacidad -= dto.cantidad;
+
+        let id = ObjectId::new();
+        let purchase = Purchase {
+            id: Some(id),
+            usuario_id,
+            evento_id: dto.evento_id,
+            cantidad: dto.cantidad,
+            estado: PurchaseStatus::Pendiente,
+            fecha_compra: chrono::Utc::now().to_rfc3339(),
+            reservada_hasta: Some(hold_deadline(self.seat_hold)),
+            pagada_en: None,
+            cancelada_en: None,
+            reembolsada_en: None,
+            expirada_en: None,
+        };
+        purchases.insert(id, purchase.clone());
+        Ok(purchase)
+    }
+
+    async fn get_purchase(&self, id: ObjectId) -> Result<Purchase, AppError> {
+        let purchases = self.purchases.read().map_err(poisoned)?;
+        purchases.get(&id).cloned().ok_or(AppError::NotFoundError)
+    }
+
+    async fn transition_purchase(&self, id: ObjectId, estado: PurchaseStatus) -> Result<Purchase, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let mut purchases = self.purchases.write().map_err(poisoned)?;
+        let purchase = purchases.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        let now = DateTime::now();
+
+        check_transition(purchase, estado, now)?;
+        if purchase.estado.holds_seats() && !estado.holds_seats() {
+            release_seats(&mut events, purchase)?;
+        }
+        purchase.record_transition(estado, now);
+        Ok(purchase.clone())
+    }
+
+    async fn delete_purchase(&self, id: ObjectId) -> Result<(), AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let mut purchases = self.purchases.write().map_err(poisoned)?;
+        let purchase = purchases.get(&id).ok_or(AppError::NotFoundError)?;
+
+        check_deletable(purchase)?;
+        // Las compras canceladas o expiradas ya devolvieron sus entradas;
+        // sin el evento no se elimina nada, como en MongoDB
+        if purchase.estado.holds_seats() {
+            release_seats(&mut events, purchase)?;
+        }
+        purchases.remove(&id);
+        Ok(())
+    }
+
+    async fn reconcile_capacity(&self) -> Result<Vec<CapacityReconciliation>, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let purchases = self.purchases.read().map_err(poisoned)?;
+
+        let mut sold: HashMap<i32, i32> = HashMap::new();
+        for purchase in purchases.values().filter(|purchase| purchase.estado.holds_seats()) {
+            *sold.entry(purchase.evento_id).or_default() += purchase.cantidad;
+        }
+        let mut report = Vec::new();
+        for event in events.iter_mut() {
+            let entry = reconcile_event(event, sold.get(&event.id).copied().unwrap_or(0));
+            if let Some(capacidad) = entry.capacidad {
+                event.capacidad = capacidad;
+            }
+            report.push(entry);
+        }
+        Ok(report)
+    }
+
+    async fn expire_holds(&self) -> Result<Vec<Purchase>, AppError> {
+        let mut events = self.events.write().map_err(poisoned)?;
+        let mut purchases = self.purchases.write().map_err(poisoned)?;
+        let now = DateTime::now();
+
+        let mut expired = Vec::new();
+        for purchase in purchases.values_mut() {
+            if !hold_expired(purchase, now) {
+                continue;
+            }
+            // Una compra con problemas no detiene el resto, como en MongoDB
+            if let Err(err) = release_seats(&mut events, purchase) {
+                tracing::error!(error = %err, "No se pudo expirar la reserva");
+                continue;
+            }
+            purchase.record_transition(PurchaseStatus::Expirada, now);
+            expired.push(purchase.clone());
+        }
+        Ok(expired)
+    }
+}
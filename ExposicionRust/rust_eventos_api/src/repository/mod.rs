@@ -1 +1,3 @@
-pub mod mongodb_repo;
\ No newline at end of file
+pub mod memory_repo;
+pub mod mongodb_repo;
+pub mod purchase_repository;
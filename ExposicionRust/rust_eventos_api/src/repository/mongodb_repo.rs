@@ -1,30 +1,364 @@
+use std::{collections::HashMap, time::Duration};
+
 use crate::{
     error::AppError,
-    model::{Event, Purchase, CreatePurchaseDto, UpdatePurchaseDto},
+    metrics,
+    model::{CapacityReconciliation, CreatePurchaseDto, Event, EventDto, Purchase, PurchaseStatus},
+    repository::purchase_repository::{
+        capacity_change, check_deletable, check_transition, hold_deadline, new_event, reconcile_event,
+        PurchaseRepository, DEFAULT_SEAT_HOLD,
+    },
 };
-use futures::stream::TryStreamExt;
+use async_trait::async_trait;
+use futures::{future::BoxFuture, stream::TryStreamExt};
 use mongodb::{
-    bson::{doc, oid::ObjectId, Document},
-    Database,
+    bson::{self, doc, oid::ObjectId, DateTime, Document},
+    error::{ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    options::{FindOneAndUpdateOptions, FindOneOptions, IndexOptions, ReturnDocument},
+    Client, ClientSession, Database, IndexModel,
 };
+use serde::Deserialize;
 
 const EVENTS_COLLECTION: &str = "events";
 const PURCHASES_COLLECTION: &str = "purchases";
+// Intentos de una transacción que MongoDB aborta por un error transitorio (por ejemplo, un conflicto de escritura)
+const TRANSACTION_ATTEMPTS: u32 = 5;
+// Intentos de asignar el `id` de un evento nuevo si otro organizador tomó el mismo al mismo tiempo
+const EVENT_ID_ATTEMPTS: u32 = 5;
+// Código de error de MongoDB al violar un índice único
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+// Filtro de un evento por su identificador; Purchase.evento_id guarda este mismo `id`, nunca el `_id`
+fn event_filter(evento_id: i32) -> Document {
+    doc! {"id": evento_id}
+}
+
+// Filtro de un evento publicado (los anteriores al campo `publicado` no lo tienen y cuentan como publicados)
+fn published_event_filter(evento_id: i32) -> Document {
+    doc! {"id": evento_id, "publicado": {"$ne": false}}
+}
+
+// Indica si el error se debe a que otro documento ya usa el mismo valor de un índice único
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    let code = match &*err.kind {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => Some(write_error.code),
+        ErrorKind::Command(command) => Some(command.code),
+        _ => None,
+    };
+    code == Some(DUPLICATE_KEY_CODE)
+}
+
+// Entradas registradas en compras para un evento (resultado de la agregación de reconcile_capacity)
+#[derive(Deserialize)]
+struct SoldSeats {
+    #[serde(rename = "_id")]
+    evento_id: i32,
+    vendidas: i32,
+}
 
 #[derive(Clone)]
 pub struct MongoRepo {
+    // Necesario para abrir las sesiones de las transacciones
+    client: Client,
     db: Database,
+    seat_hold: Duration,
 }
 
 impl MongoRepo {
-    pub fn new(db: Database) -> Self {
-        MongoRepo { db }
+    pub fn new(client: Client, db_name: &str) -> Self {
+        let db = client.database(db_name);
+        MongoRepo {
+            client,
+            db,
+            seat_hold: DEFAULT_SEAT_HOLD,
+        }
+    }
+
+    // Tiempo que una compra sin pagar reserva sus entradas
+    pub fn with_seat_hold(mut self, seat_hold: Duration) -> Self {
+        self.seat_hold = seat_hold;
+        self
+    }
+
+    // Crea los índices que necesita el repositorio (se ejecuta al iniciar el servidor). Falla si no se puede
+    // crear el índice único de los eventos; los demás solo aceleran consultas y su fallo se registra
+    pub async fn ensure_indexes(&self) -> Result<(), AppError> {
+        // Índice único del identificador del evento: las compras y las consultas lo usan como clave
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let id_index = IndexModel::builder()
+            .keys(doc! {"id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        events_collection.create_index(id_index, None).await?;
+
+        // Índices para las compras de un usuario, para sumar las compras de un evento
+        // y para que el barrido encuentre las reservas vencidas
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let indexes = vec![
+            IndexModel::builder().keys(doc! {"usuario_id": 1}).build(),
+            IndexModel::builder().keys(doc! {"evento_id": 1}).build(),
+            IndexModel::builder().keys(doc! {"estado": 1, "reservada_hasta": 1}).build(),
+        ];
+        if let Err(err) = collection.create_indexes(indexes, None).await {
+            tracing::warn!(error = %err, "No se pudieron crear los índices de las compras");
+        }
+        Ok(())
+    }
+
+    // Pasa las compras guardadas con `pagado`/`expirada` (antes de existir `estado`) al nuevo campo;
+    // se ejecuta al iniciar el servidor y no hace nada si ya no quedan
+    pub async fn migrate_purchase_status(&self) -> Result<u64, AppError> {
+        let collection = self.db.collection::<Document>(PURCHASES_COLLECTION);
+        // El orden importa: una compra expirada también tiene `pagado: false`
+        let legacy = [
+            (doc! {"expirada": true}, PurchaseStatus::Expirada),
+            (doc! {"pagado": true}, PurchaseStatus::Pagada),
+            (doc! {}, PurchaseStatus::Pendiente),
+        ];
+        let mut migrated = 0;
+        for (mut filter, estado) in legacy {
+            filter.insert("estado", doc! {"$exists": false});
+            let update = doc! {
+                "$set": {"estado": estado.as_str()},
+                "$unset": {"pagado": "", "expirada": ""},
+            };
+            migrated += collection.update_many(filter, update, None).await?.modified_count;
+        }
+        Ok(migrated)
+    }
+
+    // Devuelve al evento las entradas de la compra; falla si el evento no existe (la transacción se deshace)
+    async fn release_seats_in(&self, session: &mut ClientSession, purchase: &Purchase) -> Result<(), AppError> {
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let update = doc! {"$inc": {"capacidad": purchase.cantidad}};
+        let update_result = events_collection
+            .update_one_with_session(event_filter(purchase.evento_id), update, None, session)
+            .await?;
+        if update_result.matched_count == 0 {
+            return Err(AppError::InternalError(format!(
+                "La compra {} pertenece al evento {}, que no existe",
+                purchase.id.map(|id| id.to_hex()).unwrap_or_default(),
+                purchase.evento_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn delete_purchase_in(&self, session: &mut ClientSession, id: ObjectId) -> Result<(), AppError> {
+        // 1. Obtener la compra para conocer su estado y cuántas entradas devolver
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let purchase = collection
+            .find_one_with_session(doc! {"_id": id}, None, session)
+            .await?
+            .ok_or(AppError::NotFoundError)?;
+
+        // 2. Las compras pagadas o reembolsadas no se eliminan
+        check_deletable(&purchase)?;
+
+        // 3. Eliminar la compra; si cambia de estado al mismo tiempo, la transacción falla por conflicto y se reintenta
+        let filter = doc! {"_id": id, "estado": purchase.estado.as_str()};
+        let delete_result = collection.delete_one_with_session(filter, None, session).await?;
+        if delete_result.deleted_count == 0 {
+            return Err(AppError::NotFoundError);
+        }
+
+        // 4. Devolver las entradas si todavía las ocupaba (las canceladas y expiradas ya las devolvieron)
+        if purchase.estado.holds_seats() {
+            self.release_seats_in(session, &purchase).await?;
+        }
+        Ok(())
+    }
+
+    // Entradas ocupadas por las compras pendientes y pagadas de cada evento
+    async fn sold_seats_in(&self, session: &mut ClientSession) -> Result<HashMap<i32, i32>, AppError> {
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let pipeline = vec![
+            doc! {"$match": {"estado": {"$in": ["pendiente", "pagada"]}}},
+            doc! {"$group": {"_id": "$evento_id", "vendidas": {"$sum": "$cantidad"}}},
+        ];
+        let mut cursor = collection.aggregate_with_session(pipeline, None, session).await?;
+        let mut sold = HashMap::new();
+        while let Some(document) = cursor.next(session).await.transpose()? {
+            let seats: SoldSeats = bson::from_document(document)?;
+            sold.insert(seats.evento_id, seats.vendidas);
+        }
+        Ok(sold)
+    }
+
+    // Los eventos anteriores a `capacidad_total` no la tienen; se calcula como las entradas disponibles más las
+    // ocupadas por sus compras. Se ejecuta al iniciar el servidor y no hace nada si todos los eventos la tienen
+    pub async fn migrate_capacity_total(&self) -> Result<u64, AppError> {
+        self.run_transaction(|repo, session| Box::pin(repo.migrate_capacity_total_in(session)))
+            .await
+    }
+
+    async fn migrate_capacity_total_in(&self, session: &mut ClientSession) -> Result<u64, AppError> {
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let missing = doc! {"capacidad_total": {"$exists": false}};
+        let mut cursor = events_collection.find_with_session(missing.clone(), None, session).await?;
+        let mut events = Vec::new();
+        while let Some(event) = cursor.next(session).await.transpose()? {
+            events.push(event);
+        }
+        if events.is_empty() {
+            return Ok(0);
+        }
+
+        // Una compra simultánea modifica el evento y hace fallar la transacción por conflicto
+        let sold = self.sold_seats_in(session).await?;
+        let mut migrated = 0;
+        for event in events {
+            let capacidad_total = event.capacidad + sold.get(&event.id).copied().unwrap_or(0);
+            let mut filter = event_filter(event.id);
+            filter.extend(missing.clone());
+            let update = doc! {"$set": {"capacidad_total": capacidad_total}};
+            migrated += events_collection
+                .update_one_with_session(filter, update, None, session)
+                .await?
+                .modified_count;
+        }
+        Ok(migrated)
+    }
+
+    async fn reconcile_capacity_in(&self, session: &mut ClientSession) -> Result<Vec<CapacityReconciliation>, AppError> {
+        // 1. Sumar las entradas de las compras que todavía las ocupan (pendientes y pagadas)
+        let mut sold = self.sold_seats_in(session).await?;
+
+        // 2. Recalcular las entradas disponibles de cada evento
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let mut cursor = events_collection.find_with_session(None, None, session).await?;
+        let mut events = Vec::new();
+        while let Some(event) = cursor.next(session).await.transpose()? {
+            events.push(event);
+        }
+
+        let mut report = Vec::new();
+        for event in events {
+            let entry = reconcile_event(&event, sold.remove(&event.id).unwrap_or(0));
+            if let Some(capacidad) = entry.capacidad.filter(|capacidad| *capacidad != event.capacidad) {
+                let update = doc! {"$set": {"capacidad": capacidad, "updated_at": DateTime::now()}};
+                events_collection
+                    .update_one_with_session(event_filter(event.id), update, None, session)
+                    .await?;
+            }
+            report.push(entry);
+        }
+        for (evento_id, vendidas) in sold {
+            tracing::warn!(evento_id, vendidas, "Hay compras de un evento que no existe");
+        }
+        Ok(report)
+    }
+
+    async fn delete_event_in(&self, session: &mut ClientSession, id: i32) -> Result<(), AppError> {
+        // 1. Un evento con compras que ocupan entradas no se elimina
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let filter = doc! {"evento_id": id, "estado": {"$in": ["pendiente", "pagada"]}};
+        if collection.count_documents_with_session(filter, None, session).await? > 0 {
+            return Err(AppError::EventHasPurchases);
+        }
+
+        // 2. Eliminar el evento; una compra simultánea lo modifica y hace fallar la transacción por conflicto
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let delete_result = events_collection
+            .delete_one_with_session(event_filter(id), None, session)
+            .await?;
+        if delete_result.deleted_count == 0 {
+            return Err(AppError::NotFoundError);
+        }
+        Ok(())
+    }
+
+    async fn transition_in(
+        &self,
+        session: &mut ClientSession,
+        id: ObjectId,
+        estado: PurchaseStatus,
+        now: DateTime,
+    ) -> Result<Purchase, AppError> {
+        // 1. Obtener la compra y validar el cambio con la tabla de transiciones
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let mut purchase = collection
+            .find_one_with_session(doc! {"_id": id}, None, session)
+            .await?
+            .ok_or(AppError::NotFoundError)?;
+        check_transition(&purchase, estado, now)?;
+
+        // 2. Cambiar el estado y registrar cuándo; un cambio simultáneo hace fallar la transacción por conflicto
+        let mut set = doc! {"estado": estado.as_str()};
+        if let Some(field) = estado.timestamp_field() {
+            set.insert(field, now);
+        }
+        let filter = doc! {"_id": id, "estado": purchase.estado.as_str()};
+        let update_result = collection
+            .update_one_with_session(filter, doc! {"$set": set}, None, session)
+            .await?;
+        if update_result.matched_count == 0 {
+            return Err(AppError::NotFoundError);
+        }
+
+        // 3. Devolver las entradas si la compra deja de ocuparlas (cancelada, reembolsada o expirada)
+        if purchase.estado.holds_seats() && !estado.holds_seats() {
+            self.release_seats_in(session, &purchase).await?;
+        }
+        purchase.record_transition(estado, now);
+        Ok(purchase)
+    }
+
+    // Ejecuta `operation` en una transacción y la reintenta completa ante errores transitorios
+    async fn run_transaction<T, F>(&self, operation: F) -> Result<T, AppError>
+    where
+        F: for<'a> Fn(&'a MongoRepo, &'a mut ClientSession) -> BoxFuture<'a, Result<T, AppError>>,
+    {
+        let mut session = self.client.start_session(None).await?;
+        let mut attempt = 1;
+        loop {
+            session.start_transaction(None).await?;
+            let result = operation(self, &mut session).await;
+            match finish_transaction(&mut session, result).await {
+                Err(err) if is_transient(&err) && attempt < TRANSACTION_ATTEMPTS => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+// Confirma la transacción si `result` es correcto o la aborta si no
+async fn finish_transaction<T>(session: &mut ClientSession, result: Result<T, AppError>) -> Result<T, AppError> {
+    let value = match result {
+        Ok(value) => value,
+        Err(err) => {
+            // MongoDB puede haberla abortado ya; el error original es el que importa
+            let _ = session.abort_transaction().await;
+            return Err(err);
+        }
+    };
+    loop {
+        match session.commit_transaction().await {
+            Ok(()) => return Ok(value),
+            // Confirmar otra vez es seguro: MongoDB no aplica la transacción dos veces
+            Err(err) if err.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+fn is_transient(err: &AppError) -> bool {
+    matches!(err, AppError::MongoError(err) if err.contains_label(TRANSIENT_TRANSACTION_ERROR))
+}
+
+#[async_trait]
+impl PurchaseRepository for MongoRepo {
+    // Verificar que MongoDB responde (usado por /health/ready)
+    async fn ping(&self) -> Result<(), AppError> {
+        self.db.run_command(doc! {"ping": 1}, None).await?;
+        Ok(())
     }
 
     // Obtener todos los eventos
-    pub async fn get_all_events(&self) -> Result<Vec<Event>, AppError> {
+    async fn get_all_events(&self) -> Result<Vec<Event>, AppError> {
+        let _timer = metrics::mongo_timer("get_all_events");
         let collection = self.db.collection::<Event>(EVENTS_COLLECTION);
-        let mut cursor = collection.find(None, None).await?;
+        let mut cursor = collection.find(doc! {"publicado": {"$ne": false}}, None).await?;
         let mut events = Vec::new();
         while let Some(event) = cursor.try_next().await? {
             events.push(event);
@@ -32,8 +366,75 @@ impl MongoRepo {
         Ok(events)
     }
 
+    // Crear un evento con el `id` siguiente al mayor; el índice único evita que dos eventos lo compartan
+    async fn create_event(&self, dto: EventDto) -> Result<Event, AppError> {
+        let _timer = metrics::mongo_timer("create_event");
+        let collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
+        let mut event = new_event(0, dto);
+
+        for _ in 0..EVENT_ID_ATTEMPTS {
+            let last = collection.find_one(None, options.clone()).await?;
+            event.id = last.map_or(1, |last| last.id + 1);
+            match collection.insert_one(&event, None).await {
+                Ok(_) => return Ok(event),
+                // Otro evento tomó el mismo `id`: se reintenta con el siguiente
+                Err(err) if is_duplicate_key(&err) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(AppError::InternalError("No se pudo asignar un ID al evento".to_string()))
+    }
+
+    // Actualizar un evento; las entradas disponibles cambian tanto como la capacidad total
+    async fn update_event(&self, id: i32, dto: EventDto) -> Result<Event, AppError> {
+        let _timer = metrics::mongo_timer("update_event");
+        let collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let event = collection.find_one(event_filter(id), None).await?.ok_or(AppError::NotFoundError)?;
+        let delta = capacity_change(&event, dto.capacidad)?;
+
+        // Si se vendieron entradas mientras tanto y ya no alcanzan, la condición falla en lugar de dejarlas negativas
+        let mut filter = event_filter(id);
+        filter.insert("capacidad", doc! {"$gte": -delta});
+        let update = doc! {
+            "$set": {
+                "nombre": dto.nombre,
+                "fecha": dto.fecha,
+                "lugar": dto.lugar,
+                "precio": dto.precio,
+                "updated_at": DateTime::now(),
+            },
+            "$inc": {"capacidad": delta, "capacidad_total": delta},
+        };
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        collection
+            .find_one_and_update(filter, update, options)
+            .await?
+            .ok_or_else(|| AppError::InvalidEvent("capacidad: no puede ser menor que las entradas ocupadas".to_string()))
+    }
+
+    // Publicar un evento
+    async fn publish_event(&self, id: i32) -> Result<Event, AppError> {
+        let _timer = metrics::mongo_timer("publish_event");
+        let collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+        let update = doc! {"$set": {"publicado": true, "updated_at": DateTime::now()}};
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        collection
+            .find_one_and_update(event_filter(id), update, options)
+            .await?
+            .ok_or(AppError::NotFoundError)
+    }
+
+    // Eliminar un evento sin compras activas en una sola transacción
+    async fn delete_event(&self, id: i32) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer("delete_event");
+        self.run_transaction(|repo, session| Box::pin(repo.delete_event_in(session, id)))
+            .await
+    }
+
     // Obtener todas las compras de un usuario (ahora usuario_id es String)
-    pub async fn get_purchases_by_user(&self, usuario_id: String) -> Result<Vec<Purchase>, AppError> {
+    async fn get_purchases_by_user(&self, usuario_id: String) -> Result<Vec<Purchase>, AppError> {
+        let _timer = metrics::mongo_timer("get_purchases_by_user");
         let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
         let filter = doc! {"usuario_id": usuario_id};
         let mut cursor = collection.find(filter, None).await?;
@@ -44,10 +445,23 @@ impl MongoRepo {
         Ok(purchases)
     }
 
-    // Crear una compra (ahora usuario_id es String)
-    pub async fn create_purchase(&self, usuario_id: String, dto: CreatePurchaseDto) -> Result<Purchase, AppError> {
-        // Ya no se valida la existencia del evento ni la capacidad, solo se registra el evento_id recibido
+    // Crear una compra descontando las entradas del evento (ahora usuario_id es String)
+    async fn create_purchase(&self, usuario_id: String, dto: CreatePurchaseDto) -> Result<Purchase, AppError> {
+        let _timer = metrics::mongo_timer("create_purchase");
+        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
+
+        // 1. Descontar las entradas en una sola operación: solo coincide si quedan suficientes,
+        // así dos compras simultáneas no pueden vender el mismo asiento
+        let mut filter = published_event_filter(dto.evento_id);
+        filter.insert("capacidad", doc! {"$gte": dto.cantidad});
+        let update = doc! {"$inc": {"capacidad": -dto.cantidad}};
+        if events_collection.find_one_and_update(filter, update, None).await?.is_none() {
+            // Sin coincidencia: el evento no existe o no le quedan entradas suficientes
+            let exists = events_collection.find_one(published_event_filter(dto.evento_id), None).await?.is_some();
+            return Err(if exists { AppError::SoldOut } else { AppError::NotFoundError });
+        }
 
+        // 2. Registrar la compra
         let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
 
         let purchase = Purchase {
@@ -55,11 +469,31 @@ impl MongoRepo {
             usuario_id,
             evento_id: dto.evento_id,
             cantidad: dto.cantidad,
-            pagado: false,
+            estado: PurchaseStatus::Pendiente,
             fecha_compra: chrono::Utc::now().to_rfc3339(),
+            reservada_hasta: Some(hold_deadline(self.seat_hold)),
+            pagada_en: None,
+            cancelada_en: None,
+            reembolsada_en: None,
+            expirada_en: None,
         };
 
-        let insert_result = collection.insert_one(purchase, None).await?;
+        let insert_result = match collection.insert_one(purchase, None).await {
+            Ok(result) => result,
+            Err(err) => {
+                // 3. Si no se pudo registrar, devolver las entradas descontadas
+                let update = doc! {"$inc": {"capacidad": dto.cantidad}};
+                if let Err(restore_err) = events_collection.update_one(event_filter(dto.evento_id), update, None).await {
+                    tracing::error!(
+                        evento_id = dto.evento_id,
+                        cantidad = dto.cantidad,
+                        error = %restore_err,
+                        "No se pudieron devolver las entradas de una compra fallida"
+                    );
+                }
+                return Err(err.into());
+            }
+        };
         let id = insert_result
             .inserted_id
             .as_object_id()
@@ -69,7 +503,8 @@ impl MongoRepo {
     }
 
     // Obtener una compra por ID
-    pub async fn get_purchase(&self, id: ObjectId) -> Result<Purchase, AppError> {
+    async fn get_purchase(&self, id: ObjectId) -> Result<Purchase, AppError> {
+        let _timer = metrics::mongo_timer("get_purchase");
         let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
         let filter = doc! {"_id": id};
         let purchase = collection
@@ -79,70 +514,51 @@ impl MongoRepo {
         Ok(purchase)
     }
 
-    // Actualizar una compra
-    pub async fn update_purchase(&self, id: ObjectId, dto: UpdatePurchaseDto) -> Result<Purchase, AppError> {
-        // Verificar que la compra existe
-        let purchase = self.get_purchase(id).await?;
-        
-        // Si la compra ya está pagada y se intenta marcar como pagada de nuevo
-        if purchase.pagado && dto.pagado.unwrap_or(false) {
-            return Err(AppError::AlreadyPaid);
-        }
-
-        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
-        let filter = doc! {"_id": id};
-
-        let mut update_doc = Document::new();
-        if let Some(pagado) = dto.pagado {
-            update_doc.insert("pagado", pagado);
-        }
-
-        if update_doc.is_empty() {
-            return self.get_purchase(id).await;
-        }
-
-        let update = doc! {"$set": update_doc};
-        let options = mongodb::options::FindOneAndUpdateOptions::builder()
-            .return_document(mongodb::options::ReturnDocument::After)
-            .build();
+    // Cambiar el estado de una compra (y devolver sus entradas si corresponde) en una sola transacción
+    async fn transition_purchase(&self, id: ObjectId, estado: PurchaseStatus) -> Result<Purchase, AppError> {
+        let _timer = metrics::mongo_timer("transition_purchase");
+        let now = DateTime::now();
+        self.run_transaction(|repo, session| Box::pin(repo.transition_in(session, id, estado, now)))
+            .await
+    }
 
-        let updated_purchase = collection
-            .find_one_and_update(filter, update, options)
-            .await?
-            .ok_or(AppError::NotFoundError)?;
+    // Eliminar una compra y devolver sus entradas al evento en una sola transacción
+    async fn delete_purchase(&self, id: ObjectId) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer("delete_purchase");
+        self.run_transaction(|repo, session| Box::pin(repo.delete_purchase_in(session, id)))
+            .await
+    }
 
-        Ok(updated_purchase)
+    // Recalcular las entradas disponibles de todos los eventos en una sola transacción
+    async fn reconcile_capacity(&self) -> Result<Vec<CapacityReconciliation>, AppError> {
+        let _timer = metrics::mongo_timer("reconcile_capacity");
+        self.run_transaction(|repo, session| Box::pin(repo.reconcile_capacity_in(session)))
+            .await
     }
 
-    // Eliminar una compra
-    pub async fn delete_purchase(&self, id: ObjectId) -> Result<(), AppError> {
-        // 1. Obtener la compra para saber cuántas entradas devolver
-        let purchase = self.get_purchase(id).await?;
-        
-        // 2. Si la compra ya está pagada, no se puede eliminar
-        if purchase.pagado {
-            return Err(AppError::AlreadyPaid);
-        }
+    // Expirar las reservas vencidas, cada una en su propia transacción
+    async fn expire_holds(&self) -> Result<Vec<Purchase>, AppError> {
+        let _timer = metrics::mongo_timer("expire_holds");
+        let now = DateTime::now();
+        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
+        let filter = doc! {"estado": "pendiente", "reservada_hasta": {"$lte": now}};
+        let overdue: Vec<Purchase> = collection.find(filter, None).await?.try_collect().await?;
 
-        // 3. Devolver las entradas al evento
-        let events_collection = self.db.collection::<Event>(EVENTS_COLLECTION);
-        let filter = doc! {"_id": purchase.evento_id};
-        let update = doc! {
-            "$inc": {
-                "capacidad": purchase.cantidad
+        let mut expired = Vec::new();
+        for id in overdue.into_iter().filter_map(|purchase| purchase.id) {
+            match self
+                .run_transaction(|repo, session| {
+                    Box::pin(repo.transition_in(session, id, PurchaseStatus::Expirada, now))
+                })
+                .await
+            {
+                Ok(purchase) => expired.push(purchase),
+                // Se pagó, se canceló o se eliminó mientras tanto
+                Err(AppError::InvalidTransition { .. } | AppError::NotFoundError) => {}
+                // Una compra con problemas no detiene el resto; se reintenta en el siguiente barrido
+                Err(err) => tracing::error!(compra_id = %id, error = %err, "No se pudo expirar la reserva"),
             }
-        };
-        events_collection.update_one(filter, update, None).await?;
-
-        // 4. Eliminar la compra
-        let collection = self.db.collection::<Purchase>(PURCHASES_COLLECTION);
-        let filter = doc! {"_id": id};
-        let delete_result = collection.delete_one(filter, None).await?;
-        
-        if delete_result.deleted_count == 0 {
-            return Err(AppError::NotFoundError);
         }
-        
-        Ok(())
+        Ok(expired)
     }
-}
\ No newline at end of file
+}
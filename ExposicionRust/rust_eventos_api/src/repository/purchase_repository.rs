@@ -0,0 +1,144 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    model::{CapacityReconciliation, CreatePurchaseDto, Event, EventDto, Purchase, PurchaseStatus},
+};
+
+// Tiempo que una compra sin pagar reserva sus entradas si no se configura otro (SEAT_HOLD_MINUTES)
+pub const DEFAULT_SEAT_HOLD: Duration = Duration::from_secs(15 * 60);
+
+// Contrato común para el almacenamiento de eventos y compras (MongoDB o memoria)
+#[async_trait]
+pub trait PurchaseRepository: Send + Sync {
+    // Verificar que el almacenamiento responde (usado por /health/ready)
+    async fn ping(&self) -> Result<(), AppError>;
+
+    // Obtener los eventos publicados
+    async fn get_all_events(&self) -> Result<Vec<Event>, AppError>;
+
+    // Crear un evento sin publicar con el siguiente `id` libre
+    async fn create_event(&self, dto: EventDto) -> Result<Event, AppError>;
+
+    // Reemplazar los datos de un evento; `dto.capacidad` es la nueva capacidad_total y no puede
+    // ser menor que las entradas ocupadas (InvalidEvent)
+    async fn update_event(&self, id: i32, dto: EventDto) -> Result<Event, AppError>;
+
+    // Publicar un evento para que se liste y se puedan comprar entradas
+    async fn publish_event(&self, id: i32) -> Result<Event, AppError>;
+
+    // Eliminar un evento; falla con EventHasPurchases si tiene compras pendientes o pagadas
+    async fn delete_event(&self, id: i32) -> Result<(), AppError>;
+
+    // Obtener todas las compras de un usuario
+    async fn get_purchases_by_user(&self, usuario_id: String) -> Result<Vec<Purchase>, AppError>;
+
+    // Crear una compra para el usuario autenticado descontando las entradas del evento;
+    // falla con NotFoundError si el evento no existe o no está publicado y con SoldOut si no quedan suficientes
+    async fn create_purchase(&self, usuario_id: String, dto: CreatePurchaseDto) -> Result<Purchase, AppError>;
+
+    // Obtener una compra por ID
+    async fn get_purchase(&self, id: ObjectId) -> Result<Purchase, AppError>;
+
+    // Cambiar el estado de una compra y devolver sus entradas al evento si deja de ocuparlas;
+    // falla con InvalidTransition si la tabla de PurchaseStatus no lo permite
+    // y con HoldExpired si se intenta pagar después de que venció la reserva
+    async fn transition_purchase(&self, id: ObjectId, estado: PurchaseStatus) -> Result<Purchase, AppError>;
+
+    // Eliminar una compra pendiente, cancelada o expirada (CannotDelete si no) y, si todavía
+    // ocupaba entradas, devolverlas al evento: las dos cosas o ninguna
+    async fn delete_purchase(&self, id: ObjectId) -> Result<(), AppError>;
+
+    // Recalcular las entradas disponibles de cada evento como capacidad_total menos las compras registradas;
+    // se ejecuta con el servidor detenido (--reconcile-capacity)
+    async fn reconcile_capacity(&self) -> Result<Vec<CapacityReconciliation>, AppError>;
+
+    // Pasar a expiradas las compras pendientes cuya reserva venció y devolver sus entradas;
+    // devuelve las compras expiradas
+    async fn expire_holds(&self) -> Result<Vec<Purchase>, AppError>;
+}
+
+// Fin de la reserva de una compra creada ahora
+pub(crate) fn hold_deadline(hold: Duration) -> DateTime {
+    DateTime::from_system_time(SystemTime::now() + hold)
+}
+
+// Compra pendiente cuya reserva venció (el barrido la expira)
+pub(crate) fn hold_expired(purchase: &Purchase, now: DateTime) -> bool {
+    purchase.estado == PurchaseStatus::Pendiente && purchase.reservada_hasta.is_some_and(|deadline| deadline <= now)
+}
+
+// Valida un cambio de estado con la tabla de PurchaseStatus; pagar además exige que la reserva siga vigente
+pub(crate) fn check_transition(purchase: &Purchase, estado: PurchaseStatus, now: DateTime) -> Result<(), AppError> {
+    if !purchase.estado.can_transition_to(estado) {
+        return Err(AppError::InvalidTransition { from: purchase.estado, to: estado });
+    }
+    if estado == PurchaseStatus::Pagada && hold_expired(purchase, now) {
+        return Err(AppError::HoldExpired);
+    }
+    Ok(())
+}
+
+// Solo se eliminan las compras que no tienen un pago asociado
+pub(crate) fn check_deletable(purchase: &Purchase) -> Result<(), AppError> {
+    match purchase.estado {
+        PurchaseStatus::Pendiente | PurchaseStatus::Cancelada | PurchaseStatus::Expirada => Ok(()),
+        estado => Err(AppError::CannotDelete(estado)),
+    }
+}
+
+// Entradas disponibles de un evento según sus compras; sin capacidad_total no se puede recalcular
+pub(crate) fn reconcile_event(event: &Event, vendidas: i32) -> CapacityReconciliation {
+    let capacidad = event.capacidad_total.map(|total| total - vendidas);
+    match capacidad {
+        None => tracing::warn!(evento_id = event.id, "El evento no tiene capacidad_total; no se recalcula"),
+        Some(capacidad) if capacidad < 0 => {
+            tracing::warn!(evento_id = event.id, capacidad, "El evento tiene más entradas vendidas que su capacidad_total")
+        }
+        Some(_) => {}
+    }
+    CapacityReconciliation {
+        evento_id: event.id,
+        nombre: event.nombre.clone(),
+        vendidas,
+        capacidad_anterior: event.capacidad,
+        capacidad,
+    }
+}
+
+// Evento nuevo sin publicar; todas sus entradas están disponibles
+pub(crate) fn new_event(id: i32, dto: EventDto) -> Event {
+    let now = DateTime::now();
+    Event {
+        id,
+        nombre: dto.nombre,
+        fecha: dto.fecha,
+        lugar: dto.lugar,
+        capacidad: dto.capacidad,
+        capacidad_total: Some(dto.capacidad),
+        precio: dto.precio,
+        publicado: false,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+// Diferencia que se suma a las entradas disponibles y totales al pasar a `capacidad_total` entradas
+pub(crate) fn capacity_change(event: &Event, capacidad_total: i32) -> Result<i32, AppError> {
+    let Some(anterior) = event.capacidad_total else {
+        return Err(AppError::InvalidEvent(
+            "capacidad: el evento no tiene capacidad_total registrada y no se puede cambiar".to_string(),
+        ));
+    };
+    let ocupadas = anterior - event.capacidad;
+    if capacidad_total < ocupadas {
+        return Err(AppError::InvalidEvent(format!(
+            "capacidad: no puede ser menor que las {} entradas ocupadas",
+            ocupadas
+        )));
+    }
+    Ok(capacidad_total - anterior)
+}
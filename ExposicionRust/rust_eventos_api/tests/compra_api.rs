@@ -0,0 +1,740 @@
+// Pruebas de integración de los endpoints de eventos y compras, con el repositorio en memoria (sin MongoDB)
+
+use std::{sync::Arc, time::Duration};
+
+use actix_http::Request;
+use actix_web::{
+    body::{self, MessageBody},
+    dev::{Service, ServiceResponse},
+    http::StatusCode,
+    middleware::from_fn,
+    test, web, App, Error,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::DateTime;
+use rust_mongodb_crud::{
+    api::{self, compra_api::Claims},
+    auth::JwtKey,
+    holds,
+    model::{CapacityReconciliation, CreatePurchaseDto, Event, PurchaseStatus},
+    notifications::{Notification, Notifier},
+    repository::{memory_repo::MemoryRepo, purchase_repository::PurchaseRepository},
+    telemetry,
+};
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+const JWT_SECRET: &str = "llave-de-prueba";
+// ID con formato válido que no corresponde a ninguna compra
+const MISSING_ID: &str = "65f1a2b3c4d5e6f708192a3b";
+
+fn event(id: i32, nombre: &str, capacidad: i32) -> Event {
+    Event {
+        id,
+        nombre: nombre.to_string(),
+        fecha: "2024-12-15T20:00:00Z".to_string(),
+        lugar: "Estadio Nacional".to_string(),
+        capacidad,
+        capacidad_total: Some(capacidad),
+        precio: "75.00".to_string(),
+        publicado: true,
+        created_at: DateTime::now(),
+        updated_at: DateTime::now(),
+    }
+}
+
+fn repo() -> MemoryRepo {
+    MemoryRepo::with_events(vec![event(1, "Concierto de Rock", 1000), event(2, "Festival de Jazz", 500)])
+}
+
+// Aplicación con las mismas rutas que el servidor; las notificaciones quedan en el receptor devuelto
+async fn app() -> (
+    impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    UnboundedReceiver<Notification>,
+) {
+    app_with(Arc::new(repo())).await
+}
+
+async fn app_with(
+    repo: Arc<dyn PurchaseRepository>,
+) -> (
+    impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    UnboundedReceiver<Notification>,
+) {
+    let (notifier, notifications) = Notifier::channel();
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(telemetry::request_context))
+            .app_data(web::Data::from(repo))
+            .app_data(web::Data::new(JwtKey::from_secret(JWT_SECRET)))
+            .app_data(web::Data::new(notifier))
+            .service(web::scope("/api").configure(api::routes)),
+    )
+    .await;
+    (app, notifications)
+}
+
+// Token firmado con `secret` para el usuario `sub`, vigente por `ttl` segundos (negativo: vencido)
+fn token_with(sub: &str, secret: &str, ttl: i64) -> String {
+    signed_token(sub, "cliente", secret, ttl)
+}
+
+fn signed_token(sub: &str, rol: &str, secret: &str, ttl: i64) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        name: format!("Usuario {}", sub),
+        email: format!("{}@example.com", sub),
+        rol: rol.to_string(),
+        sub: sub.to_string(),
+        iat: now as usize,
+        exp: (now + ttl) as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+}
+
+fn token(sub: &str) -> String {
+    token_with(sub, JWT_SECRET, 3600)
+}
+
+// Token del personal de soporte (puede cancelar compras ajenas y reembolsar)
+fn support_token(sub: &str) -> String {
+    signed_token(sub, "soporte", JWT_SECRET, 3600)
+}
+
+// Token de un organizador (puede crear, modificar, publicar y eliminar eventos)
+fn organizer_token(sub: &str) -> String {
+    signed_token(sub, "organizador", JWT_SECRET, 3600)
+}
+
+// Envía la solicitud y devuelve el código y el cuerpo JSON (Null si está vacío).
+// Los errores de los middlewares (autenticación) también se convierten en respuesta.
+async fn call(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    req: Request,
+) -> (StatusCode, Value) {
+    let (status, bytes) = match app.call(req).await {
+        Ok(resp) => (resp.status(), body::to_bytes(resp.into_body()).await.unwrap_or_default()),
+        Err(err) => {
+            let resp = err.error_response();
+            (resp.status(), body::to_bytes(resp.into_body()).await.unwrap_or_default())
+        }
+    };
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap_or(Value::Null) };
+    (status, body)
+}
+
+fn get(uri: &str, token: &str) -> Request {
+    test::TestRequest::get().uri(uri).insert_header(("Authorization", format!("Bearer {}", token))).to_request()
+}
+
+fn put(uri: &str, token: &str) -> Request {
+    test::TestRequest::put().uri(uri).insert_header(("Authorization", format!("Bearer {}", token))).to_request()
+}
+
+fn delete(uri: &str, token: &str) -> Request {
+    test::TestRequest::delete().uri(uri).insert_header(("Authorization", format!("Bearer {}", token))).to_request()
+}
+
+fn create(token: &str, body: Value) -> Request {
+    test::TestRequest::post()
+        .uri("/api/compras")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(body)
+        .to_request()
+}
+
+// Crea una compra del usuario y devuelve su ID
+async fn purchase_id(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    usuario: &str,
+) -> String {
+    let (status, body) = call(app, create(&token(usuario), json!({ "evento_id": 1, "cantidad": 2 }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    body["_id"]["$oid"].as_str().unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn get_all_events_is_public() {
+    let (app, _) = app().await;
+
+    let (status, body) = call(&app, test::TestRequest::get().uri("/api/eventos").to_request()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let nombres: Vec<&str> = body.as_array().unwrap().iter().map(|e| e["nombre"].as_str().unwrap()).collect();
+    assert_eq!(nombres, ["Concierto de Rock", "Festival de Jazz"]);
+}
+
+#[actix_web::test]
+async fn protected_routes_require_a_valid_token() {
+    let (app, _) = app().await;
+    let uri = format!("/api/compras/{}", MISSING_ID);
+
+    // Sin encabezado Authorization
+    let requests = [
+        test::TestRequest::get().uri("/api/compras").to_request(),
+        test::TestRequest::post().uri("/api/compras").set_json(json!({ "evento_id": 1, "cantidad": 1 })).to_request(),
+        test::TestRequest::put().uri(&format!("{}/pagar", uri)).to_request(),
+        test::TestRequest::delete().uri(&uri).to_request(),
+    ];
+    for req in requests {
+        let (status, _) = call(&app, req).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // Token mal formado, firmado con otra llave o vencido
+    for token in ["no-es-un-jwt".to_string(), token_with("ana", "otra-llave", 3600), token_with("ana", JWT_SECRET, -3600)] {
+        let (status, _) = call(&app, get("/api/compras", &token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[actix_web::test]
+async fn create_purchase_registers_it_for_the_authenticated_user() {
+    let (app, _) = app().await;
+
+    let (status, body) = call(&app, create(&token("ana"), json!({ "evento_id": 2, "cantidad": 3 }))).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["usuario_id"], "ana");
+    assert_eq!(body["evento_id"], 2);
+    assert_eq!(body["cantidad"], 3);
+    assert_eq!(body["estado"], "pendiente");
+    assert!(body["_id"]["$oid"].is_string());
+}
+
+#[actix_web::test]
+async fn create_purchase_rejects_malformed_body() {
+    let (app, _) = app().await;
+
+    let (status, _) = call(&app, create(&token("ana"), json!({ "evento_id": "uno" }))).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn get_user_purchases_returns_only_own_purchases() {
+    let (app, _) = app().await;
+    let own = purchase_id(&app, "ana").await;
+    purchase_id(&app, "luis").await;
+
+    let (status, body) = call(&app, get("/api/compras", &token("ana"))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let compras = body.as_array().unwrap();
+    assert_eq!(compras.len(), 1);
+    assert_eq!(compras[0]["_id"]["$oid"], own.as_str());
+}
+
+#[actix_web::test]
+async fn pay_purchase_marks_it_paid_and_enqueues_notification() {
+    let (app, mut notifications) = app().await;
+    let id = purchase_id(&app, "ana").await;
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/compras/{}/pagar", id))
+        .insert_header(("Authorization", format!("Bearer {}", token("ana"))))
+        .insert_header(("X-Request-Id", "pago-123"))
+        .to_request();
+    let (status, body) = call(&app, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["estado"], "pagada");
+    assert!(body["pagada_en"].is_object());
+
+    let notification = notifications.try_recv().expect("no se encoló la notificación");
+    assert_eq!(notification.compra_id, id);
+    assert_eq!(notification.request_id.as_str(), "pago-123");
+    let payload: Value = serde_json::from_str(&notification.payload).unwrap();
+    assert_eq!(payload["tipo"], "pago_confirmado");
+    assert_eq!(payload["usuario_id"], "ana");
+    assert_eq!(payload["correo"], "ana@example.com");
+    assert_eq!(payload["cantidad"], 2);
+}
+
+#[actix_web::test]
+async fn pay_purchase_twice_is_an_invalid_transition() {
+    let (app, mut notifications) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    let uri = format!("/api/compras/{}/pagar", id);
+    call(&app, put(&uri, &token("ana"))).await;
+
+    let (status, body) = call(&app, put(&uri, &token("ana"))).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "La compra está pagada y no puede pasar a pagada");
+    assert!(body["request_id"].is_string());
+    // Solo se notifica el primer pago
+    assert!(notifications.try_recv().is_ok());
+    assert!(notifications.try_recv().is_err());
+}
+
+#[actix_web::test]
+async fn pay_purchase_rejects_other_users_invalid_and_unknown_ids() {
+    let (app, mut notifications) = app().await;
+    let id = purchase_id(&app, "ana").await;
+
+    let (status, body) = call(&app, put(&format!("/api/compras/{}/pagar", id), &token("luis"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["message"], "Error de autenticación: No tienes permisos para pagar esta compra");
+
+    let (status, body) = call(&app, put("/api/compras/123/pagar", &token("ana"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["message"], "ID inválido: ID inválido");
+
+    let (status, _) = call(&app, put(&format!("/api/compras/{}/pagar", MISSING_ID), &token("ana"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // La compra sigue sin pagar y no se notificó nada
+    let (_, body) = call(&app, get("/api/compras", &token("ana"))).await;
+    assert_eq!(body[0]["estado"], "pendiente");
+    assert!(notifications.try_recv().is_err());
+}
+
+#[actix_web::test]
+async fn delete_purchase_removes_unpaid_purchase() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    let uri = format!("/api/compras/{}", id);
+
+    let (status, _) = call(&app, delete(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, body) = call(&app, get("/api/compras", &token("ana"))).await;
+    assert_eq!(body, json!([]));
+    let (status, _) = call(&app, delete(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn delete_purchase_rejects_paid_other_users_and_invalid_ids() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    let uri = format!("/api/compras/{}", id);
+
+    let (status, _) = call(&app, delete(&uri, &token("luis"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    call(&app, put(&format!("{}/pagar", uri), &token("ana"))).await;
+    let (status, body) = call(&app, delete(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "No se puede eliminar una compra pagada; solo las pendientes, canceladas o expiradas");
+
+    let (status, _) = call(&app, delete("/api/compras/no-es-un-id", &token("ana"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = call(&app, delete(&format!("/api/compras/{}", MISSING_ID), &token("ana"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// Capacidad restante del evento según GET /api/eventos
+async fn capacidad(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    evento_id: i32,
+) -> i64 {
+    let (_, body) = call(app, test::TestRequest::get().uri("/api/eventos").to_request()).await;
+    let event = body.as_array().unwrap().iter().find(|e| e["id"] == evento_id).unwrap();
+    event["capacidad"].as_i64().unwrap()
+}
+
+#[actix_web::test]
+async fn create_purchase_decrements_event_capacity() {
+    let (app, _) = app().await;
+
+    call(&app, create(&token("ana"), json!({ "evento_id": 2, "cantidad": 3 }))).await;
+    call(&app, create(&token("luis"), json!({ "evento_id": 2, "cantidad": 497 }))).await;
+
+    assert_eq!(capacidad(&app, 2).await, 0);
+    assert_eq!(capacidad(&app, 1).await, 1000);
+}
+
+#[actix_web::test]
+async fn create_purchase_fails_with_sold_out_when_capacity_is_insufficient() {
+    let (app, _) = app().await;
+    call(&app, create(&token("ana"), json!({ "evento_id": 2, "cantidad": 499 }))).await;
+
+    let (status, body) = call(&app, create(&token("luis"), json!({ "evento_id": 2, "cantidad": 2 }))).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "No quedan entradas suficientes para este evento");
+    // No se descontó nada y no se registró la compra
+    assert_eq!(capacidad(&app, 2).await, 1);
+    let (_, body) = call(&app, get("/api/compras", &token("luis"))).await;
+    assert_eq!(body, json!([]));
+
+    // La última entrada todavía se puede comprar
+    let (status, _) = call(&app, create(&token("luis"), json!({ "evento_id": 2, "cantidad": 1 }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[actix_web::test]
+async fn create_purchase_rejects_unknown_event_and_invalid_quantities() {
+    let (app, _) = app().await;
+
+    let (status, _) = call(&app, create(&token("ana"), json!({ "evento_id": 99, "cantidad": 1 }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for cantidad in [0, -5] {
+        let (status, body) = call(&app, create(&token("ana"), json!({ "evento_id": 1, "cantidad": cantidad }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Cantidad inválida: debe ser mayor a 0");
+    }
+    assert_eq!(capacidad(&app, 1).await, 1000);
+}
+
+#[actix_web::test]
+async fn concurrent_purchases_never_oversell() {
+    let (app, _) = app().await;
+
+    // 10 compras simultáneas de 60 entradas para un evento con 500
+    let requests = (0..10).map(|i| call(&app, create(&token(&format!("usuario{}", i)), json!({ "evento_id": 2, "cantidad": 60 }))));
+    let results = futures::future::join_all(requests).await;
+
+    let sold = results.iter().filter(|(status, _)| *status == StatusCode::CREATED).count();
+    let sold_out = results.iter().filter(|(status, _)| *status == StatusCode::CONFLICT).count();
+    assert_eq!((sold, sold_out), (8, 2));
+    assert_eq!(capacidad(&app, 2).await, 20);
+}
+
+#[actix_web::test]
+async fn delete_purchase_restores_event_capacity() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    assert_eq!(capacidad(&app, 1).await, 998);
+
+    let (status, _) = call(&app, delete(&format!("/api/compras/{}", id), &token("ana"))).await;
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(capacidad(&app, 1).await, 1000);
+}
+
+#[actix_web::test]
+async fn reconcile_capacity_recomputes_available_seats_from_purchases() {
+    // El concierto perdió 100 entradas por compras eliminadas; el festival no tiene capacidad_total
+    let repo = MemoryRepo::with_events(vec![
+        Event { capacidad: 900, ..event(1, "Concierto de Rock", 1000) },
+        Event { capacidad_total: None, ..event(2, "Festival de Jazz", 500) },
+    ]);
+    for (evento_id, cantidad) in [(1, 2), (1, 3), (2, 4)] {
+        repo.create_purchase("ana".to_string(), CreatePurchaseDto { evento_id, cantidad }).await.unwrap();
+    }
+
+    let report = repo.reconcile_capacity().await.unwrap();
+
+    assert_eq!(
+        report,
+        [
+            CapacityReconciliation {
+                evento_id: 1,
+                nombre: "Concierto de Rock".to_string(),
+                vendidas: 5,
+                capacidad_anterior: 895,
+                capacidad: Some(995),
+            },
+            CapacityReconciliation {
+                evento_id: 2,
+                nombre: "Festival de Jazz".to_string(),
+                vendidas: 4,
+                capacidad_anterior: 496,
+                capacidad: None,
+            },
+        ]
+    );
+    let capacidades: Vec<i32> = repo.get_all_events().await.unwrap().iter().map(|e| e.capacidad).collect();
+    assert_eq!(capacidades, [995, 496]);
+    // Sin cambios en las compras, volver a reconciliar no modifica nada
+    assert_eq!(repo.reconcile_capacity().await.unwrap()[0].capacidad_anterior, 995);
+}
+
+#[actix_web::test]
+async fn expired_holds_return_seats_and_cannot_be_paid() {
+    // Sin tiempo de reserva, toda compra sin pagar vence en cuanto se crea
+    let repo = Arc::new(repo().with_seat_hold(Duration::ZERO));
+    let (app, _) = app_with(repo.clone()).await;
+    let id = purchase_id(&app, "ana").await;
+    assert_eq!(capacidad(&app, 1).await, 998);
+
+    // Vencida pero todavía sin barrer: tampoco se puede pagar
+    let uri = format!("/api/compras/{}", id);
+    let (status, body) = call(&app, put(&format!("{}/pagar", uri), &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "La reserva de la compra venció; sus entradas volvieron a estar disponibles");
+
+    let (notifier, mut notifications) = Notifier::channel();
+    assert_eq!(holds::sweep(repo.as_ref(), Some(&notifier)).await, 1);
+
+    assert_eq!(capacidad(&app, 1).await, 1000);
+    let (_, body) = call(&app, get("/api/compras", &token("ana"))).await;
+    assert_eq!(body[0]["estado"], "expirada");
+    assert!(body[0]["expirada_en"].is_object());
+    let notification = notifications.try_recv().unwrap();
+    assert_eq!(notification.compra_id, id);
+    let payload: Value = serde_json::from_str(&notification.payload).unwrap();
+    assert_eq!(payload["tipo"], "reserva_expirada");
+    assert_eq!(payload["usuario_id"], "ana");
+
+    let (status, body) = call(&app, put(&format!("{}/pagar", uri), &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "La compra está expirada y no puede pasar a pagada");
+
+    // Un segundo barrido no la vuelve a expirar y eliminarla no devuelve las entradas otra vez
+    assert_eq!(holds::sweep(repo.as_ref(), Some(&notifier)).await, 0);
+    let (status, _) = call(&app, delete(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(capacidad(&app, 1).await, 1000);
+}
+
+#[actix_web::test]
+async fn sweep_keeps_paid_purchases_and_active_holds() {
+    let repo = Arc::new(repo());
+    let (app, _) = app_with(repo.clone()).await;
+    let pagada = purchase_id(&app, "ana").await;
+    call(&app, put(&format!("/api/compras/{}/pagar", pagada), &token("ana"))).await;
+    let (status, body) = call(&app, create(&token("luis"), json!({ "evento_id": 1, "cantidad": 3 }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(body["reservada_hasta"].is_object());
+    assert_eq!(body["estado"], "pendiente");
+
+    assert_eq!(holds::sweep(repo.as_ref(), None).await, 0);
+    assert_eq!(capacidad(&app, 1).await, 995);
+}
+
+#[actix_web::test]
+async fn reconcile_capacity_ignores_expired_purchases() {
+    let repo = repo().with_seat_hold(Duration::ZERO);
+    repo.create_purchase("ana".to_string(), CreatePurchaseDto { evento_id: 2, cantidad: 4 }).await.unwrap();
+    repo.expire_holds().await.unwrap();
+
+    let report = repo.reconcile_capacity().await.unwrap();
+
+    assert_eq!((report[1].vendidas, report[1].capacidad), (0, Some(500)));
+}
+
+#[actix_web::test]
+async fn cancel_purchase_releases_seats_and_is_final() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    let uri = format!("/api/compras/{}", id);
+
+    let (status, _) = call(&app, put(&format!("{}/cancelar", uri), &token("luis"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = call(&app, put(&format!("{}/cancelar", uri), &token("ana"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["estado"], "cancelada");
+    assert!(body["cancelada_en"].is_object());
+    assert_eq!(capacidad(&app, 1).await, 1000);
+
+    // Una compra cancelada ya no se paga ni se cancela otra vez
+    let (status, body) = call(&app, put(&format!("{}/pagar", uri), &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "La compra está cancelada y no puede pasar a pagada");
+    let (status, _) = call(&app, put(&format!("{}/cancelar", uri), &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Eliminarla no devuelve las entradas dos veces
+    let (status, _) = call(&app, delete(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(capacidad(&app, 1).await, 1000);
+}
+
+#[actix_web::test]
+async fn support_staff_can_cancel_any_purchase() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+
+    let (status, body) = call(&app, put(&format!("/api/compras/{}/cancelar", id), &support_token("sofia"))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["estado"], "cancelada");
+}
+
+#[actix_web::test]
+async fn refund_purchase_requires_support_role_and_a_paid_purchase() {
+    let (app, _) = app().await;
+    let id = purchase_id(&app, "ana").await;
+    let uri = format!("/api/compras/{}/reembolsar", id);
+
+    // Solo se reembolsan compras pagadas
+    let (status, body) = call(&app, put(&uri, &support_token("sofia"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "La compra está pendiente y no puede pasar a reembolsada");
+
+    call(&app, put(&format!("/api/compras/{}/pagar", id), &token("ana"))).await;
+    assert_eq!(capacidad(&app, 1).await, 998);
+
+    // Ni siquiera el dueño puede reembolsarse sin ser personal de soporte
+    let (status, body) = call(&app, put(&uri, &token("ana"))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["message"], "Permiso denegado: se requiere uno de los roles: admin, soporte");
+
+    let (status, body) = call(&app, put(&uri, &support_token("sofia"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["estado"], "reembolsada");
+    assert!(body["pagada_en"].is_object());
+    assert!(body["reembolsada_en"].is_object());
+    assert_eq!(capacidad(&app, 1).await, 1000);
+
+    // Reembolsada es final y no se elimina
+    let (status, _) = call(&app, put(&uri, &support_token("sofia"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(&app, delete(&format!("/api/compras/{}", id), &token("ana"))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = call(&app, put(&format!("/api/compras/{}/reembolsar", MISSING_ID), &support_token("sofia"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn purchase_status_transition_table() {
+    use PurchaseStatus::*;
+    let all = [Pendiente, Pagada, Cancelada, Reembolsada, Expirada];
+    let allowed: Vec<_> = all
+        .iter()
+        .flat_map(|from| all.iter().map(move |to| (*from, *to)))
+        .filter(|(from, to)| from.can_transition_to(*to))
+        .collect();
+
+    assert_eq!(
+        allowed,
+        [(Pendiente, Pagada), (Pendiente, Cancelada), (Pendiente, Expirada), (Pagada, Reembolsada)]
+    );
+}
+
+fn evento(nombre: &str, capacidad: i32) -> Value {
+    json!({
+        "nombre": nombre,
+        "fecha": "2099-06-01T21:00:00Z",
+        "lugar": "Teatro Municipal",
+        "capacidad": capacidad,
+        "precio": "40.50",
+    })
+}
+
+fn send_event(req: test::TestRequest, token: &str, body: Value) -> Request {
+    req.insert_header(("Authorization", format!("Bearer {}", token))).set_json(body).to_request()
+}
+
+#[actix_web::test]
+async fn created_events_are_hidden_until_published() {
+    let (app, _) = app().await;
+    let organizador = organizer_token("olga");
+
+    let (status, body) =
+        call(&app, send_event(test::TestRequest::post().uri("/api/eventos"), &organizador, evento("Ópera", 80))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["id"], 3);
+    assert_eq!(body["publicado"], false);
+    assert_eq!(body["capacidad_total"], 80);
+
+    // Sin publicar no aparece en el listado ni acepta compras
+    let (_, body) = call(&app, test::TestRequest::get().uri("/api/eventos").to_request()).await;
+    assert!(body.as_array().unwrap().iter().all(|e| e["id"] != 3));
+    let (status, _) = call(&app, create(&token("ana"), json!({ "evento_id": 3, "cantidad": 1 }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&app, put("/api/eventos/3/publicar", &organizador)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["publicado"], true);
+
+    let (status, _) = call(&app, create(&token("ana"), json!({ "evento_id": 3, "cantidad": 1 }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(capacidad(&app, 3).await, 79);
+}
+
+#[actix_web::test]
+async fn event_management_requires_organizer_role() {
+    let (app, _) = app().await;
+
+    for token in [token("ana"), support_token("sofia")] {
+        let (status, body) =
+            call(&app, send_event(test::TestRequest::post().uri("/api/eventos"), &token, evento("Ópera", 80))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "Permiso denegado: se requiere uno de los roles: organizador, admin");
+
+        let (status, _) = call(&app, delete("/api/eventos/1", &token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    let (status, _) =
+        call(&app, test::TestRequest::post().uri("/api/eventos").set_json(evento("Ópera", 80)).to_request()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let admin = signed_token("root", "admin", JWT_SECRET, 3600);
+    let (status, _) = call(&app, put("/api/eventos/1/publicar", &admin)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn create_event_validates_all_fields() {
+    let (app, _) = app().await;
+
+    let invalid = json!({
+        "nombre": "  ",
+        "fecha": "2020-01-01T00:00:00Z",
+        "lugar": "Teatro Municipal",
+        "capacidad": 0,
+        "precio": "12.345",
+    });
+    let (status, body) =
+        call(&app, send_event(test::TestRequest::post().uri("/api/eventos"), &organizer_token("olga"), invalid)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["message"],
+        "Evento inválido: nombre: no puede estar vacío; fecha: debe ser posterior a la fecha actual; \
+         capacidad: debe ser mayor que cero; precio: debe ser un número no negativo con hasta dos decimales"
+    );
+
+    for (campo, valor) in [("fecha", "15/12/2099"), ("precio", "-5"), ("precio", "10.")] {
+        let mut body = evento("Ópera", 80);
+        body[campo] = json!(valor);
+        let (status, _) =
+            call(&app, send_event(test::TestRequest::post().uri("/api/eventos"), &organizer_token("olga"), body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} = {}", campo, valor);
+    }
+}
+
+#[actix_web::test]
+async fn update_event_keeps_sold_seats() {
+    let (app, _) = app().await;
+    let organizador = organizer_token("olga");
+    call(&app, create(&token("ana"), json!({ "evento_id": 2, "cantidad": 100 }))).await;
+
+    // No puede quedar por debajo de las 100 entradas ya reservadas
+    let (status, body) =
+        call(&app, send_event(test::TestRequest::put().uri("/api/eventos/2"), &organizador, evento("Jazz", 99))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().starts_with("Evento inválido: capacidad"));
+
+    let (status, body) =
+        call(&app, send_event(test::TestRequest::put().uri("/api/eventos/2"), &organizador, evento("Jazz", 300))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["nombre"], "Jazz");
+    assert_eq!(body["capacidad_total"], 300);
+    assert_eq!(body["capacidad"], 200);
+
+    let (status, _) =
+        call(&app, send_event(test::TestRequest::put().uri("/api/eventos/99"), &organizador, evento("Jazz", 300))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn delete_event_requires_no_active_purchases() {
+    let (app, _) = app().await;
+    let organizador = organizer_token("olga");
+    let id = purchase_id(&app, "ana").await;
+
+    let (status, _) = call(&app, delete("/api/eventos/1", &organizador)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Una compra cancelada ya no impide eliminarlo
+    call(&app, put(&format!("/api/compras/{}/cancelar", id), &token("ana"))).await;
+    let (status, _) = call(&app, delete("/api/eventos/1", &organizador)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = call(&app, delete("/api/eventos/1", &organizador)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&app, delete("/api/eventos/uno", &organizador)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().starts_with("ID inválido"));
+}
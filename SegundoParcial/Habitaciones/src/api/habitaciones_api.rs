@@ -6,9 +6,9 @@ use actix_web::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::AppError,
-    model::{CreateHabitacionDto, UpdateHabitacionDto},
-    repository::mongodb_repo::MongoRepo,
+    error::{AppError, ErrorResponse},
+    model::{CreateHabitacionDto, Habitacion, UpdateHabitacionDto},
+    repository::habitacion_repository::HabitacionRepository,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,54 +19,91 @@ pub struct Claims {
     pub exp: usize,
 }
 
-// Listar habitaciones (protegido)
+/// Listar habitaciones (protegido)
+#[utoipa::path(
+    tag = "habitaciones",
+    responses(
+        (status = 200, description = "Habitaciones registradas", body = Vec<Habitacion>),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[get("/habitaciones")]
 pub async fn listar_habitaciones(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn HabitacionRepository>,
     _claims: web::ReqData<Claims>,
 ) -> Result<HttpResponse, AppError> {
-    println!("Entrando a listar_habitaciones");
     let habitaciones = db.get_all_habitaciones().await?;
-    println!("Habitaciones obtenidas: {:?}", habitaciones);
+    tracing::debug!(total = habitaciones.len(), "Habitaciones obtenidas");
     Ok(HttpResponse::Ok().json(habitaciones))
 }
 
-// Crear habitacion (protegido)
+/// Crear habitacion (protegido)
+#[utoipa::path(
+    tag = "habitaciones",
+    request_body = CreateHabitacionDto,
+    responses(
+        (status = 201, description = "Habitación creada", body = Habitacion),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[post("/habitaciones")]
 pub async fn crear_habitacion(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn HabitacionRepository>,
     _claims: web::ReqData<Claims>,
     dto: Json<CreateHabitacionDto>,
 ) -> Result<HttpResponse, AppError> {
-    println!("Entrando a crear_habitacion con dto: {:?}", dto);
     let habitacion = db.create_habitacion(dto.into_inner()).await?;
-    println!("Habitacion creada: {:?}", habitacion);
+    tracing::info!(id = habitacion.id, numero = habitacion.numero_habitacion, "Habitación creada");
     Ok(HttpResponse::Created().json(habitacion))
 }
 
-// Actualizar habitacion (protegido)
+/// Actualizar habitacion (protegido)
+#[utoipa::path(
+    tag = "habitaciones",
+    params(("id" = i32, Path, description = "ID de la habitación")),
+    request_body = UpdateHabitacionDto,
+    responses(
+        (status = 200, description = "Habitación actualizada", body = Habitacion),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 404, description = "Habitación no encontrada", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[put("/habitaciones/{id}")]
 pub async fn actualizar_habitacion(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn HabitacionRepository>,
     _claims: web::ReqData<Claims>,
     id: Path<i32>,
     dto: Json<UpdateHabitacionDto>,
 ) -> Result<HttpResponse, AppError> {
-    println!("Entrando a actualizar_habitacion con id: {:?}, dto: {:?}", id, dto);
     let habitacion = db.update_habitacion(id.into_inner(), dto.into_inner()).await?;
-    println!("Habitacion actualizada: {:?}", habitacion);
+    tracing::info!(id = habitacion.id, estado = %habitacion.estado, "Habitación actualizada");
     Ok(HttpResponse::Ok().json(habitacion))
 }
 
-// Eliminar habitacion (protegido)
+/// Eliminar habitacion (protegido)
+#[utoipa::path(
+    tag = "habitaciones",
+    params(("id" = i32, Path, description = "ID de la habitación")),
+    responses(
+        (status = 204, description = "Habitación eliminada"),
+        (status = 400, description = "ID inválido", body = ErrorResponse),
+        (status = 401, description = "Token ausente o inválido", body = ErrorResponse),
+        (status = 404, description = "Habitación no encontrada", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[delete("/habitaciones/{id}")]
 pub async fn eliminar_habitacion(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn HabitacionRepository>,
     _claims: web::ReqData<Claims>,
     id: Path<i32>,
 ) -> Result<HttpResponse, AppError> {
-    println!("Entrando a eliminar_habitacion con id: {:?}", id);
-    db.delete_habitacion(id.into_inner()).await?;
-    println!("Habitacion eliminada");
+    let id = id.into_inner();
+    db.delete_habitacion(id).await?;
+    tracing::info!(id, "Habitación eliminada");
     Ok(HttpResponse::NoContent().finish())
 }
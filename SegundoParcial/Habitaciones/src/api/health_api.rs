@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, web, HttpResponse};
+use serde_json::json;
+
+use crate::{
+    health::{run_check, CheckStatus, ReadinessReport},
+    repository::habitacion_repository::HabitacionRepository,
+};
+
+// Endpoint de vida: responde mientras el proceso pueda atender solicitudes (público)
+#[get("/health/live")]
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "up" }))
+}
+
+// Endpoint de disponibilidad: verifica que MongoDB responda (público)
+#[get("/health/ready")]
+pub async fn ready(db: web::Data<dyn HabitacionRepository>) -> HttpResponse {
+    let mongodb = run_check("mongodb", async { db.ping().await.map_err(|e| e.to_string()) }).await;
+
+    let report = ReadinessReport::new(BTreeMap::from([("mongodb", mongodb)]));
+    match report.status {
+        CheckStatus::Up => HttpResponse::Ok().json(report),
+        CheckStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
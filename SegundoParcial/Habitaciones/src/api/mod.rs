@@ -1,2 +1,26 @@
+use actix_web::web;
+use actix_web_httpauth::middleware::HttpAuthentication;
 
-pub mod habitaciones_api;
\ No newline at end of file
+use crate::{auth::jwt_validator, error::AppError};
+
+pub mod habitaciones_api;
+pub mod health_api;
+
+// Rutas de la API; el servidor las monta bajo /api (las pruebas de integración también)
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    // Configuración de autenticación Bearer con JWT
+    let auth = HttpAuthentication::bearer(jwt_validator);
+
+    cfg
+        // Un id que no es un número es un error del cliente (400), no una ruta inexistente
+        .app_data(web::PathConfig::default().error_handler(|err, _| AppError::InvalidRoomID(err.to_string()).into()))
+        // Endpoints protegidos que requieren autenticación
+        .service(
+            web::scope("")
+                .wrap(auth)
+                .service(habitaciones_api::listar_habitaciones)
+                .service(habitaciones_api::crear_habitacion)
+                .service(habitaciones_api::actualizar_habitacion)
+                .service(habitaciones_api::eliminar_habitacion),
+        );
+}
@@ -0,0 +1,38 @@
+use actix_web::{dev::ServiceRequest, web, Error, HttpMessage};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+
+use crate::api::habitaciones_api::Claims;
+
+// Llave con la que se validan los tokens JWT; se crea una sola vez al iniciar
+pub struct JwtKey(DecodingKey);
+
+impl JwtKey {
+    pub fn from_secret(secret: &str) -> Self {
+        JwtKey(DecodingKey::from_secret(secret.as_bytes()))
+    }
+}
+
+// Función de validación JWT para el middleware de autenticación
+pub async fn jwt_validator(req: ServiceRequest, credentials: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+    let token = credentials.token();
+    let Some(key) = req.app_data::<web::Data<JwtKey>>().cloned() else {
+        tracing::error!("La llave JWT no está registrada en la aplicación");
+        return Err((actix_web::error::ErrorInternalServerError("Configuración no disponible"), req));
+    };
+
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = true;
+
+    match decode::<Claims>(token, &key.0, &validation) {
+        Ok(token_data) => {
+            let claims = token_data.claims;
+            req.extensions_mut().insert(claims); // <-- Inserta Claims completo
+            Ok(req)
+        },
+        Err(err) => {
+            tracing::warn!(error = %err, "Token JWT inválido");
+            Err((actix_web::error::ErrorUnauthorized("Token JWT inválido"), req))
+        }
+    }
+}
@@ -2,6 +2,9 @@ use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use mongodb::bson;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::telemetry::RequestId;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -20,23 +23,18 @@ pub enum AppError {
     #[error("Habitación no encontrada")]
     NotFoundError,
 
-    #[error("No hay habitaciones disponibles")]
-    NoRoomsAvailable,
-
-    #[error("La habitación ya está ocupada")]
-    RoomAlreadyOccupied,
-
     #[error("Error interno del servidor: {0}")]
     InternalError(String),
-
-    #[error("Error de autenticación: {0}")]
-    Unauthorized(String),
 }
 
-#[derive(Serialize, Deserialize)]
-struct ErrorResponse {
+// Cuerpo JSON de todas las respuestas de error
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
     status: String,
     message: String,
+    // ID de la solicitud (X-Request-Id) para buscarla en los registros
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl ResponseError for AppError {
@@ -45,6 +43,7 @@ impl ResponseError for AppError {
         HttpResponse::build(status_code).json(ErrorResponse {
             status: status_code.to_string(),
             message: self.to_string(),
+            request_id: RequestId::current().map(|id| id.to_string()),
         })
     }
 
@@ -52,9 +51,6 @@ impl ResponseError for AppError {
         match self {
             AppError::NotFoundError => StatusCode::NOT_FOUND,
             AppError::InvalidRoomID(_) => StatusCode::BAD_REQUEST,
-            AppError::NoRoomsAvailable => StatusCode::BAD_REQUEST,
-            AppError::RoomAlreadyOccupied => StatusCode::BAD_REQUEST,
-            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
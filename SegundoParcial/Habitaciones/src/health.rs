@@ -0,0 +1,62 @@
+use std::{collections::BTreeMap, future::Future, time::Duration};
+
+use serde::Serialize;
+use tokio::time::{timeout, Instant};
+
+// Tiempo máximo que /health/ready espera a cada dependencia
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+// Resultado de verificar una dependencia
+#[derive(Debug, Serialize)]
+pub struct DependencyReport {
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Reporte de disponibilidad con el detalle de cada dependencia
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<&'static str, DependencyReport>,
+}
+
+impl ReadinessReport {
+    // El servicio está listo solo si todas las dependencias responden
+    pub fn new(checks: BTreeMap<&'static str, DependencyReport>) -> Self {
+        let status = if checks.values().all(|check| check.status == CheckStatus::Up) {
+            CheckStatus::Up
+        } else {
+            CheckStatus::Down
+        };
+        ReadinessReport { status, checks }
+    }
+}
+
+// Ejecuta una verificación con tiempo límite y mide su latencia
+pub async fn run_check<F>(name: &str, check: F) -> DependencyReport
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = match timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Sin respuesta después de {} ms", CHECK_TIMEOUT.as_millis())),
+    };
+    if let Err(err) = &result {
+        tracing::warn!(dependency = name, error = %err, "La dependencia no está disponible");
+    }
+    DependencyReport {
+        status: if result.is_ok() { CheckStatus::Up } else { CheckStatus::Down },
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err(),
+    }
+}
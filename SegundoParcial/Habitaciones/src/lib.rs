@@ -0,0 +1,12 @@
+// Módulos del servicio; main.rs arma el servidor y las pruebas de integración usan las mismas rutas
+pub mod api;
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod health;
+pub mod metrics;
+pub mod model;
+pub mod openapi;
+pub mod repository;
+pub mod shutdown;
+pub mod telemetry;
@@ -1,107 +1,106 @@
-mod api;
-mod error;
-mod model;
-mod repository;
-
 use actix_cors::Cors;
-use actix_web::{
-    middleware::Logger, 
-    web, 
-    App, 
-    HttpServer, 
-    dev::ServiceRequest, 
-    Error, 
-    HttpMessage
-};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
-use actix_web_httpauth::middleware::HttpAuthentication;
-use api::habitaciones_api::{
-    listar_habitaciones, crear_habitacion, actualizar_habitacion, eliminar_habitacion,Claims
-};
+use actix_web::{middleware::from_fn, web, App, HttpServer};
 use dotenv::dotenv;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use mongodb::{options::ClientOptions, Client};
-use repository::mongodb_repo::MongoRepo;
-use std::env;
-
-// Función de validación JWT para el middleware de autenticación
-async fn jwt_validator(req: ServiceRequest, credentials: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let token = credentials.token();
-    let jwt_secret = env::var("LlaveJWT").expect("LlaveJWT no está establecida en .env");
-
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.validate_exp = true;
-
-    match decode::<Claims>(
-    token,
-    &DecodingKey::from_secret(jwt_secret.as_ref()),
-    &validation,
-) {
-    Ok(token_data) => {
-        let claims = token_data.claims;
-        req.extensions_mut().insert(claims); // <-- Inserta Claims completo
-        Ok(req)
-    },
-    Err(_) => {
-        Err((actix_web::error::ErrorUnauthorized("Token JWT inválido").into(), req))
-    }
-}
-}
+use rust_mongodb_crud::{
+    api::{self, health_api::{live, ready}},
+    auth::JwtKey,
+    config::{CliOptions, Config},
+    metrics,
+    openapi::ApiDoc,
+    repository::{habitacion_repository::HabitacionRepository, mongodb_repo::MongoRepo},
+    shutdown,
+    telemetry,
+};
+use std::{env, process, sync::Arc};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
-    // Validar que las variables de entorno necesarias estén presentes
-    let mongo_uri = env::var("MONGO_URI").expect("MONGO_URI no está establecida en .env");
-    let mongo_db_name = env::var("MONGO_DB_NAME").expect("MONGO_DB_NAME no está establecida en .env");
-    let _jwt_secret = env::var("LlaveJWT").expect("LlaveJWT no está establecida en .env");
+    // Opciones de la línea de comandos: --print-config y --config <ruta>
+    let options = CliOptions::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+    // Cargar y validar toda la configuración una sola vez; si algo falta o es inválido se listan todos los problemas
+    let config = Config::load(options.config_file.as_deref()).unwrap_or_else(|err| {
+        eprint!("{}", err);
+        process::exit(1);
+    });
+    if options.print_config {
+        // Configuración efectiva sin secretos
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+
+    // Registros estructurados (JSON salvo log_format = "text") con el X-Request-Id de cada solicitud
+    telemetry::init(config.log_format);
+    // La llave JWT y la contraseña de MongoDB no se muestran
+    tracing::info!(?config, "Configuración cargada");
 
-    let client_options = ClientOptions::parse(&mongo_uri)
+    let client_options = ClientOptions::parse(config.mongo_uri.expose())
         .await
         .expect("Error al analizar la URI de MongoDB");
     let client = Client::with_options(client_options).expect("Error al crear el cliente de MongoDB");
-    let db = client.database(&mongo_db_name);
+    let db = client.database(&config.mongo_db_name);
 
-    let mongo_repo = MongoRepo::new(db);
-    let mongo_data = web::Data::new(mongo_repo);
+    let mongo_repo: Arc<dyn HabitacionRepository> = Arc::new(MongoRepo::new(db));
+    let mongo_data = web::Data::from(mongo_repo);
+    // La llave JWT se prepara una sola vez para todas las solicitudes
+    let jwt_key = web::Data::new(JwtKey::from_secret(config.jwt_secret.expose()));
 
-    let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let server_port = env::var("SERVER_PORT")
-        .unwrap_or_else(|_| "8081".to_string())
-        .parse::<u16>()
-        .expect("SERVER_PORT debe ser un número");
+    let (server_host, server_port) = (config.server_host.clone(), config.server_port);
+    let shutdown_timeout = config.shutdown_timeout();
 
     log::info!("Iniciando servidor en http://{}:{}", server_host, server_port);
 
-    HttpServer::new(move || {
+    // Registrar las métricas de Prometheus antes de atender solicitudes
+    metrics::init();
+
+    // Documento OpenAPI, generado una sola vez para todos los workers
+    let openapi = ApiDoc::openapi();
+
+    let server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header();
 
-        // Configuración de autenticación Bearer con JWT
-        let auth = HttpAuthentication::bearer(jwt_validator);
-
         App::new()
             .wrap(cors)
-            .wrap(Logger::default())
+            // Cuenta las solicitudes y mide su latencia por ruta
+            .wrap(from_fn(metrics::track_requests))
+            // Cuenta las solicitudes en curso para el resumen del apagado
+            .wrap(from_fn(shutdown::track_in_flight))
+            // Asigna el X-Request-Id y registra cada solicitud (debe ser el más externo)
+            .wrap(from_fn(telemetry::request_context))
             .app_data(mongo_data.clone())
-            .service(
-                web::scope("/api")
-                    // Endpoints protegidos que requieren autenticación
-                    .service(
-                        web::scope("")
-                            .wrap(auth)
-                            .service(listar_habitaciones)
-                            .service(crear_habitacion)
-                            .service(actualizar_habitacion)
-                            .service(eliminar_habitacion)
-                    ),
-            )
+            // Llave con la que jwt_validator valida los tokens
+            .app_data(jwt_key.clone())
+            // Endpoints de salud para Docker y nginx (públicos, fuera de /api)
+            .service(live)
+            .service(ready)
+            // Métricas para Prometheus (público, fuera de /api)
+            .service(metrics::metrics)
+            // Swagger UI en /api/docs y el documento en /api/openapi.json (públicos)
+            .service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", openapi.clone()))
+            // Endpoints de habitaciones (protegidos con JWT)
+            .service(web::scope("/api").configure(api::routes))
     })
+    // Las señales se manejan en shutdown para esperar las solicitudes y cerrar MongoDB
+    .disable_signals()
     .bind((server_host, server_port))?
-    .run()
-    .await
+    .run();
+
+    // Atiende solicitudes hasta recibir SIGTERM o Ctrl+C y espera las que estén en curso
+    let summary = shutdown::serve_until_signal(server, shutdown_timeout).await;
+
+    client.shutdown().await;
+    tracing::info!("Conexión con MongoDB cerrada");
+
+    summary?.log();
+    Ok(())
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
-use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Habitacion {
     pub id: i32,
     pub numero_habitacion: i32,
@@ -11,7 +11,7 @@ pub struct Habitacion {
     pub descripcion: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateHabitacionDto {
     pub numero_habitacion: i32,
     pub tipo_habitacion: String,
@@ -20,7 +20,7 @@ pub struct CreateHabitacionDto {
     pub descripcion: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateHabitacionDto {
     pub numero_habitacion: i32,
     pub tipo_habitacion: String,
@@ -0,0 +1,38 @@
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{api::habitaciones_api, error::ErrorResponse};
+
+// Documento OpenAPI generado a partir de los endpoints y los modelos
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "API de habitaciones", description = "Administración de las habitaciones del hotel."),
+    servers((url = "/api")),
+    paths(
+        habitaciones_api::listar_habitaciones,
+        habitaciones_api::crear_habitacion,
+        habitaciones_api::actualizar_habitacion,
+        habitaciones_api::eliminar_habitacion,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "habitaciones", description = "Habitaciones del hotel (requieren JWT)"),
+    )
+)]
+pub struct ApiDoc;
+
+// Registra el esquema `bearer_auth` que usan las rutas protegidas por `jwt_validator`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
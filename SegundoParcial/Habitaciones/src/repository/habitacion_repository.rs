@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::AppError,
+    model::{CreateHabitacionDto, Habitacion, UpdateHabitacionDto},
+};
+
+// Contrato común para el almacenamiento de habitaciones (MongoDB o memoria)
+#[async_trait]
+pub trait HabitacionRepository: Send + Sync {
+    // Verificar que el almacenamiento responde (usado por /health/ready)
+    async fn ping(&self) -> Result<(), AppError>;
+
+    // Obtener todas las habitaciones
+    async fn get_all_habitaciones(&self) -> Result<Vec<Habitacion>, AppError>;
+
+    // Crear una habitacion con id autogenerado (el mayor id actual más 1)
+    async fn create_habitacion(&self, dto: CreateHabitacionDto) -> Result<Habitacion, AppError>;
+
+    // Reemplazar los datos de una habitacion existente
+    async fn update_habitacion(&self, id: i32, dto: UpdateHabitacionDto) -> Result<Habitacion, AppError>;
+
+    // Eliminar una habitacion
+    async fn delete_habitacion(&self, id: i32) -> Result<(), AppError>;
+}
@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, sync::RwLock};
+
+use async_trait::async_trait;
+
+use crate::{
+    error::AppError,
+    model::{CreateHabitacionDto, Habitacion, UpdateHabitacionDto},
+    repository::habitacion_repository::HabitacionRepository,
+};
+
+// Repositorio en memoria para las pruebas de integración (sin MongoDB)
+#[derive(Default)]
+pub struct MemoryRepo {
+    // Ordenadas por id, igual que se crean
+    habitaciones: RwLock<BTreeMap<i32, Habitacion>>,
+}
+
+impl MemoryRepo {
+    pub fn new() -> Self {
+        MemoryRepo::default()
+    }
+}
+
+// Un bloqueo envenenado solo ocurre si otra solicitud falló a la mitad
+fn poisoned<T>(_: T) -> AppError {
+    AppError::InternalError("El repositorio en memoria quedó en un estado inconsistente".to_string())
+}
+
+#[async_trait]
+impl HabitacionRepository for MemoryRepo {
+    async fn ping(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn get_all_habitaciones(&self) -> Result<Vec<Habitacion>, AppError> {
+        Ok(self.habitaciones.read().map_err(poisoned)?.values().cloned().collect())
+    }
+
+    async fn create_habitacion(&self, dto: CreateHabitacionDto) -> Result<Habitacion, AppError> {
+        let mut habitaciones = self.habitaciones.write().map_err(poisoned)?;
+        let next_id = habitaciones.keys().next_back().map_or(1, |id| id + 1);
+        let habitacion = Habitacion {
+            id: next_id,
+            numero_habitacion: dto.numero_habitacion,
+            tipo_habitacion: dto.tipo_habitacion,
+            precio_noche: dto.precio_noche,
+            estado: dto.estado,
+            descripcion: dto.descripcion,
+        };
+        habitaciones.insert(next_id, habitacion.clone());
+        Ok(habitacion)
+    }
+
+    async fn update_habitacion(&self, id: i32, dto: UpdateHabitacionDto) -> Result<Habitacion, AppError> {
+        let mut habitaciones = self.habitaciones.write().map_err(poisoned)?;
+        let habitacion = habitaciones.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        habitacion.numero_habitacion = dto.numero_habitacion;
+        habitacion.tipo_habitacion = dto.tipo_habitacion;
+        habitacion.precio_noche = dto.precio_noche;
+        habitacion.estado = dto.estado;
+        habitacion.descripcion = dto.descripcion;
+        Ok(habitacion.clone())
+    }
+
+    async fn delete_habitacion(&self, id: i32) -> Result<(), AppError> {
+        let mut habitaciones = self.habitaciones.write().map_err(poisoned)?;
+        habitaciones.remove(&id).map(|_| ()).ok_or(AppError::NotFoundError)
+    }
+}
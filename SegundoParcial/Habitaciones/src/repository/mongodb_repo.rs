@@ -1,7 +1,10 @@
 use crate::{
     error::AppError,
+    metrics,
     model::{Habitacion, CreateHabitacionDto, UpdateHabitacionDto},
+    repository::habitacion_repository::HabitacionRepository,
 };
+use async_trait::async_trait;
 use futures::stream::TryStreamExt;
 use mongodb::{
     bson::{doc, Document},
@@ -19,9 +22,19 @@ impl MongoRepo {
     pub fn new(db: Database) -> Self {
         MongoRepo { db }
     }
+}
+
+#[async_trait]
+impl HabitacionRepository for MongoRepo {
+    // Verificar que MongoDB responde (usado por /health/ready)
+    async fn ping(&self) -> Result<(), AppError> {
+        self.db.run_command(doc! {"ping": 1}, None).await?;
+        Ok(())
+    }
 
     // Obtener todas las habitaciones
-    pub async fn get_all_habitaciones(&self) -> Result<Vec<Habitacion>, AppError> {
+    async fn get_all_habitaciones(&self) -> Result<Vec<Habitacion>, AppError> {
+        let _timer = metrics::mongo_timer("get_all_habitaciones");
         let collection = self.db.collection::<Habitacion>(HABITACIONES_COLLECTION);
         let mut cursor = collection.find(None, None).await?;
         let mut habitaciones = Vec::new();
@@ -32,7 +45,8 @@ impl MongoRepo {
     }
 
     // Crear una habitacion con id autogenerado
-    pub async fn create_habitacion(&self, dto: CreateHabitacionDto) -> Result<Habitacion, AppError> {
+    async fn create_habitacion(&self, dto: CreateHabitacionDto) -> Result<Habitacion, AppError> {
+        let _timer = metrics::mongo_timer("create_habitacion");
         let collection = self.db.collection::<Habitacion>(HABITACIONES_COLLECTION);
 
         // Obtener el mayor id actual y sumarle 1
@@ -60,7 +74,8 @@ impl MongoRepo {
     }
 
     // Actualizar una habitacion (no crea nuevo registro)
-    pub async fn update_habitacion(&self, id: i32, dto: UpdateHabitacionDto) -> Result<Habitacion, AppError> {
+    async fn update_habitacion(&self, id: i32, dto: UpdateHabitacionDto) -> Result<Habitacion, AppError> {
+        let _timer = metrics::mongo_timer("update_habitacion");
         let collection = self.db.collection::<Habitacion>(HABITACIONES_COLLECTION);
         let filter = doc! {"id": id};
 
@@ -86,7 +101,8 @@ impl MongoRepo {
     }
 
     // Eliminar una habitacion
-    pub async fn delete_habitacion(&self, id: i32) -> Result<(), AppError> {
+    async fn delete_habitacion(&self, id: i32) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer("delete_habitacion");
         let collection = self.db.collection::<Habitacion>(HABITACIONES_COLLECTION);
         let filter = doc! {"id": id};
         let delete_result = collection.delete_one(filter, None).await?;
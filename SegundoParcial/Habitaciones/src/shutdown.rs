@@ -0,0 +1,168 @@
+use std::{
+    error::Error as StdError,
+    io,
+    pin::Pin,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Server, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::Bytes,
+    Error,
+};
+
+// Cada cuánto se revisa si ya terminaron las solicitudes en curso durante el apagado
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Solicitudes en curso y cómo terminaron, para el resumen del apagado
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static ABORTED: AtomicU64 = AtomicU64::new(0);
+
+// Cuenta una solicitud mientras se atiende; si se descarta sin terminar
+// (se venció el plazo del apagado) se cuenta como cancelada
+struct InFlightGuard {
+    finished: bool,
+}
+
+impl InFlightGuard {
+    fn start() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { finished: false }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        let outcome = if self.finished { &COMPLETED } else { &ABORTED };
+        outcome.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// Cuerpo de la respuesta que mantiene la solicitud en curso hasta terminar de enviarse
+struct TrackedBody {
+    body: BoxBody,
+    _guard: InFlightGuard,
+}
+
+impl MessageBody for TrackedBody {
+    type Error = Box<dyn StdError>;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Pin::new(&mut self.get_mut().body).poll_next(cx)
+    }
+}
+
+// Middleware que lleva la cuenta de las solicitudes en curso
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut guard = InFlightGuard::start();
+    let response = next.call(req).await;
+    // Ya hay respuesta (o error); si se corta el envío del cuerpo no se cuenta como cancelada
+    guard.finished = true;
+    Ok(response?.map_body(|_, body| TrackedBody { body: body.boxed(), _guard: guard }))
+}
+
+// Espera SIGTERM (`docker stop`, redespliegues) o Ctrl+C
+async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("No se pudo escuchar SIGTERM");
+        tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+// Resumen del apagado del servidor HTTP
+pub struct ShutdownSummary {
+    signal: &'static str,
+    // Solicitudes en curso al recibir la señal
+    in_flight: usize,
+    // Solicitudes que terminaron durante el apagado
+    completed: u64,
+    // Solicitudes canceladas al vencerse el plazo del apagado
+    aborted: u64,
+    started: Instant,
+}
+
+impl ShutdownSummary {
+    // Registra el resumen; se llama después de cerrar las demás conexiones para incluir su duración
+    pub fn log(&self) {
+        tracing::info!(
+            signal = self.signal,
+            in_flight = self.in_flight,
+            completed = self.completed,
+            aborted = self.aborted,
+            elapsed_ms = self.started.elapsed().as_millis() as u64,
+            "Apagado completo"
+        );
+    }
+}
+
+// Atiende solicitudes hasta recibir una señal; luego deja de aceptar conexiones y espera a las
+// solicitudes en curso durante `timeout` como máximo (después se cancelan)
+pub async fn serve_until_signal(mut server: Server, timeout: Duration) -> io::Result<ShutdownSummary> {
+    let handle = server.handle();
+    let signal = tokio::select! {
+        result = &mut server => {
+            result?;
+            tracing::warn!("El servidor HTTP se detuvo sin recibir una señal");
+            let started = Instant::now();
+            return Ok(ShutdownSummary { signal: "ninguna", in_flight: 0, completed: 0, aborted: 0, started });
+        }
+        signal = wait_for_signal() => signal,
+    };
+
+    let in_flight = IN_FLIGHT.load(Ordering::SeqCst);
+    tracing::info!(signal, in_flight, "Señal recibida: se dejan de aceptar conexiones y se esperan las solicitudes en curso");
+    let (completed, aborted) = (COMPLETED.load(Ordering::SeqCst), ABORTED.load(Ordering::SeqCst));
+    let started = Instant::now();
+
+    // No se usa `stop(true)`: en actix-server un worker puede terminar antes de recibir la orden
+    // de detenerse (cuando el hilo que acepta conexiones se cierra primero) y descartar sus
+    // solicitudes en curso. Por eso se pausa la aceptación y la espera se hace aquí
+    let drain = async {
+        handle.pause().await;
+        let drained = tokio::time::timeout(timeout, async {
+            while IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+                tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!("Se venció el plazo del apagado; se cancelan las solicitudes en curso");
+        }
+        handle.stop(false).await;
+    };
+    // El servidor debe seguir atendiendo mientras se detiene
+    let ((), result) = tokio::join!(drain, server);
+    result?;
+
+    Ok(ShutdownSummary {
+        signal,
+        in_flight,
+        completed: COMPLETED.load(Ordering::SeqCst) - completed,
+        aborted: ABORTED.load(Ordering::SeqCst) - aborted,
+        started,
+    })
+}
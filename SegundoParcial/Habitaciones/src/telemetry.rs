@@ -0,0 +1,141 @@
+use std::{
+    fmt,
+    future::{ready, Ready},
+    time::Instant,
+};
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::{
+        header::{HeaderName, HeaderValue},
+        StatusCode,
+    },
+    middleware::Next,
+    Error, FromRequest, HttpMessage, HttpRequest,
+};
+use tracing::Instrument;
+
+use crate::config::LogFormat;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+// Encabezado con el que se recibe y se devuelve el ID de la solicitud
+const REQUEST_ID_HEADER: &str = "x-request-id";
+// Longitud máxima aceptada para un ID recibido del cliente o del proxy
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    // ID de la solicitud que se atiende en la tarea actual (lo usan las respuestas de error)
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+// Identificador que correlaciona los registros de una solicitud entre nginx y los servicios
+#[derive(Debug, Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    fn generate() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    // Reutiliza el ID recibido si es válido; si no, genera uno nuevo
+    fn from_headers(req: &ServiceRequest) -> Self {
+        req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+            .filter(|id| id.bytes().all(|b| b.is_ascii_graphic()))
+            .map(|id| RequestId(id.to_string()))
+            .unwrap_or_else(RequestId::generate)
+    }
+
+    // ID de la solicitud en curso, si la tarea actual atiende una
+    pub fn current() -> Option<RequestId> {
+        CURRENT_REQUEST_ID.try_with(RequestId::clone).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let id = req.extensions().get::<RequestId>().cloned();
+        ready(Ok(id.unwrap_or_else(RequestId::generate)))
+    }
+}
+
+// Configura los registros: JSON por defecto o texto legible con `log_format = "text"`.
+// Los mensajes de `log::` también pasan por `tracing` e incluyen el ID de la solicitud.
+pub fn init(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).with_span_list(false).init(),
+    }
+}
+
+// Middleware que asigna el ID de la solicitud, lo agrega a cada registro y lo devuelve en `X-Request-Id`
+pub async fn request_context(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = RequestId::from_headers(&req);
+    req.extensions_mut().insert(request_id.clone());
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+    );
+    let started = Instant::now();
+
+    let result = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.call(req))
+        .instrument(span.clone())
+        .await;
+    let header = HeaderValue::from_str(request_id.as_str()).ok();
+    let log_response = |status: StatusCode| {
+        span.in_scope(|| {
+            tracing::info!(
+                status = status.as_u16(),
+                latency_ms = started.elapsed().as_millis() as u64,
+                "Solicitud atendida"
+            )
+        })
+    };
+
+    match result {
+        Ok(mut res) => {
+            if let Some(value) = header {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            log_response(res.status());
+            Ok(res.map_into_boxed_body())
+        }
+        // Los errores de los middlewares internos (por ejemplo, la autenticación) se convierten en
+        // respuesta aquí, dentro del contexto de la solicitud, para que también lleven su ID
+        Err(err) => {
+            let mut response = CURRENT_REQUEST_ID.sync_scope(request_id.clone(), || err.error_response());
+            if let Some(value) = header {
+                response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            log_response(response.status());
+            Err(InternalError::from_response(err, response).into())
+        }
+    }
+}
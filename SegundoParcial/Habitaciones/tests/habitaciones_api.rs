@@ -0,0 +1,217 @@
+// Pruebas de integración de los endpoints de habitaciones, con el repositorio en memoria (sin MongoDB)
+
+use std::sync::Arc;
+
+use actix_http::Request;
+use actix_web::{
+    body::{self, MessageBody},
+    dev::{Service, ServiceResponse},
+    http::StatusCode,
+    middleware::from_fn,
+    test, web, App, Error,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rust_mongodb_crud::{
+    api::{self, habitaciones_api::Claims},
+    auth::JwtKey,
+    repository::{habitacion_repository::HabitacionRepository, memory_repo::MemoryRepo},
+    telemetry,
+};
+use serde_json::{json, Value};
+
+const JWT_SECRET: &str = "llave-de-prueba";
+
+// Aplicación con las mismas rutas que el servidor, sobre un repositorio en memoria vacío
+async fn app() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let repo: Arc<dyn HabitacionRepository> = Arc::new(MemoryRepo::new());
+    test::init_service(
+        App::new()
+            .wrap(from_fn(telemetry::request_context))
+            .app_data(web::Data::from(repo))
+            .app_data(web::Data::new(JwtKey::from_secret(JWT_SECRET)))
+            .service(web::scope("/api").configure(api::routes)),
+    )
+    .await
+}
+
+// Token firmado con `secret`, vigente por `ttl` segundos (negativo: vencido)
+fn token_with(secret: &str, ttl: i64) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        email: "recepcion@hotel.com".to_string(),
+        sub: 7,
+        iat: now as usize,
+        exp: (now + ttl) as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+}
+
+fn bearer() -> (&'static str, String) {
+    ("Authorization", format!("Bearer {}", token_with(JWT_SECRET, 3600)))
+}
+
+fn habitacion(numero: i32, estado: &str) -> Value {
+    json!({
+        "numero_habitacion": numero,
+        "tipo_habitacion": "doble",
+        "precio_noche": "80.00",
+        "estado": estado,
+        "descripcion": "Vista al mar",
+    })
+}
+
+// Envía la solicitud y devuelve el código y el cuerpo JSON (Null si está vacío).
+// Los errores de los middlewares (autenticación) también se convierten en respuesta.
+async fn call(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    req: Request,
+) -> (StatusCode, Value) {
+    let (status, bytes) = match app.call(req).await {
+        Ok(resp) => (resp.status(), body::to_bytes(resp.into_body()).await.unwrap_or_default()),
+        Err(err) => {
+            let resp = err.error_response();
+            (resp.status(), body::to_bytes(resp.into_body()).await.unwrap_or_default())
+        }
+    };
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap_or(Value::Null) };
+    (status, body)
+}
+
+async fn create(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    body: Value,
+) -> Value {
+    let req = test::TestRequest::post().uri("/api/habitaciones").insert_header(bearer()).set_json(body).to_request();
+    let (status, body) = call(app, req).await;
+    assert_eq!(status, StatusCode::CREATED);
+    body
+}
+
+#[actix_web::test]
+async fn routes_require_a_valid_token() {
+    let app = app().await;
+
+    let requests = [
+        test::TestRequest::get().uri("/api/habitaciones").to_request(),
+        test::TestRequest::post().uri("/api/habitaciones").set_json(habitacion(101, "disponible")).to_request(),
+        test::TestRequest::put().uri("/api/habitaciones/1").set_json(habitacion(101, "ocupada")).to_request(),
+        test::TestRequest::delete().uri("/api/habitaciones/1").to_request(),
+    ];
+    for req in requests {
+        let (status, _) = call(&app, req).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // Token mal formado, firmado con otra llave o vencido
+    for token in ["no-es-un-jwt".to_string(), token_with("otra-llave", 3600), token_with(JWT_SECRET, -3600)] {
+        let req = test::TestRequest::get()
+            .uri("/api/habitaciones")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+        let (status, _) = call(&app, req).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[actix_web::test]
+async fn crear_habitacion_assigns_consecutive_ids() {
+    let app = app().await;
+
+    let primera = create(&app, habitacion(101, "disponible")).await;
+    let segunda = create(&app, habitacion(102, "disponible")).await;
+
+    assert_eq!(primera["id"], 1);
+    assert_eq!(primera["numero_habitacion"], 101);
+    assert_eq!(segunda["id"], 2);
+}
+
+#[actix_web::test]
+async fn crear_habitacion_rejects_incomplete_body() {
+    let app = app().await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/habitaciones")
+        .insert_header(bearer())
+        .set_json(json!({ "numero_habitacion": 101 }))
+        .to_request();
+    let (status, _) = call(&app, req).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn listar_habitaciones_returns_created_rooms() {
+    let app = app().await;
+    let req = test::TestRequest::get().uri("/api/habitaciones").insert_header(bearer()).to_request();
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!([]));
+
+    create(&app, habitacion(101, "disponible")).await;
+    create(&app, habitacion(102, "ocupada")).await;
+
+    let req = test::TestRequest::get().uri("/api/habitaciones").insert_header(bearer()).to_request();
+    let (_, body) = call(&app, req).await;
+    let numeros: Vec<i64> = body.as_array().unwrap().iter().map(|h| h["numero_habitacion"].as_i64().unwrap()).collect();
+    assert_eq!(numeros, [101, 102]);
+}
+
+#[actix_web::test]
+async fn actualizar_habitacion_replaces_its_data() {
+    let app = app().await;
+    create(&app, habitacion(101, "disponible")).await;
+
+    let req = test::TestRequest::put()
+        .uri("/api/habitaciones/1")
+        .insert_header(bearer())
+        .set_json(habitacion(201, "ocupada"))
+        .to_request();
+    let (status, body) = call(&app, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["numero_habitacion"], 201);
+    assert_eq!(body["estado"], "ocupada");
+}
+
+#[actix_web::test]
+async fn actualizar_habitacion_rejects_unknown_and_invalid_ids() {
+    let app = app().await;
+
+    let req = test::TestRequest::put()
+        .uri("/api/habitaciones/99")
+        .insert_header(bearer())
+        .set_json(habitacion(101, "ocupada"))
+        .to_request();
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Habitación no encontrada");
+    assert!(body["request_id"].is_string());
+
+    let req = test::TestRequest::put()
+        .uri("/api/habitaciones/abc")
+        .insert_header(bearer())
+        .set_json(habitacion(101, "ocupada"))
+        .to_request();
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().starts_with("ID de habitación inválido"));
+}
+
+#[actix_web::test]
+async fn eliminar_habitacion_removes_it() {
+    let app = app().await;
+    create(&app, habitacion(101, "disponible")).await;
+
+    let req = test::TestRequest::delete().uri("/api/habitaciones/1").insert_header(bearer()).to_request();
+    let (status, _) = call(&app, req).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::delete().uri("/api/habitaciones/1").insert_header(bearer()).to_request();
+    let (status, _) = call(&app, req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::delete().uri("/api/habitaciones/uno").insert_header(bearer()).to_request();
+    let (status, _) = call(&app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,11 @@
+// Genera el código del servicio gRPC a partir de `proto/libros.proto`.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Usa el `protoc` incluido en el crate si el sistema no indica otro
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_build::configure()
+        .build_client(false) // Los clientes generan su propio código a partir del `.proto`
+        .compile_protos(&["proto/libros.proto"], &["proto"])?;
+    Ok(())
+}
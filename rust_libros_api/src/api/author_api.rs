@@ -0,0 +1,121 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+
+use crate::{
+    api::reference_api,
+    error::{AppError, ErrorResponse},
+    model::{Author, BookPage, BookQuery, CreateAuthorDto, UpdateAuthorDto},
+    repository::{book_repository::BookRepository, reference_repository::ReferenceRepository},
+};
+
+/// Endpoint para obtener todos los autores
+#[utoipa::path(
+    tag = "autores",
+    responses(
+        (status = 200, description = "Autores ordenados por nombre", body = Vec<Author>),
+    )
+)]
+#[get("/autores")]
+pub async fn get_all_authors(db: web::Data<dyn ReferenceRepository<Author>>) -> Result<HttpResponse, AppError> {
+    reference_api::get_all(db.get_ref()).await
+}
+
+/// Endpoint para obtener un autor por su ID
+#[utoipa::path(
+    tag = "autores",
+    params(("id" = String, Path, description = "ID del autor")),
+    responses(
+        (status = 200, description = "Autor", body = Author),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/autores/{id}")]
+pub async fn get_author(
+    db: web::Data<dyn ReferenceRepository<Author>>,
+    author_id: Path<String>, // ID del autor proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    reference_api::get(db.get_ref(), &author_id).await
+}
+
+/// Endpoint para obtener los libros de un autor con paginación, filtros y orden
+#[utoipa::path(
+    tag = "autores",
+    params(("id" = String, Path, description = "ID del autor"), BookQuery),
+    responses(
+        (status = 200, description = "Página de libros del autor", body = BookPage),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/autores/{id}/libros")]
+pub async fn get_author_books(
+    db: web::Data<dyn ReferenceRepository<Author>>,
+    books: web::Data<dyn BookRepository>,
+    author_id: Path<String>, // ID del autor proporcionado en la URL
+    query: Query<BookQuery>, // Parámetros de paginación, filtros y orden
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    reference_api::get_books(db.get_ref(), books.get_ref(), &author_id, query.into_inner(), &req).await
+}
+
+/// Endpoint para crear un nuevo autor
+#[utoipa::path(
+    tag = "autores",
+    request_body = CreateAuthorDto,
+    responses(
+        (status = 201, description = "Autor creado", body = Author),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[post("/autores")]
+pub async fn create_author(
+    db: web::Data<dyn ReferenceRepository<Author>>,
+    author_dto: Json<CreateAuthorDto>, // Datos del autor en formato JSON
+) -> Result<HttpResponse, AppError> {
+    reference_api::create(db.get_ref(), author_dto.into_inner()).await
+}
+
+/// Endpoint para actualizar un autor (el nuevo nombre se copia en sus libros)
+#[utoipa::path(
+    tag = "autores",
+    params(("id" = String, Path, description = "ID del autor")),
+    request_body = UpdateAuthorDto,
+    responses(
+        (status = 200, description = "Autor actualizado", body = Author),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[put("/autores/{id}")]
+pub async fn update_author(
+    db: web::Data<dyn ReferenceRepository<Author>>,
+    author_id: Path<String>, // ID del autor proporcionado en la URL
+    author_dto: Json<UpdateAuthorDto>, // Datos actualizados del autor en formato JSON
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    reference_api::update(db.get_ref(), &author_id, author_dto.into_inner(), &req).await
+}
+
+/// Endpoint para eliminar un autor (solo si ningún libro lo referencia)
+#[utoipa::path(
+    tag = "autores",
+    params(("id" = String, Path, description = "ID del autor")),
+    responses(
+        (status = 204, description = "Autor eliminado"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[delete("/autores/{id}")]
+pub async fn delete_author(
+    db: web::Data<dyn ReferenceRepository<Author>>,
+    author_id: Path<String>, // ID del autor proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    reference_api::delete(db.get_ref(), &author_id).await
+}
@@ -1,66 +1,421 @@
 use actix_web::{
-    delete, get, post, put,
-    web::{self, Json, Path},
-    HttpResponse,
+    delete, get,
+    http::header::{ETag, EntityTag, IfMatch, IfNoneMatch},
+    post, put,
+    web::{self, Json, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse,
 };
 use mongodb::bson::oid::ObjectId;
+use sha2::{Digest, Sha256};
+use validator::Validate;
 
 use crate::{
-    error::AppError,
-    model::{CreateBookDto, UpdateBookDto},
-    repository::mongodb_repo::MongoRepo,
+    error::{AppError, ErrorResponse},
+    history, isbn,
+    model::{
+        Book, BookHistoryEntry, BookListOptions, BookPage, BookQuery, CreateBookDto, SearchQuery, SearchResults,
+        UpdateBookDto,
+    },
+    repository::book_repository::BookRepository,
 };
 
-// Endpoint para obtener todos los libros
+/// Endpoint para obtener los libros con paginación, filtros y orden
+#[utoipa::path(
+    tag = "libros",
+    params(BookQuery),
+    responses(
+        (status = 200, description = "Página de libros", body = BookPage),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+    )
+)]
 #[get("/libro")]
-pub async fn get_all_books(db: web::Data<MongoRepo>) -> Result<HttpResponse, AppError> {
-    // Llama al repositorio para obtener todos los libros
-    let books = db.get_all_books().await?;
-    // Devuelve los libros en formato JSON con un código de estado 200 (OK)
-    Ok(HttpResponse::Ok().json(books))
+pub async fn get_all_books(
+    db: web::Data<dyn BookRepository>,
+    query: Query<BookQuery>, // Parámetros de paginación, filtros y orden
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+    // Valida los parámetros antes de consultar el repositorio
+    let options = query.to_options()?;
+
+    // Llama al repositorio para obtener la página solicitada
+    let page = book_page(db.get_ref(), query, options, &req).await?;
+
+    // Devuelve la página en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Obtiene una página de libros y arma el enlace a la siguiente conservando los filtros
+pub(crate) async fn book_page(
+    db: &dyn BookRepository,
+    query: BookQuery,
+    options: BookListOptions,
+    req: &HttpRequest,
+) -> Result<BookPage, AppError> {
+    let list = db.get_all_books(&options).await?;
+
+    let next = if list.has_more {
+        let mut next_query = query;
+        next_query.per_page = Some(options.limit);
+        if options.after.is_some() {
+            // Paginación por cursor: el siguiente cursor es el último libro de esta página
+            next_query.after = list.books.last().and_then(|book| book.id).map(|id| id.to_hex());
+        } else {
+            next_query.page = Some(options.skip / options.limit + 2);
+        }
+        let query_string = serde_urlencoded::to_string(&next_query).map_err(|_| AppError::InternalError)?;
+        Some(format!("{}?{}", req.path(), query_string))
+    } else {
+        None
+    };
+
+    Ok(BookPage {
+        page: options.after.is_none().then_some(options.skip / options.limit + 1),
+        per_page: options.limit,
+        total: list.total,
+        data: list.books,
+        next,
+    })
 }
 
-// Endpoint para crear un nuevo libro
+/// Endpoint para buscar libros por texto en el título, el autor y la descripción
+#[utoipa::path(
+    tag = "libros",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Resultados ordenados por relevancia", body = SearchResults),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+    )
+)]
+#[get("/libro/search")]
+pub async fn search_books(
+    db: web::Data<dyn BookRepository>,
+    query: Query<SearchQuery>, // Texto a buscar y límite de resultados
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+    let limit = query.limit()?;
+
+    // Llama al repositorio para buscar los libros más relevantes
+    let hits = db.search_books(query.q.trim(), limit).await?;
+
+    // Devuelve los resultados en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(SearchResults {
+        query: query.q,
+        total: hits.len(),
+        data: hits,
+    }))
+}
+
+/// Endpoint para obtener un libro por su ID
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 200, description = "Libro con su `ETag`", body = Book),
+        (status = 304, description = "El cliente ya tiene esta versión (`If-None-Match`)"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/libro/{id}")]
+pub async fn get_book(
+    db: web::Data<dyn BookRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    // Llama al repositorio para obtener el libro
+    let book = db.get_book(id).await?;
+    let etag = book_etag(&book)?;
+
+    // Si el cliente ya tiene esta versión, responde 304 (Not Modified) sin cuerpo
+    if is_not_modified(&etag, &req) {
+        return Ok(HttpResponse::NotModified().insert_header(ETag(etag)).finish());
+    }
+
+    // Devuelve el libro en formato JSON con su ETag y un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(book))
+}
+
+/// Endpoint para obtener un libro por su ISBN (acepta ISBN-10 o ISBN-13)
+#[utoipa::path(
+    tag = "libros",
+    params(("isbn" = String, Path, description = "ISBN-10 o ISBN-13, con o sin guiones")),
+    responses(
+        (status = 200, description = "Libro con su `ETag`", body = Book),
+        (status = 304, description = "El cliente ya tiene esta versión (`If-None-Match`)"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/libro/isbn/{isbn}")]
+pub async fn get_book_by_isbn(
+    db: web::Data<dyn BookRepository>,
+    isbn: Path<String>, // ISBN proporcionado en la URL
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Normaliza el ISBN recibido a su forma ISBN-13
+    let isbn = isbn::normalize(&isbn.into_inner())
+        .ok_or_else(|| AppError::InvalidIDError("ISBN inválido".to_string()))?;
+
+    // Llama al repositorio para obtener el libro
+    let book = db.get_book_by_isbn(&isbn).await?;
+    let etag = book_etag(&book)?;
+
+    // Si el cliente ya tiene esta versión, responde 304 (Not Modified) sin cuerpo
+    if is_not_modified(&etag, &req) {
+        return Ok(HttpResponse::NotModified().insert_header(ETag(etag)).finish());
+    }
+
+    // Devuelve el libro en formato JSON con su ETag y un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(book))
+}
+
+/// Endpoint para crear un nuevo libro
+#[utoipa::path(
+    tag = "libros",
+    request_body = CreateBookDto,
+    responses(
+        (status = 201, description = "Libro creado", body = Book),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
 #[post("/libro")]
 pub async fn create_book(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn BookRepository>,
     book_dto: Json<CreateBookDto>, // Datos del libro en formato JSON
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
+    let book_dto = book_dto.into_inner();
+    // Valida los datos antes de llegar al repositorio
+    book_dto.validate()?;
+
     // Llama al repositorio para crear un nuevo libro con los datos proporcionados
-    let created_book = db.create_book(book_dto.into_inner()).await?;
+    let created_book = db.create_book(book_dto, &history::actor(&req)).await?;
+    let etag = book_etag(&created_book)?;
     // Devuelve el libro creado en formato JSON con un código de estado 201 (Created)
-    Ok(HttpResponse::Created().json(created_book))
+    Ok(HttpResponse::Created().insert_header(ETag(etag)).json(created_book))
 }
 
-// Endpoint para actualizar un libro existente
+/// Endpoint para actualizar un libro existente
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro")),
+    request_body = UpdateBookDto,
+    responses(
+        (status = 200, description = "Libro actualizado", body = Book),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 412, description = "`If-Match` no coincide con la versión actual", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
 #[put("/libro/{id}")]
 pub async fn update_book(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn BookRepository>,
     book_id: Path<String>, // ID del libro proporcionado en la URL
     book_dto: Json<UpdateBookDto>, // Datos actualizados del libro en formato JSON
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
     // Convierte el ID proporcionado en un ObjectId de MongoDB
     let id = ObjectId::parse_str(book_id.into_inner())
         .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
-    
-    // Llama al repositorio para actualizar el libro con el ID y los datos proporcionados
-    let updated_book = db.update_book(id, book_dto.into_inner()).await?;
+
+    let book_dto = book_dto.into_inner();
+    // Valida los datos antes de llegar al repositorio
+    book_dto.validate()?;
+
+    // Rechaza la escritura si el cliente editó una versión desactualizada
+    let current = db.get_book(id).await?;
+    let expected_version = check_if_match(&current, &req)?;
+
+    // Llama al repositorio para actualizar el libro; falla con 412 si otra escritura se adelantó
+    let updated_book = db.update_book(id, book_dto, expected_version, &history::actor(&req)).await?;
+    let etag = book_etag(&updated_book)?;
     // Devuelve el libro actualizado en formato JSON con un código de estado 200 (OK)
-    Ok(HttpResponse::Ok().json(updated_book))
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(updated_book))
 }
 
-// Endpoint para eliminar un libro existente
+/// Endpoint para enviar un libro a la papelera (se puede restaurar hasta que se purgue)
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 204, description = "Libro enviado a la papelera"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 412, description = "`If-Match` no coincide con la versión actual", body = ErrorResponse),
+    )
+)]
 #[delete("/libro/{id}")]
 pub async fn delete_book(
-    db: web::Data<MongoRepo>,
+    db: web::Data<dyn BookRepository>,
     book_id: Path<String>, // ID del libro proporcionado en la URL
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
     // Convierte el ID proporcionado en un ObjectId de MongoDB
     let id = ObjectId::parse_str(book_id.into_inner())
         .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
-    
-    // Llama al repositorio para eliminar el libro con el ID proporcionado
-    db.delete_book(id).await?;
+
+    // Rechaza la eliminación si el cliente tiene una versión desactualizada
+    let current = db.get_book(id).await?;
+    let expected_version = check_if_match(&current, &req)?;
+
+    // Llama al repositorio para enviar el libro a la papelera
+    db.delete_book(id, expected_version, &history::actor(&req)).await?;
     // Devuelve una respuesta vacía con un código de estado 204 (No Content)
     Ok(HttpResponse::NoContent().finish())
-}
\ No newline at end of file
+}
+
+/// Endpoint para obtener los libros de la papelera
+#[utoipa::path(
+    tag = "libros",
+    responses(
+        (status = 200, description = "Libros en la papelera", body = Vec<Book>),
+    )
+)]
+#[get("/libro/trash")]
+pub async fn get_trash(db: web::Data<dyn BookRepository>) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener los libros eliminados
+    let books = db.get_deleted_books().await?;
+    // Devuelve los libros en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(books))
+}
+
+/// Endpoint para restaurar un libro de la papelera
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 200, description = "Libro restaurado", body = Book),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[post("/libro/{id}/restore")]
+pub async fn restore_book(
+    db: web::Data<dyn BookRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    // Llama al repositorio para restaurar el libro
+    let restored_book = db.restore_book(id, &history::actor(&req)).await?;
+    let etag = book_etag(&restored_book)?;
+    // Devuelve el libro restaurado en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(restored_book))
+}
+
+/// Endpoint para obtener el historial de cambios de un libro (incluye los libros en la papelera)
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 200, description = "Historial del más antiguo al más reciente", body = Vec<BookHistoryEntry>),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/libro/{id}/history")]
+pub async fn get_book_history(
+    db: web::Data<dyn BookRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    // Llama al repositorio para obtener el historial del libro
+    let entries = db.get_history(id).await?;
+    if entries.is_empty() {
+        db.get_book(id).await?; // Sin historial: 404 si el libro no existe (lista vacía si es anterior al historial)
+    }
+    // Devuelve el historial en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Endpoint para revertir un libro a la versión registrada en una entrada de su historial
+#[utoipa::path(
+    tag = "libros",
+    params(("id" = String, Path, description = "ID del libro"), ("entry_id" = String, Path, description = "ID de la entrada del historial")),
+    responses(
+        (status = 200, description = "Libro revertido", body = Book),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 412, description = "`If-Match` no coincide con la versión actual", body = ErrorResponse),
+    )
+)]
+#[post("/libro/{id}/history/{entry_id}/revert")]
+pub async fn revert_book(
+    db: web::Data<dyn BookRepository>,
+    path: Path<(String, String)>, // ID del libro y de la entrada del historial
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let (book_id, entry_id) = path.into_inner();
+    // Convierte los IDs proporcionados en ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id).map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+    let entry_id = ObjectId::parse_str(entry_id)
+        .map_err(|_| AppError::InvalidIDError("ID de historial inválido".to_string()))?;
+
+    // Busca la versión a restaurar entre las entradas del libro
+    let entry = db
+        .get_history(id)
+        .await?
+        .into_iter()
+        .find(|entry| entry.id == Some(entry_id))
+        .ok_or(AppError::NotFoundError)?;
+
+    // Rechaza la escritura si el cliente tiene una versión desactualizada
+    let current = db.get_book(id).await?;
+    let expected_version = check_if_match(&current, &req)?;
+
+    // Llama al repositorio para reemplazar los campos por los de esa versión
+    let reverted_book = db.replace_book(id, &entry.snapshot, expected_version, &history::actor(&req)).await?;
+    let etag = book_etag(&reverted_book)?;
+    // Devuelve el libro revertido en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(reverted_book))
+}
+
+// Calcula un ETag fuerte a partir del contenido serializado del libro
+pub(crate) fn book_etag(book: &Book) -> Result<EntityTag, AppError> {
+    let body = serde_json::to_vec(book).map_err(|_| AppError::InternalError)?;
+    let digest = Sha256::digest(&body);
+    Ok(EntityTag::new_strong(hex::encode(&digest[..16])))
+}
+
+// Indica si el encabezado `If-None-Match` coincide con el ETag (el cliente ya tiene esta versión)
+pub(crate) fn is_not_modified(etag: &EntityTag, req: &HttpRequest) -> bool {
+    match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        None => false,
+    }
+}
+
+// Verifica el encabezado `If-Match` contra la versión actual del libro y devuelve la versión a exigir
+// en la escritura, para que el repositorio la rechace si otra solicitud modificó el libro mientras tanto
+pub(crate) fn check_if_match(book: &Book, req: &HttpRequest) -> Result<Option<i64>, AppError> {
+    match req.get_header::<IfMatch>() {
+        // Sin encabezado, o con `*`, la escritura no está condicionada a una versión concreta
+        None | Some(IfMatch::Any) => Ok(None),
+        Some(IfMatch::Items(tags)) => {
+            let current = book_etag(book)?;
+            if tags.iter().any(|tag| tag.strong_eq(&current)) {
+                Ok(Some(book.version))
+            } else {
+                Err(AppError::PreconditionFailed)
+            }
+        }
+    }
+}
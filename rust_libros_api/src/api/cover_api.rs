@@ -0,0 +1,226 @@
+use std::time::SystemTime;
+
+use actix_multipart::Multipart;
+use actix_web::{
+    delete, get,
+    http::header::{CacheControl, CacheDirective, ETag, EntityTag, HttpDate, LastModified},
+    put,
+    web::{self, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use futures::StreamExt;
+use mongodb::bson::oid::ObjectId;
+use utoipa::ToSchema;
+
+use crate::{
+    api::book_api::{book_etag, check_if_match, is_not_modified},
+    covers::{self, MAX_COVER_BYTES, THUMBNAIL_CONTENT_TYPE},
+    error::{AppError, ErrorResponse},
+    history,
+    model::{Book, CoverQuery, CoverSize},
+    repository::{book_repository::BookRepository, cover_repository::CoverRepository},
+};
+
+const COVER_FIELD: &str = "portada"; // Campo del formulario multipart con la imagen.
+const COVER_MAX_AGE: u32 = 24 * 60 * 60; // Segundos que el cliente puede usar la imagen sin revalidarla.
+
+// Formulario de subida de la portada (solo describe la carga en el documento OpenAPI).
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct CoverUpload {
+    #[schema(value_type = String, format = Binary)]
+    portada: Vec<u8>, // Imagen JPEG, PNG o WebP de hasta 5 MB.
+}
+
+// Bytes de la imagen servida (solo para el documento OpenAPI).
+#[derive(ToSchema)]
+#[schema(value_type = String, format = Binary)]
+#[allow(dead_code)]
+pub struct CoverImage(Vec<u8>);
+
+/// Endpoint para subir o reemplazar la portada de un libro (formulario multipart con el campo `portada`)
+#[utoipa::path(
+    tag = "portadas",
+    params(("id" = String, Path, description = "ID del libro")),
+    request_body(content = CoverUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Libro con los datos de su portada", body = Book),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 412, description = "`If-Match` no coincide con la versión actual", body = ErrorResponse),
+        (status = 413, description = "Imagen demasiado grande", body = ErrorResponse),
+        (status = 415, description = "Tipo de imagen no soportado", body = ErrorResponse),
+    )
+)]
+#[put("/libro/{id}/portada")]
+pub async fn upload_cover(
+    db: web::Data<dyn BookRepository>,
+    covers: web::Data<dyn CoverRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+    mut payload: Multipart, // Formulario con la imagen
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    // Rechaza la escritura si el cliente editó una versión desactualizada
+    let current = db.get_book(id).await?;
+    let expected_version = check_if_match(&current, &req)?;
+
+    // Lee la imagen y genera las miniaturas fuera del hilo del servidor
+    let bytes = read_cover_field(&mut payload).await?;
+    let processed = web::block(move || covers::process(bytes))
+        .await
+        .map_err(|_| AppError::InternalError)??;
+
+    // Guarda las imágenes con las claves de su huella antes de asociarlas al libro, así las de la portada actual
+    // siguen disponibles si la actualización falla
+    let cover = covers::save_images(covers.get_ref(), id, processed).await?;
+    let (updated_book, previous) = match db
+        .set_cover(id, Some(cover.clone()), expected_version, &history::actor(&req))
+        .await
+    {
+        Ok(result) => result,
+        Err(err) => {
+            // Si otra subida no asoció la misma imagen al libro, las imágenes nuevas quedan huérfanas
+            let in_use = matches!(db.get_book(id).await, Ok(book) if book.portada.as_ref().is_some_and(|portada| portada.hash == cover.hash));
+            if !in_use {
+                covers::delete_images(covers.get_ref(), id, &cover).await;
+            }
+            return Err(err);
+        }
+    };
+    // Las imágenes de la portada reemplazada ya no se sirven (salvo que sea la misma imagen)
+    if let Some(previous) = previous.filter(|previous| previous.hash != cover.hash) {
+        covers::delete_images(covers.get_ref(), id, &previous).await;
+    }
+
+    let etag = book_etag(&updated_book)?;
+    // Devuelve el libro con los datos de su portada y un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(updated_book))
+}
+
+/// Endpoint para obtener la portada de un libro (`?size=original|medium|thumb`)
+#[utoipa::path(
+    tag = "portadas",
+    params(("id" = String, Path, description = "ID del libro"), CoverQuery),
+    responses(
+        (status = 200, description = "Imagen de la portada (las miniaturas son JPEG)", content((CoverImage = "image/jpeg"), (CoverImage = "image/png"), (CoverImage = "image/webp"))),
+        (status = 304, description = "El cliente ya tiene esta imagen (`If-None-Match`)"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/libro/{id}/portada")]
+pub async fn get_cover(
+    db: web::Data<dyn BookRepository>,
+    covers: web::Data<dyn CoverRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+    query: Query<CoverQuery>, // Tamaño solicitado
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    // Un libro sin portada responde 404 (Not Found)
+    let cover = db.get_book(id).await?.portada.ok_or(AppError::NotFoundError)?;
+    let size = query.size;
+
+    // El ETag cambia con cada imagen subida, así que el cliente puede revalidar sin descargarla
+    let etag = EntityTag::new_strong(format!("{}-{}", cover.hash, size.name()));
+    let last_modified = LastModified(HttpDate::from(SystemTime::from(cover.actualizada_en.to_chrono())));
+    let cache_control = CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(COVER_MAX_AGE)]);
+    if is_not_modified(&etag, &req) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header(cache_control)
+            .finish());
+    }
+
+    let content_type = match size {
+        CoverSize::Original => cover.content_type.clone(),
+        _ => THUMBNAIL_CONTENT_TYPE.to_string(),
+    };
+    // Las portadas subidas antes de incluir la huella en la clave siguen guardadas con la clave anterior
+    let image = match covers.load_image(&size.key(id, &cover)).await {
+        Err(AppError::NotFoundError) => covers.load_image(&size.legacy_key(id)).await?,
+        result => result?,
+    };
+
+    // Devuelve la imagen con sus encabezados de caché y un código de estado 200 (OK)
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ETag(etag))
+        .insert_header(last_modified)
+        .insert_header(cache_control)
+        .body(image))
+}
+
+/// Endpoint para quitar la portada de un libro
+#[utoipa::path(
+    tag = "portadas",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 204, description = "Portada eliminada"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 412, description = "`If-Match` no coincide con la versión actual", body = ErrorResponse),
+    )
+)]
+#[delete("/libro/{id}/portada")]
+pub async fn delete_cover(
+    db: web::Data<dyn BookRepository>,
+    covers: web::Data<dyn CoverRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Convierte el ID proporcionado en un ObjectId de MongoDB
+    let id = ObjectId::parse_str(book_id.into_inner())
+        .map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))?;
+
+    let current = db.get_book(id).await?;
+    if current.portada.is_none() {
+        return Err(AppError::NotFoundError);
+    }
+    let expected_version = check_if_match(&current, &req)?;
+
+    // Primero se desasocia la portada del libro y luego se eliminan sus imágenes
+    let (_, previous) = db.set_cover(id, None, expected_version, &history::actor(&req)).await?;
+    if let Some(previous) = previous {
+        covers::delete_images(covers.get_ref(), id, &previous).await;
+    }
+    // Devuelve una respuesta vacía con un código de estado 204 (No Content)
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Lee el campo `portada` del formulario sin superar el tamaño máximo permitido
+async fn read_cover_field(payload: &mut Multipart) -> Result<Vec<u8>, AppError> {
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|err| AppError::InvalidPayloadError(err.to_string()))?;
+        if field.name() != Some(COVER_FIELD) {
+            continue; // Los demás campos se ignoran
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|err| AppError::InvalidPayloadError(err.to_string()))?;
+            if bytes.len() + chunk.len() > MAX_COVER_BYTES {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "La portada no puede superar los {} MB",
+                    MAX_COVER_BYTES / (1024 * 1024)
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        if bytes.is_empty() {
+            return Err(AppError::InvalidPayloadError("La portada está vacía".to_string()));
+        }
+        return Ok(bytes);
+    }
+    Err(AppError::InvalidPayloadError(format!(
+        "Falta el campo `{}` con la imagen de portada",
+        COVER_FIELD
+    )))
+}
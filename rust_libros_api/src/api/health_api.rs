@@ -0,0 +1,20 @@
+use actix_web::{get, web, HttpResponse};
+use serde_json::json;
+
+use crate::health::{CheckStatus, Readiness};
+
+// Endpoint de vida: responde mientras el proceso pueda atender solicitudes
+#[get("/health/live")]
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "up" }))
+}
+
+// Endpoint de disponibilidad: verifica las dependencias (MongoDB, almacén de portadas)
+#[get("/health/ready")]
+pub async fn ready(readiness: web::Data<Readiness>) -> HttpResponse {
+    let report = readiness.report().await;
+    match report.status {
+        CheckStatus::Up => HttpResponse::Ok().json(report),
+        CheckStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
@@ -0,0 +1,212 @@
+use actix_web::{
+    delete, get, post,
+    web::{self, Json, Path},
+    HttpResponse,
+};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use validator::Validate;
+
+use crate::{
+    error::{AppError, ErrorResponse},
+    model::{CreateHoldDto, CreateLoanDto, Hold, Loan},
+    repository::loan_repository::LoanRepository,
+};
+
+/// Endpoint para prestar un libro a un socio
+#[utoipa::path(
+    tag = "prestamos",
+    request_body = CreateLoanDto,
+    responses(
+        (status = 201, description = "Préstamo creado", body = Loan),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[post("/prestamos")]
+pub async fn checkout_book(
+    db: web::Data<dyn LoanRepository>,
+    loan_dto: Json<CreateLoanDto>, // Libro y socio en formato JSON
+) -> Result<HttpResponse, AppError> {
+    let loan_dto = loan_dto.into_inner();
+    // Valida los datos antes de llegar al repositorio
+    loan_dto.validate()?;
+
+    // Llama al repositorio para registrar el préstamo (aplica el límite por socio y las copias disponibles)
+    let loan = db.checkout(parse_id(&loan_dto.libro_id)?, loan_dto.socio_id.trim()).await?;
+    // Devuelve el préstamo creado en formato JSON con un código de estado 201 (Created)
+    Ok(HttpResponse::Created().json(loan))
+}
+
+/// Endpoint para obtener los préstamos vencidos
+#[utoipa::path(
+    tag = "prestamos",
+    responses(
+        (status = 200, description = "Préstamos activos vencidos", body = Vec<Loan>),
+    )
+)]
+#[get("/prestamos/vencidos")]
+pub async fn get_overdue_loans(db: web::Data<dyn LoanRepository>) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener los préstamos activos con el vencimiento ya pasado
+    let loans = db.get_overdue_loans(DateTime::now()).await?;
+    // Devuelve los préstamos en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(loans))
+}
+
+/// Endpoint para obtener un préstamo por su ID
+#[utoipa::path(
+    tag = "prestamos",
+    params(("id" = String, Path, description = "ID del préstamo")),
+    responses(
+        (status = 200, description = "Préstamo", body = Loan),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/prestamos/{id}")]
+pub async fn get_loan(
+    db: web::Data<dyn LoanRepository>,
+    loan_id: Path<String>, // ID del préstamo proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener el préstamo
+    let loan = db.get_loan(parse_id(&loan_id)?).await?;
+    // Devuelve el préstamo en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(loan))
+}
+
+/// Endpoint para registrar la devolución de un préstamo
+#[utoipa::path(
+    tag = "prestamos",
+    params(("id" = String, Path, description = "ID del préstamo")),
+    responses(
+        (status = 200, description = "Préstamo devuelto", body = Loan),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[post("/prestamos/{id}/devolucion")]
+pub async fn return_loan(
+    db: web::Data<dyn LoanRepository>,
+    loan_id: Path<String>, // ID del préstamo proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para registrar la devolución (la copia pasa a la cola de reservas si la hay)
+    let loan = db.return_loan(parse_id(&loan_id)?).await?;
+    // Devuelve el préstamo devuelto en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(loan))
+}
+
+/// Endpoint para renovar un préstamo
+#[utoipa::path(
+    tag = "prestamos",
+    params(("id" = String, Path, description = "ID del préstamo")),
+    responses(
+        (status = 200, description = "Préstamo renovado", body = Loan),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[post("/prestamos/{id}/renovacion")]
+pub async fn renew_loan(
+    db: web::Data<dyn LoanRepository>,
+    loan_id: Path<String>, // ID del préstamo proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para extender el vencimiento del préstamo
+    let loan = db.renew_loan(parse_id(&loan_id)?).await?;
+    // Devuelve el préstamo renovado en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(loan))
+}
+
+/// Endpoint para obtener los préstamos activos de un socio
+#[utoipa::path(
+    tag = "prestamos",
+    params(("socio_id" = String, Path, description = "Identificador del socio")),
+    responses(
+        (status = 200, description = "Préstamos activos del socio", body = Vec<Loan>),
+    )
+)]
+#[get("/socios/{socio_id}/prestamos")]
+pub async fn get_member_loans(
+    db: web::Data<dyn LoanRepository>,
+    member_id: Path<String>, // Identificador del socio proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener los préstamos del socio
+    let loans = db.get_member_loans(member_id.trim()).await?;
+    // Devuelve los préstamos en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(loans))
+}
+
+/// Endpoint para reservar un libro sin copias disponibles
+#[utoipa::path(
+    tag = "reservas",
+    request_body = CreateHoldDto,
+    responses(
+        (status = 201, description = "Reserva creada", body = Hold),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[post("/reservas")]
+pub async fn place_hold(
+    db: web::Data<dyn LoanRepository>,
+    hold_dto: Json<CreateHoldDto>, // Libro y socio en formato JSON
+) -> Result<HttpResponse, AppError> {
+    let hold_dto = hold_dto.into_inner();
+    // Valida los datos antes de llegar al repositorio
+    hold_dto.validate()?;
+
+    // Llama al repositorio para poner al socio en la cola de reservas
+    let hold = db.place_hold(parse_id(&hold_dto.libro_id)?, hold_dto.socio_id.trim()).await?;
+    // Devuelve la reserva creada en formato JSON con un código de estado 201 (Created)
+    Ok(HttpResponse::Created().json(hold))
+}
+
+/// Endpoint para cancelar una reserva
+#[utoipa::path(
+    tag = "reservas",
+    params(("id" = String, Path, description = "ID de la reserva")),
+    responses(
+        (status = 200, description = "Reserva cancelada", body = Hold),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[delete("/reservas/{id}")]
+pub async fn cancel_hold(
+    db: web::Data<dyn LoanRepository>,
+    hold_id: Path<String>, // ID de la reserva proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para cancelar la reserva
+    let hold = db.cancel_hold(parse_id(&hold_id)?).await?;
+    // Devuelve la reserva cancelada en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(hold))
+}
+
+/// Endpoint para obtener la cola de reservas de un libro
+#[utoipa::path(
+    tag = "reservas",
+    params(("id" = String, Path, description = "ID del libro")),
+    responses(
+        (status = 200, description = "Cola de reservas pendientes", body = Vec<Hold>),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/libro/{id}/reservas")]
+pub async fn get_book_holds(
+    db: web::Data<dyn LoanRepository>,
+    book_id: Path<String>, // ID del libro proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener las reservas pendientes en orden de llegada
+    let holds = db.get_book_holds(parse_id(&book_id)?).await?;
+    // Devuelve la cola en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(holds))
+}
+
+// Convierte el ID proporcionado en un ObjectId de MongoDB
+fn parse_id(id: &str) -> Result<ObjectId, AppError> {
+    ObjectId::parse_str(id).map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))
+}
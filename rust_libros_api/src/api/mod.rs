@@ -1,2 +1,51 @@
+use actix_web::web;
 
-pub mod book_api;
\ No newline at end of file
+pub mod author_api;
+pub mod book_api;
+pub mod cover_api;
+pub mod health_api;
+pub mod loan_api;
+pub mod publisher_api;
+pub mod reference_api;
+pub mod transfer_api;
+
+// Registra los endpoints de la API; el servidor los monta bajo `/api` (las pruebas de integración también).
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(book_api::get_all_books) // Endpoint para obtener todos los libros.
+        .service(book_api::search_books) // Endpoint para buscar libros (antes de `/libro/{id}`).
+        .service(transfer_api::export_books) // Endpoint para exportar el catálogo (antes de `/libro/{id}`).
+        .service(book_api::get_trash) // Endpoint para ver la papelera (antes de `/libro/{id}`).
+        .service(transfer_api::import_books) // Endpoint para importar libros en lote.
+        .service(book_api::get_book) // Endpoint para obtener un libro por su ID.
+        .service(book_api::get_book_by_isbn) // Endpoint para obtener un libro por su ISBN.
+        .service(book_api::create_book) // Endpoint para crear un libro.
+        .service(book_api::update_book) // Endpoint para actualizar un libro.
+        .service(book_api::delete_book) // Endpoint para enviar un libro a la papelera.
+        .service(book_api::restore_book) // Endpoint para restaurar un libro de la papelera.
+        .service(book_api::get_book_history) // Endpoint para ver el historial de cambios de un libro.
+        .service(book_api::revert_book) // Endpoint para revertir un libro a una versión anterior.
+        .service(cover_api::upload_cover) // Endpoint para subir la portada de un libro.
+        .service(cover_api::get_cover) // Endpoint para obtener la portada de un libro o sus miniaturas.
+        .service(cover_api::delete_cover) // Endpoint para quitar la portada de un libro.
+        .service(author_api::get_all_authors) // Endpoint para obtener todos los autores.
+        .service(author_api::get_author) // Endpoint para obtener un autor por su ID.
+        .service(author_api::get_author_books) // Endpoint para obtener los libros de un autor.
+        .service(author_api::create_author) // Endpoint para crear un autor.
+        .service(author_api::update_author) // Endpoint para actualizar un autor.
+        .service(author_api::delete_author) // Endpoint para eliminar un autor sin libros.
+        .service(publisher_api::get_all_publishers) // Endpoint para obtener todas las editoriales.
+        .service(publisher_api::get_publisher) // Endpoint para obtener una editorial por su ID.
+        .service(publisher_api::get_publisher_books) // Endpoint para obtener los libros de una editorial.
+        .service(publisher_api::create_publisher) // Endpoint para crear una editorial.
+        .service(publisher_api::update_publisher) // Endpoint para actualizar una editorial.
+        .service(publisher_api::delete_publisher) // Endpoint para eliminar una editorial sin libros.
+        .service(loan_api::checkout_book) // Endpoint para prestar un libro.
+        .service(loan_api::get_overdue_loans) // Endpoint para ver los préstamos vencidos (antes de `/prestamos/{id}`).
+        .service(loan_api::get_loan) // Endpoint para obtener un préstamo por su ID.
+        .service(loan_api::return_loan) // Endpoint para devolver un préstamo.
+        .service(loan_api::renew_loan) // Endpoint para renovar un préstamo.
+        .service(loan_api::get_member_loans) // Endpoint para ver los préstamos activos de un socio.
+        .service(loan_api::place_hold) // Endpoint para reservar un libro.
+        .service(loan_api::cancel_hold) // Endpoint para cancelar una reserva.
+        .service(loan_api::get_book_holds); // Endpoint para ver la cola de reservas de un libro.
+}
@@ -0,0 +1,121 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+
+use crate::{
+    api::reference_api,
+    error::{AppError, ErrorResponse},
+    model::{BookPage, BookQuery, CreatePublisherDto, Publisher, UpdatePublisherDto},
+    repository::{book_repository::BookRepository, reference_repository::ReferenceRepository},
+};
+
+/// Endpoint para obtener todas las editoriales
+#[utoipa::path(
+    tag = "editoriales",
+    responses(
+        (status = 200, description = "Editoriales ordenadas por nombre", body = Vec<Publisher>),
+    )
+)]
+#[get("/editoriales")]
+pub async fn get_all_publishers(db: web::Data<dyn ReferenceRepository<Publisher>>) -> Result<HttpResponse, AppError> {
+    reference_api::get_all(db.get_ref()).await
+}
+
+/// Endpoint para obtener una editorial por su ID
+#[utoipa::path(
+    tag = "editoriales",
+    params(("id" = String, Path, description = "ID de la editorial")),
+    responses(
+        (status = 200, description = "Editorial", body = Publisher),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/editoriales/{id}")]
+pub async fn get_publisher(
+    db: web::Data<dyn ReferenceRepository<Publisher>>,
+    publisher_id: Path<String>, // ID de la editorial proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    reference_api::get(db.get_ref(), &publisher_id).await
+}
+
+/// Endpoint para obtener los libros de una editorial con paginación, filtros y orden
+#[utoipa::path(
+    tag = "editoriales",
+    params(("id" = String, Path, description = "ID de la editorial"), BookQuery),
+    responses(
+        (status = 200, description = "Página de libros de la editorial", body = BookPage),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+    )
+)]
+#[get("/editoriales/{id}/libros")]
+pub async fn get_publisher_books(
+    db: web::Data<dyn ReferenceRepository<Publisher>>,
+    books: web::Data<dyn BookRepository>,
+    publisher_id: Path<String>, // ID de la editorial proporcionado en la URL
+    query: Query<BookQuery>, // Parámetros de paginación, filtros y orden
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    reference_api::get_books(db.get_ref(), books.get_ref(), &publisher_id, query.into_inner(), &req).await
+}
+
+/// Endpoint para crear una nueva editorial
+#[utoipa::path(
+    tag = "editoriales",
+    request_body = CreatePublisherDto,
+    responses(
+        (status = 201, description = "Editorial creada", body = Publisher),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[post("/editoriales")]
+pub async fn create_publisher(
+    db: web::Data<dyn ReferenceRepository<Publisher>>,
+    publisher_dto: Json<CreatePublisherDto>, // Datos de la editorial en formato JSON
+) -> Result<HttpResponse, AppError> {
+    reference_api::create(db.get_ref(), publisher_dto.into_inner()).await
+}
+
+/// Endpoint para actualizar una editorial (el nuevo nombre se copia en sus libros)
+#[utoipa::path(
+    tag = "editoriales",
+    params(("id" = String, Path, description = "ID de la editorial")),
+    request_body = UpdatePublisherDto,
+    responses(
+        (status = 200, description = "Editorial actualizada", body = Publisher),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 422, description = "Datos inválidos (detalle por campo)", body = ErrorResponse),
+    )
+)]
+#[put("/editoriales/{id}")]
+pub async fn update_publisher(
+    db: web::Data<dyn ReferenceRepository<Publisher>>,
+    publisher_id: Path<String>, // ID de la editorial proporcionado en la URL
+    publisher_dto: Json<UpdatePublisherDto>, // Datos actualizados de la editorial en formato JSON
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    reference_api::update(db.get_ref(), &publisher_id, publisher_dto.into_inner(), &req).await
+}
+
+/// Endpoint para eliminar una editorial (solo si ningún libro la referencia)
+#[utoipa::path(
+    tag = "editoriales",
+    params(("id" = String, Path, description = "ID de la editorial")),
+    responses(
+        (status = 204, description = "Editorial eliminada"),
+        (status = 400, description = "ID o parámetros inválidos", body = ErrorResponse),
+        (status = 404, description = "Recurso no encontrado", body = ErrorResponse),
+        (status = 409, description = "Conflicto con el estado actual", body = ErrorResponse),
+    )
+)]
+#[delete("/editoriales/{id}")]
+pub async fn delete_publisher(
+    db: web::Data<dyn ReferenceRepository<Publisher>>,
+    publisher_id: Path<String>, // ID de la editorial proporcionado en la URL
+) -> Result<HttpResponse, AppError> {
+    reference_api::delete(db.get_ref(), &publisher_id).await
+}
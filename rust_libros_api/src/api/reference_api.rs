@@ -0,0 +1,93 @@
+use actix_web::{HttpRequest, HttpResponse};
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::{
+    api::book_api::book_page,
+    error::AppError,
+    history,
+    model::{BookQuery, BookReference},
+    repository::{book_repository::BookRepository, reference_repository::ReferenceRepository},
+};
+
+// Endpoints compartidos por autores y editoriales; `author_api` y `publisher_api` declaran sus rutas y su documentación
+
+// Lista todos los registros ordenados por nombre
+pub async fn get_all<E: BookReference>(db: &dyn ReferenceRepository<E>) -> Result<HttpResponse, AppError> {
+    // Llama al repositorio para obtener los registros
+    let entries = db.get_all().await?;
+    // Devuelve los registros en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+// Obtiene un registro por su ID
+pub async fn get<E: BookReference>(db: &dyn ReferenceRepository<E>, id: &str) -> Result<HttpResponse, AppError> {
+    let id = parse_id(id)?;
+    // Llama al repositorio para obtener el registro
+    let entry = db.get(id).await?;
+    // Devuelve el registro en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+// Obtiene los libros que referencian el registro con paginación, filtros y orden
+pub async fn get_books<E: BookReference>(
+    db: &dyn ReferenceRepository<E>,
+    books: &dyn BookRepository,
+    id: &str,
+    query: BookQuery,
+    req: &HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let id = parse_id(id)?;
+    // Verifica que el registro exista para distinguir uno sin libros de uno inexistente
+    db.get(id).await?;
+
+    let mut options = query.to_options()?;
+    E::filter_books(&mut options.filter, id);
+
+    // Llama al repositorio para obtener la página de libros del registro
+    let page = book_page(books, query, options, req).await?;
+    // Devuelve la página en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Crea un nuevo registro
+pub async fn create<E: BookReference>(db: &dyn ReferenceRepository<E>, dto: E::CreateDto) -> Result<HttpResponse, AppError> {
+    // Valida los datos antes de llegar al repositorio
+    dto.validate()?;
+
+    // Llama al repositorio para crear el registro
+    let created = db.create(dto).await?;
+    // Devuelve el registro creado en formato JSON con un código de estado 201 (Created)
+    Ok(HttpResponse::Created().json(created))
+}
+
+// Actualiza un registro (el nuevo nombre se copia en sus libros)
+pub async fn update<E: BookReference>(
+    db: &dyn ReferenceRepository<E>,
+    id: &str,
+    dto: E::UpdateDto,
+    req: &HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let id = parse_id(id)?;
+    // Valida los datos antes de llegar al repositorio
+    dto.validate()?;
+
+    // Llama al repositorio para actualizar el registro (un cambio de nombre queda en el historial de sus libros)
+    let updated = db.update(id, dto, &history::actor(req)).await?;
+    // Devuelve el registro actualizado en formato JSON con un código de estado 200 (OK)
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+// Elimina un registro (solo si ningún libro lo referencia)
+pub async fn delete<E: BookReference>(db: &dyn ReferenceRepository<E>, id: &str) -> Result<HttpResponse, AppError> {
+    let id = parse_id(id)?;
+    // Llama al repositorio para eliminar el registro
+    db.delete(id).await?;
+    // Devuelve una respuesta vacía con un código de estado 204 (No Content)
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Convierte el ID proporcionado en un ObjectId de MongoDB
+fn parse_id(id: &str) -> Result<ObjectId, AppError> {
+    ObjectId::parse_str(id).map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))
+}
@@ -0,0 +1,323 @@
+use actix_web::{
+    get,
+    http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+    post,
+    web::{self, Bytes, Payload, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppError, ErrorResponse, FieldError},
+    history,
+    model::{Book, CreateBookDto},
+    repository::book_repository::{BatchError, BookRepository},
+    transfer::{self, RowDecoder, TransferFormat},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    #[default]
+    BestEffort, // Importa las filas válidas y reporta las inválidas.
+    AllOrNothing, // Si alguna fila falla, no se importa ninguna.
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    pub format: Option<TransferFormat>, // Formato de la carga; si falta se usa el `Content-Type`.
+    #[serde(default)]
+    pub mode: ImportMode, // Modo de importación.
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    pub format: Option<TransferFormat>, // Formato de la exportación (CSV por defecto).
+}
+
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Imported, // La fila se guardó.
+    Invalid, // La fila no pasó la validación.
+    Rejected, // El repositorio rechazó la fila (p. ej. ISBN duplicado).
+    Skipped, // La fila era válida pero no se guardó (modo todo o nada).
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RowResult {
+    pub row: usize, // Número de fila de datos (empieza en 1, sin contar encabezados).
+    pub status: RowStatus, // Resultado de la fila.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>, // ID del libro creado.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>, // Errores de la fila.
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub mode: ImportMode, // Modo usado.
+    pub total: usize, // Filas leídas.
+    pub imported: usize, // Filas guardadas.
+    pub failed: usize, // Filas inválidas o rechazadas.
+    pub rows: Vec<RowResult>, // Resultado de cada fila.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Error que interrumpió la lectura de la carga (las filas reportadas ya se guardaron).
+}
+
+// Filas válidas que el modo todo o nada puede retener antes de guardarlas.
+const MAX_PENDING_ROWS: usize = 10_000;
+
+/// Endpoint para importar libros desde CSV o NDJSON, leyendo la carga a medida que llega
+#[utoipa::path(
+    tag = "transferencia",
+    params(ImportQuery),
+    request_body(description = "Filas en CSV (con encabezados) o NDJSON", content((String = "text/csv"), (String = "application/x-ndjson"))),
+    responses(
+        (status = 200, description = "Reporte de la importación", body = ImportReport),
+        (status = 422, description = "Importación todo o nada con filas inválidas", body = ImportReport),
+        (status = 400, description = "Carga ilegible; en modo best_effort, reporte de las filas ya guardadas con `error`", body = ImportReport),
+        (status = 413, description = "Fila demasiado grande, o más filas de las que admite el modo todo o nada", body = ErrorResponse),
+    )
+)]
+#[post("/libro/import")]
+pub async fn import_books(
+    db: web::Data<dyn BookRepository>,
+    query: Query<ImportQuery>, // Formato y modo de importación
+    req: HttpRequest,
+    mut payload: Payload, // Cuerpo de la solicitud como flujo de fragmentos
+) -> Result<HttpResponse, AppError> {
+    let format = query
+        .format
+        .or_else(|| {
+            req.headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(TransferFormat::from_content_type)
+        })
+        .ok_or_else(|| {
+            AppError::InvalidPayloadError(
+                "Formato no soportado: use text/csv, application/x-ndjson o ?format=csv|ndjson".to_string(),
+            )
+        })?;
+    let mode = query.mode;
+
+    let mut decoder = RowDecoder::new(format);
+    let mut importer = Importer::new(db.get_ref(), mode, history::actor(&req));
+
+    // Procesa cada fragmento en cuanto llega
+    if let Err(err) = read_rows(&mut payload, &mut decoder, &mut importer).await {
+        if mode == ImportMode::AllOrNothing {
+            return Err(err); // Todavía no se guardó ninguna fila
+        }
+        // Las filas anteriores al error ya se guardaron: se reportan junto con el error
+        let mut report = importer.finish().await?;
+        log::warn!("Importación interrumpida tras {} filas importadas: {}", report.imported, err);
+        report.error = Some(err.to_string());
+        return Ok(HttpResponse::build(err.status_code()).json(report));
+    }
+
+    let report = importer.finish().await?;
+    log::info!(
+        "Importación {:?}: {} filas, {} importadas, {} con error",
+        report.mode,
+        report.total,
+        report.imported,
+        report.failed
+    );
+
+    // En modo todo o nada, una importación fallida responde 422 (Unprocessable Entity)
+    if mode == ImportMode::AllOrNothing && report.failed > 0 {
+        return Ok(HttpResponse::UnprocessableEntity().json(report));
+    }
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// Lee la carga fragmento por fragmento y entrega cada fila completa al importador
+async fn read_rows(payload: &mut Payload, decoder: &mut RowDecoder, importer: &mut Importer<'_>) -> Result<(), AppError> {
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|err| AppError::InvalidPayloadError(err.to_string()))?;
+        for row in decoder.feed(&chunk) {
+            importer.process(row?).await?;
+        }
+    }
+    for row in decoder.finish() {
+        importer.process(row?).await?;
+    }
+    Ok(())
+}
+
+/// Endpoint para exportar todos los libros en CSV o NDJSON, enviándolos a medida que se leen
+#[utoipa::path(
+    tag = "transferencia",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Catálogo completo", content((String = "text/csv"), (String = "application/x-ndjson"))),
+    )
+)]
+#[get("/libro/export")]
+pub async fn export_books(
+    db: web::Data<dyn BookRepository>,
+    query: Query<ExportQuery>, // Formato de exportación
+) -> Result<HttpResponse, AppError> {
+    let format = query.format.unwrap_or(TransferFormat::Csv);
+
+    let header_row = match format {
+        TransferFormat::Csv => vec![Ok(Bytes::from(transfer::csv_header()?))],
+        TransferFormat::Ndjson => Vec::new(),
+    };
+    let books = db
+        .stream_books()
+        .await?
+        .and_then(move |book| async move { transfer::encode_book(format, &book).map(Bytes::from) });
+    let body = stream::iter(header_row).chain(books);
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!("libros.{}", format.extension()))],
+        })
+        .streaming(body))
+}
+
+// Acumula el resultado de la importación fila por fila según el modo elegido
+struct Importer<'a> {
+    db: &'a dyn BookRepository,
+    mode: ImportMode,
+    user: String, // Usuario que se registra en el historial de cada libro
+    rows: Vec<RowResult>,
+    pending: Vec<(usize, CreateBookDto)>, // Filas válidas a la espera (modo todo o nada)
+    failed: usize,
+}
+
+impl<'a> Importer<'a> {
+    fn new(db: &'a dyn BookRepository, mode: ImportMode, user: String) -> Self {
+        Importer {
+            db,
+            mode,
+            user,
+            rows: Vec::new(),
+            pending: Vec::new(),
+            failed: 0,
+        }
+    }
+
+    // Registra una fila: en modo "best effort" se guarda de inmediato
+    async fn process(&mut self, (row, parsed): transfer::ParsedRow) -> Result<(), AppError> {
+        match parsed {
+            Err(errors) => {
+                self.failed += 1;
+                self.rows.push(RowResult {
+                    row,
+                    status: RowStatus::Invalid,
+                    id: None,
+                    errors,
+                });
+            }
+            Ok(dto) => match self.mode {
+                ImportMode::BestEffort => {
+                    let result = self.insert(row, dto).await;
+                    self.rows.push(result);
+                }
+                ImportMode::AllOrNothing => {
+                    if self.pending.len() >= MAX_PENDING_ROWS {
+                        return Err(AppError::PayloadTooLarge(format!(
+                            "El modo all_or_nothing admite hasta {} filas; use mode=best_effort o divida el archivo",
+                            MAX_PENDING_ROWS
+                        )));
+                    }
+                    self.pending.push((row, dto));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // Cierra la importación; en modo todo o nada guarda todas las filas pendientes en un solo lote o ninguna
+    async fn finish(mut self) -> Result<ImportReport, AppError> {
+        let pending = std::mem::take(&mut self.pending);
+        if self.failed > 0 {
+            // Hubo filas inválidas: ninguna fila pendiente se guarda
+            self.rows.extend(pending.into_iter().map(|(row, _)| skipped(row)));
+        } else if !pending.is_empty() {
+            let (rows, dtos): (Vec<usize>, Vec<CreateBookDto>) = pending.into_iter().unzip();
+            match self.db.create_books(dtos, &self.user).await {
+                Ok(books) => self.rows.extend(rows.into_iter().zip(books).map(|(row, book)| imported(row, &book))),
+                // Una fila fue rechazada: el lote no se guardó y el resto de las filas se marca como omitido
+                Err(BatchError { index: Some(index), error }) => {
+                    self.failed += 1;
+                    let mut results: Vec<RowResult> = rows.iter().map(|row| skipped(*row)).collect();
+                    if let Some(result) = results.get_mut(index) {
+                        *result = rejected(result.row, error);
+                    }
+                    self.rows.extend(results);
+                }
+                Err(BatchError { index: None, error }) => return Err(error),
+            }
+        }
+
+        self.rows.sort_by_key(|result| result.row);
+        Ok(ImportReport {
+            mode: self.mode,
+            total: self.rows.len(),
+            imported: self.rows.iter().filter(|r| r.status == RowStatus::Imported).count(),
+            failed: self.failed,
+            rows: self.rows,
+            error: None,
+        })
+    }
+
+    // Guarda una fila en el repositorio (con su entrada del historial)
+    async fn insert(&mut self, row: usize, dto: CreateBookDto) -> RowResult {
+        match self.db.create_book(dto, &self.user).await {
+            Ok(book) => imported(row, &book),
+            Err(err) => {
+                self.failed += 1;
+                rejected(row, err)
+            }
+        }
+    }
+}
+
+// Resultado de una fila guardada
+fn imported(row: usize, book: &Book) -> RowResult {
+    RowResult {
+        row,
+        status: RowStatus::Imported,
+        id: book.id.map(|id| id.to_hex()),
+        errors: Vec::new(),
+    }
+}
+
+// Resultado de una fila que el repositorio rechazó
+fn rejected(row: usize, err: AppError) -> RowResult {
+    let errors = match err {
+        AppError::ValidationError(errors) => errors,
+        other => vec![FieldError {
+            field: "_".to_string(),
+            code: "rejected".to_string(),
+            message: other.to_string(),
+        }],
+    };
+    RowResult {
+        row,
+        status: RowStatus::Rejected,
+        id: None,
+        errors,
+    }
+}
+
+// Resultado de una fila válida que no se guardó
+fn skipped(row: usize) -> RowResult {
+    RowResult {
+        row,
+        status: RowStatus::Skipped,
+        id: None,
+        errors: Vec::new(),
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    collections::HashSet,
+    env, fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{Serialize, Serializer};
+
+use crate::model::LoanPolicy;
+
+// Archivo TOML que se usa si existe y no se indicó otro con `--config` o `CONFIG_FILE`.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const SECS_PER_MINUTE: u64 = 60;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Mongo, // Datos en MongoDB (por defecto).
+    Memory, // Datos en memoria; se pierden al detener el servidor.
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "mongo" => Ok(StorageBackend::Mongo),
+            "memory" => Ok(StorageBackend::Memory),
+            _ => Err("valores permitidos: mongo, memory".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverStorage {
+    GridFs, // Portadas en GridFS, en la misma base de datos.
+    Fs, // Portadas en el directorio `cover_dir`.
+}
+
+impl FromStr for CoverStorage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "gridfs" => Ok(CoverStorage::GridFs),
+            "fs" => Ok(CoverStorage::Fs),
+            _ => Err("valores permitidos: gridfs, fs".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json, // Un objeto JSON por línea (por defecto).
+    Text, // Texto legible para desarrollo.
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(LogFormat::Json),
+            "text" => Ok(LogFormat::Text),
+            _ => Err("valores permitidos: json, text".to_string()),
+        }
+    }
+}
+
+// URI de MongoDB; la contraseña se oculta al mostrarla o serializarla.
+#[derive(Clone)]
+pub struct MongoUri(String);
+
+impl MongoUri {
+    // URI completa, solo para conectarse
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // URI con la contraseña reemplazada por `****`
+    fn redacted(&self) -> String {
+        let Some((scheme, rest)) = self.0.split_once("://") else {
+            return self.0.clone();
+        };
+        match rest.split_once('@') {
+            Some((credentials, host)) => {
+                let user = credentials.split(':').next().unwrap_or_default();
+                format!("{}://{}:****@{}", scheme, user, host)
+            }
+            None => self.0.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for MongoUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.redacted())
+    }
+}
+
+impl Serialize for MongoUri {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.redacted())
+    }
+}
+
+impl FromStr for MongoUri {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.starts_with("mongodb://") || value.starts_with("mongodb+srv://") {
+            Ok(MongoUri(value.to_string()))
+        } else {
+            Err("debe empezar con mongodb:// o mongodb+srv://".to_string())
+        }
+    }
+}
+
+// Configuración del servicio, cargada una sola vez al iniciar.
+// Cada clave del archivo TOML es el nombre de la variable de entorno en minúsculas;
+// las variables de entorno (incluido `.env`) tienen prioridad sobre el archivo.
+#[derive(Debug, Clone, Serialize)]
+pub struct Config {
+    pub server_host: String, // IP o nombre de host (p. ej. `localhost`) de los servidores REST y gRPC.
+    pub server_port: u16,
+    pub grpc_port: u16,
+    pub storage_backend: StorageBackend,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mongo_uri: Option<MongoUri>, // Obligatoria con `storage_backend = "mongo"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mongo_db_name: Option<String>, // Obligatoria con `storage_backend = "mongo"`.
+    pub cover_storage: CoverStorage,
+    pub cover_dir: PathBuf,
+    pub loan_days: i64, // Días que dura un préstamo (y cada renovación).
+    pub loan_limit: usize, // Préstamos activos permitidos por socio.
+    pub loan_max_renewals: u32, // Renovaciones permitidas por préstamo.
+    pub hold_days: i64, // Días que una copia apartada espera al socio de la reserva.
+    pub trash_retention_days: u64, // Días que un libro eliminado permanece en la papelera.
+    pub trash_purge_interval_minutes: u64, // Frecuencia de la purga de la papelera.
+    pub health_check_timeout_ms: u64, // Tiempo máximo que `/health/ready` espera a cada dependencia.
+    pub shutdown_timeout_secs: u64, // Tiempo que el apagado espera a las solicitudes en curso antes de cancelarlas.
+    pub log_format: LogFormat,
+}
+
+impl Config {
+    // Carga la configuración y reúne todos los valores faltantes o inválidos en un solo error
+    pub fn load(config_file: Option<&Path>) -> Result<Config, ConfigError> {
+        let mut sources = Sources::new(config_file)?;
+        let default_policy = LoanPolicy::default();
+
+        let server_host: String = sources.or("SERVER_HOST", "127.0.0.1".to_string());
+        let server_port = sources.or("SERVER_PORT", 8080);
+        let grpc_port = sources.or("GRPC_PORT", 50051);
+        let storage_backend = sources.or("STORAGE_BACKEND", StorageBackend::Mongo);
+        let (mongo_uri, mongo_db_name) = match storage_backend {
+            StorageBackend::Mongo => (sources.required("MONGO_URI"), sources.required("MONGO_DB_NAME")),
+            StorageBackend::Memory => (sources.optional("MONGO_URI"), sources.optional("MONGO_DB_NAME")),
+        };
+        let default_cover_storage = match storage_backend {
+            StorageBackend::Mongo => CoverStorage::GridFs,
+            StorageBackend::Memory => CoverStorage::Fs,
+        };
+        let cover_storage = sources.or("COVER_STORAGE", default_cover_storage);
+        if storage_backend == StorageBackend::Memory && cover_storage == CoverStorage::GridFs {
+            sources.invalid("COVER_STORAGE", "gridfs requiere STORAGE_BACKEND=mongo");
+        }
+        let cover_dir = sources.or("COVER_DIR", PathBuf::from("portadas"));
+
+        let loan_days = sources.or("LOAN_DAYS", default_policy.loan_days);
+        let loan_limit = sources.or("LOAN_LIMIT", default_policy.max_loans);
+        let loan_max_renewals = sources.or("LOAN_MAX_RENEWALS", default_policy.max_renewals);
+        let hold_days = sources.or("HOLD_DAYS", default_policy.hold_days);
+        let trash_retention_days: u64 = sources.or("TRASH_RETENTION_DAYS", 30);
+        let trash_purge_interval_minutes: u64 = sources.or("TRASH_PURGE_INTERVAL_MINUTES", 60);
+        let health_check_timeout_ms = sources.or("HEALTH_CHECK_TIMEOUT_MS", 2000);
+        let shutdown_timeout_secs = sources.or("SHUTDOWN_TIMEOUT_SECS", 30);
+        let log_format = sources.or("LOG_FORMAT", LogFormat::Json);
+
+        if loan_days < 1 {
+            sources.invalid("LOAN_DAYS", "debe ser mayor que 0");
+        }
+        if hold_days < 1 {
+            sources.invalid("HOLD_DAYS", "debe ser mayor que 0");
+        }
+        // La purga calcula la fecha límite en milisegundos con signo
+        if trash_retention_days.checked_mul(SECS_PER_DAY * 1000).is_none_or(|ms| i64::try_from(ms).is_err()) {
+            sources.invalid("TRASH_RETENTION_DAYS", "es demasiado grande");
+        }
+        if trash_purge_interval_minutes == 0 {
+            sources.invalid("TRASH_PURGE_INTERVAL_MINUTES", "debe ser mayor que 0");
+        } else if trash_purge_interval_minutes.checked_mul(SECS_PER_MINUTE).is_none() {
+            sources.invalid("TRASH_PURGE_INTERVAL_MINUTES", "es demasiado grande");
+        }
+        if health_check_timeout_ms == 0 {
+            sources.invalid("HEALTH_CHECK_TIMEOUT_MS", "debe ser mayor que 0");
+        }
+        if server_host.trim().is_empty() {
+            sources.invalid("SERVER_HOST", "no puede estar vacío");
+        }
+        if server_port == grpc_port {
+            sources.invalid("GRPC_PORT", "debe ser distinto de SERVER_PORT");
+        }
+
+        sources.finish()?;
+        Ok(Config {
+            server_host,
+            server_port,
+            grpc_port,
+            storage_backend,
+            mongo_uri,
+            mongo_db_name,
+            cover_storage,
+            cover_dir,
+            loan_days,
+            loan_limit,
+            loan_max_renewals,
+            hold_days,
+            trash_retention_days,
+            trash_purge_interval_minutes,
+            health_check_timeout_ms,
+            shutdown_timeout_secs,
+            log_format,
+        })
+    }
+
+    pub fn loan_policy(&self) -> LoanPolicy {
+        LoanPolicy {
+            loan_days: self.loan_days,
+            max_loans: self.loan_limit,
+            max_renewals: self.loan_max_renewals,
+            hold_days: self.hold_days,
+        }
+    }
+
+    // Resuelve `server_host` una sola vez; los servidores REST y gRPC escuchan en la misma dirección
+    pub async fn server_addrs(&self) -> io::Result<(SocketAddr, SocketAddr)> {
+        let ip = tokio::net::lookup_host((self.server_host.as_str(), self.server_port))
+            .await?
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("SERVER_HOST: no se pudo resolver {}", self.server_host),
+                )
+            })?
+            .ip();
+        Ok((SocketAddr::new(ip, self.server_port), SocketAddr::new(ip, self.grpc_port)))
+    }
+
+    // `Config::load` rechaza los valores que no caben en segundos; si aun así no caben, se usa el máximo.
+    pub fn trash_retention(&self) -> Duration {
+        Duration::from_secs(self.trash_retention_days.saturating_mul(SECS_PER_DAY))
+    }
+
+    pub fn trash_purge_interval(&self) -> Duration {
+        Duration::from_secs(self.trash_purge_interval_minutes.saturating_mul(SECS_PER_MINUTE))
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_millis(self.health_check_timeout_ms)
+    }
+
+    // Configuración efectiva en TOML (con la contraseña de MongoDB oculta), para `--print-config`
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("La configuración siempre se puede serializar")
+    }
+}
+
+// Opciones de la línea de comandos
+#[derive(Debug, Default)]
+pub struct CliOptions {
+    pub print_config: bool, // `--print-config`: muestra la configuración efectiva y termina.
+    pub config_file: Option<PathBuf>, // `--config <ruta>`: archivo TOML a usar.
+}
+
+impl CliOptions {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliOptions, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--print-config" => options.print_config = true,
+                "--config" => {
+                    let path = args.next().ok_or("--config requiere la ruta de un archivo")?;
+                    options.config_file = Some(PathBuf::from(path));
+                }
+                other => {
+                    return Err(format!(
+                        "Argumento desconocido: {} (opciones: --print-config, --config <ruta>)",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+// Todos los problemas encontrados al cargar la configuración
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Configuración inválida:")?;
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+// Valores de las variables de entorno y del archivo TOML, con los errores acumulados
+struct Sources {
+    file: toml::Table,
+    file_name: Option<String>,
+    used_keys: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Sources {
+    // Lee el archivo indicado (`--config` o `CONFIG_FILE`) o `config.toml` si existe
+    fn new(config_file: Option<&Path>) -> Result<Sources, ConfigError> {
+        let explicit = config_file
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from));
+        let path = match explicit {
+            Some(path) => Some(path),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+
+        let mut sources = Sources {
+            file: toml::Table::new(),
+            file_name: None,
+            used_keys: HashSet::new(),
+            errors: Vec::new(),
+        };
+        if let Some(path) = path {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(&path)
+                .map_err(|err| ConfigError(vec![format!("No se pudo leer {}: {}", name, err)]))?;
+            sources.file = content
+                .parse()
+                .map_err(|err| ConfigError(vec![format!("{} no es un TOML válido: {}", name, err)]))?;
+            sources.file_name = Some(name);
+        }
+        Ok(sources)
+    }
+
+    // Valor sin interpretar: primero la variable de entorno, luego la clave del archivo
+    fn raw(&mut self, name: &str) -> Option<String> {
+        let key = name.to_lowercase();
+        self.used_keys.insert(key.clone());
+        if let Ok(value) = env::var(name) {
+            return Some(value);
+        }
+        self.file.get(&key).map(|value| match value {
+            toml::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    fn optional<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let raw = self.raw(name)?;
+        match raw.trim().parse() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                // No se repite el valor: la URI de MongoDB puede incluir la contraseña.
+                self.invalid(name, &format!("valor inválido: {}", err));
+                None
+            }
+        }
+    }
+
+    fn or<T>(&mut self, name: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.optional(name).unwrap_or(default)
+    }
+
+    fn required<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let present = self.raw(name).is_some();
+        if !present {
+            self.invalid(name, "es obligatoria y no está definida");
+        }
+        self.optional(name)
+    }
+
+    fn invalid(&mut self, name: &str, problem: &str) {
+        self.errors.push(format!("{}: {}", name, problem));
+    }
+
+    // Las claves desconocidas del archivo suelen ser errores de escritura
+    fn finish(mut self) -> Result<(), ConfigError> {
+        if let Some(file_name) = &self.file_name {
+            let mut unknown: Vec<_> = self.file.keys().filter(|key| !self.used_keys.contains(*key)).collect();
+            unknown.sort();
+            for key in unknown {
+                self.errors.push(format!("{}: clave desconocida `{}`", file_name, key));
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(self.errors))
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::Cursor;
+
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, ImageFormat, ImageReader, Limits};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::AppError,
+    model::{Cover, CoverSize},
+    repository::cover_repository::CoverRepository,
+};
+
+pub const MAX_COVER_BYTES: usize = 5 * 1024 * 1024; // Tamaño máximo de la imagen subida (5 MB).
+const MAX_COVER_DIMENSION: u32 = 8000; // Ancho y alto máximos, para no decodificar imágenes gigantes.
+pub const THUMBNAIL_CONTENT_TYPE: &str = "image/jpeg"; // Las miniaturas siempre se guardan en JPEG.
+const THUMBNAIL_QUALITY: u8 = 85; // Calidad JPEG de las miniaturas.
+
+// Portada lista para guardar: sus datos, la imagen original y las miniaturas generadas.
+pub struct ProcessedCover {
+    pub cover: Cover,
+    pub original: Vec<u8>,
+    pub thumbnails: Vec<(CoverSize, Vec<u8>)>,
+}
+
+// Tipo de contenido de los formatos de imagen permitidos.
+fn content_type(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+// Valida la imagen subida (el tipo se detecta por su contenido) y genera las miniaturas.
+// Decodificar y redimensionar usa CPU de forma intensiva: se debe llamar con `web::block`.
+pub fn process(bytes: Vec<u8>) -> Result<ProcessedCover, AppError> {
+    let (format, content_type) = image::guess_format(&bytes)
+        .ok()
+        .and_then(|format| Some((format, content_type(format)?)))
+        .ok_or_else(|| AppError::UnsupportedMediaType("La portada debe ser una imagen JPEG, PNG o WebP".to_string()))?;
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_COVER_DIMENSION);
+    limits.max_image_height = Some(MAX_COVER_DIMENSION);
+    let mut reader = ImageReader::with_format(Cursor::new(&bytes), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|err| AppError::InvalidPayloadError(format!("No se pudo leer la imagen: {}", err)))?;
+
+    // Las miniaturas se recortan al centro para tener siempre las mismas dimensiones
+    let mut thumbnails = Vec::new();
+    for size in CoverSize::THUMBNAILS {
+        let Some((width, height)) = size.dimensions() else {
+            continue;
+        };
+        let thumbnail = image.resize_to_fill(width, height, FilterType::Lanczos3).to_rgb8();
+        let mut encoded = Vec::new();
+        thumbnail
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, THUMBNAIL_QUALITY))
+            .map_err(|err| {
+                log::error!("No se pudo generar la miniatura de la portada: {}", err);
+                AppError::InternalError
+            })?;
+        thumbnails.push((size, encoded));
+    }
+
+    let digest = Sha256::digest(&bytes);
+    let cover = Cover {
+        content_type: content_type.to_string(),
+        bytes: bytes.len() as u64,
+        ancho: image.width(),
+        alto: image.height(),
+        hash: hex::encode(&digest[..16]),
+        actualizada_en: DateTime::now(),
+    };
+
+    Ok(ProcessedCover {
+        cover,
+        original: bytes,
+        thumbnails,
+    })
+}
+
+// Guarda la imagen original y las miniaturas con las claves de su huella.
+pub async fn save_images(covers: &dyn CoverRepository, book_id: ObjectId, processed: ProcessedCover) -> Result<Cover, AppError> {
+    covers.save_image(&CoverSize::Original.key(book_id, &processed.cover), processed.original).await?;
+    for (size, thumbnail) in processed.thumbnails {
+        covers.save_image(&size.key(book_id, &processed.cover), thumbnail).await?;
+    }
+    Ok(processed.cover)
+}
+
+// Elimina las imágenes de una portada que el libro ya no usa (también las de la clave anterior a la huella).
+// Los fallos solo se registran: el cambio del libro ya se guardó y la imagen huérfana no se vuelve a servir.
+pub async fn delete_images(covers: &dyn CoverRepository, book_id: ObjectId, cover: &Cover) {
+    for size in CoverSize::ALL {
+        for key in [size.key(book_id, cover), size.legacy_key(book_id)] {
+            if let Err(err) = covers.delete_image(&key).await {
+                log::error!("No se pudo eliminar la imagen de portada {}: {}", key, err);
+            }
+        }
+    }
+}
@@ -2,8 +2,12 @@ use actix_web::{http::StatusCode, HttpResponse, ResponseError}; // Importa tipos
 use mongodb::bson; // Importa el módulo BSON de MongoDB.
 use serde::{Deserialize, Serialize}; // Importa traits para serialización y deserialización.
 use thiserror::Error; // Importa el macro `Error` para definir errores personalizados.
+use utoipa::ToSchema; // Esquema del cuerpo de error en el documento OpenAPI.
+use crate::telemetry::RequestId; // ID de la solicitud en curso.
+use validator::ValidationErrors; // Errores producidos al validar los DTOs.
 
 #[derive(Error, Debug)] // Deriva las implementaciones de `Error` y `Debug` para la enumeración.
+#[allow(clippy::enum_variant_names)] // Los nombres con sufijo `Error` forman parte de la API existente.
 pub enum AppError {
     #[error("Error de MongoDB: {0}")] // Define un error relacionado con MongoDB, usando la fuente `mongodb::error::Error`.
     MongoError(#[from] mongodb::error::Error),
@@ -17,17 +21,78 @@ pub enum AppError {
     #[error("Error de ID inválido: {0}")] // Define un error para IDs inválidos, con un mensaje personalizado.
     InvalidIDError(String),
     
+    #[error("Parámetros de consulta inválidos: {0}")] // Define un error para parámetros de consulta inválidos (paginación, filtros u orden).
+    InvalidQueryError(String),
+    
+    #[error("Contenido inválido: {0}")] // Define un error para cargas que no se pueden interpretar (formato, codificación, etc.).
+    InvalidPayloadError(String),
+    
+    #[error("Contenido demasiado grande: {0}")] // Define un error para cargas que superan el tamaño permitido.
+    PayloadTooLarge(String),
+    
+    #[error("Tipo de contenido no soportado: {0}")] // Define un error para archivos de un tipo no permitido.
+    UnsupportedMediaType(String),
+    
+    #[error("Los datos enviados no son válidos")] // Define un error de validación con el detalle de cada campo inválido.
+    ValidationError(Vec<FieldError>),
+    
+    #[error("Conflicto: {0}")] // Define un error para recursos duplicados (p. ej. un ISBN ya registrado).
+    Conflict(String),
+    
     #[error("Recurso no encontrado")] // Define un error para recursos no encontrados.
     NotFoundError,
     
+    #[error("El recurso fue modificado por otra solicitud")] // Define un error para escrituras con un `If-Match` desactualizado.
+    PreconditionFailed,
+    
     #[error("Error interno del servidor")] // Define un error genérico para fallos internos del servidor.
     InternalError,
 }
 
-#[derive(Serialize, Deserialize)] // Permite serializar y deserializar la estructura `ErrorResponse`.
-struct ErrorResponse {
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)] // Permite serializar y deserializar la estructura `FieldError`.
+pub struct FieldError {
+    pub field: String, // Nombre del campo inválido.
+    pub code: String, // Código de la regla que no se cumplió (p. ej. `length`, `range`).
+    pub message: String, // Mensaje legible para mostrar al usuario.
+}
+
+#[derive(Serialize, Deserialize, ToSchema)] // Permite serializar y deserializar la estructura `ErrorResponse` y documentarla.
+pub struct ErrorResponse {
     status: String, // Código de estado HTTP como cadena.
     message: String, // Mensaje de error detallado.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<FieldError>>, // Errores por campo (solo en errores de validación).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>, // ID de la solicitud (`X-Request-Id`) para buscarla en los registros.
+}
+
+impl From<ValidationErrors> for AppError {
+    // Convierte los errores del crate `validator` en una lista de errores por campo.
+    fn from(errors: ValidationErrors) -> Self {
+        let mut field_errors: Vec<FieldError> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| FieldError {
+                    // Los errores a nivel de estructura indican su campo en el parámetro `field`
+                    field: error
+                        .params
+                        .get("field")
+                        .and_then(|field| field.as_str())
+                        .unwrap_or(field)
+                        .to_string(),
+                    code: error.code.to_string(),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map(|message| message.to_string())
+                        .unwrap_or_else(|| error.code.to_string()),
+                })
+            })
+            .collect();
+        field_errors.sort_by(|a, b| a.field.cmp(&b.field)); // Orden estable para el cliente.
+        AppError::ValidationError(field_errors)
+    }
 }
 
 impl ResponseError for AppError {
@@ -35,9 +100,16 @@ impl ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
         let status_code = self.status_code(); // Obtiene el código de estado HTTP correspondiente al error.
         
+        let errors = match self {
+            AppError::ValidationError(errors) => Some(errors.clone()), // Incluye el detalle por campo.
+            _ => None,
+        };
+        
         HttpResponse::build(status_code).json(ErrorResponse {
             status: status_code.to_string(), // Convierte el código de estado a cadena.
             message: self.to_string(), // Convierte el error en un mensaje legible.
+            errors,
+            request_id: RequestId::current().map(|id| id.to_string()),
         })
     }
 
@@ -46,7 +118,41 @@ impl ResponseError for AppError {
         match self {
             AppError::NotFoundError => StatusCode::NOT_FOUND, // 404 para recursos no encontrados.
             AppError::InvalidIDError(_) => StatusCode::BAD_REQUEST, // 400 para IDs inválidos.
+            AppError::InvalidQueryError(_) => StatusCode::BAD_REQUEST, // 400 para parámetros de consulta inválidos.
+            AppError::InvalidPayloadError(_) => StatusCode::BAD_REQUEST, // 400 para cargas inválidas.
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE, // 413 para cargas demasiado grandes.
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE, // 415 para tipos no permitidos.
+            AppError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY, // 422 para datos inválidos.
+            AppError::Conflict(_) => StatusCode::CONFLICT, // 409 para recursos duplicados.
+            AppError::PreconditionFailed => StatusCode::PRECONDITION_FAILED, // 412 para escrituras con versión desactualizada.
             _ => StatusCode::INTERNAL_SERVER_ERROR, // 500 para otros errores.
         }
     }
-}
\ No newline at end of file
+}
+impl From<AppError> for tonic::Status {
+    // Convierte `AppError` en el estado gRPC equivalente al código HTTP de la API REST.
+    fn from(error: AppError) -> Self {
+        let message = match &error {
+            // Los errores de validación se resumen en el mensaje como `campo: detalle`.
+            AppError::ValidationError(errors) => errors
+                .iter()
+                .map(|error| format!("{}: {}", error.field, error.message))
+                .collect::<Vec<_>>()
+                .join("; "),
+            other => other.to_string(),
+        };
+        let code = match error {
+            AppError::NotFoundError => tonic::Code::NotFound, // Equivale a 404.
+            AppError::InvalidIDError(_)
+            | AppError::InvalidQueryError(_)
+            | AppError::InvalidPayloadError(_)
+            | AppError::UnsupportedMediaType(_)
+            | AppError::ValidationError(_) => tonic::Code::InvalidArgument, // Equivale a 400, 415 y 422.
+            AppError::PayloadTooLarge(_) => tonic::Code::ResourceExhausted, // Equivale a 413.
+            AppError::Conflict(_) => tonic::Code::AlreadyExists, // Equivale a 409 (p. ej. un ISBN repetido).
+            AppError::PreconditionFailed => tonic::Code::FailedPrecondition, // Equivale a 412.
+            _ => tonic::Code::Internal, // Equivale a 500.
+        };
+        tonic::Status::new(code, message)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{future::Future, net::SocketAddr, sync::Arc};
+
+use mongodb::bson::oid::ObjectId;
+use tonic::{Request, Response, Status};
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    history,
+    model::{Book, BookQuery, CreateBookDto, UpdateBookDto},
+    repository::book_repository::BookRepository,
+};
+
+// Código generado a partir de `proto/libros.proto`.
+pub mod pb {
+    tonic::include_proto!("libros.v1");
+}
+
+use pb::libros_server::{Libros, LibrosServer};
+
+// Servicio gRPC del catálogo; comparte el repositorio con la API REST
+pub struct BookService {
+    db: Arc<dyn BookRepository>,
+}
+
+impl BookService {
+    pub fn new(db: Arc<dyn BookRepository>) -> Self {
+        BookService { db }
+    }
+}
+
+// Ejecuta el servidor gRPC en la dirección indicada hasta que termine `shutdown`
+pub async fn serve(
+    addr: SocketAddr,
+    db: Arc<dyn BookRepository>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(LibrosServer::new(BookService::new(db)))
+        .serve_with_shutdown(addr, shutdown) // Al apagarse deja de aceptar llamadas y espera las que están en curso.
+        .await
+}
+
+#[tonic::async_trait]
+impl Libros for BookService {
+    async fn list_books(
+        &self,
+        request: Request<pb::ListBooksRequest>,
+    ) -> Result<Response<pb::ListBooksResponse>, Status> {
+        let request = request.into_inner();
+        let query = BookQuery {
+            page: request.page,
+            per_page: request.per_page,
+            after: request.after,
+            autor: request.autor,
+            editorial: request.editorial,
+            autor_id: request.autor_id,
+            editorial_id: request.editorial_id,
+            anio_min: request.anio_min,
+            anio_max: request.anio_max,
+            paginas_min: request.paginas_min,
+            paginas_max: request.paginas_max,
+            sort: request.sort,
+        };
+        // Valida los parámetros igual que el listado REST
+        let options = query.to_options()?;
+
+        let list = self.db.get_all_books(&options).await?;
+        Ok(Response::new(pb::ListBooksResponse {
+            books: list.books.into_iter().map(pb::Book::from).collect(),
+            total: list.total,
+            has_more: list.has_more,
+        }))
+    }
+
+    async fn get_book(&self, request: Request<pb::GetBookRequest>) -> Result<Response<pb::Book>, Status> {
+        let id = parse_id(&request.get_ref().id)?;
+        let book = self.db.get_book(id).await?;
+        Ok(Response::new(book.into()))
+    }
+
+    async fn create_book(&self, request: Request<pb::CreateBookRequest>) -> Result<Response<pb::Book>, Status> {
+        let user = actor(&request);
+        let request = request.into_inner();
+        let book_dto = CreateBookDto {
+            titulo: request.titulo,
+            autor: request.autor,
+            autor_id: request.autor_id,
+            editorial: request.editorial,
+            editorial_id: request.editorial_id,
+            anio: request.anio,
+            descripcion: request.descripcion,
+            numero_pagina: request.numero_pagina,
+            isbn: request.isbn,
+            copias: request.copias,
+        };
+        // Valida los datos antes de llegar al repositorio
+        book_dto.validate().map_err(AppError::from)?;
+
+        let created_book = self.db.create_book(book_dto, &user).await?;
+        Ok(Response::new(created_book.into()))
+    }
+
+    async fn update_book(&self, request: Request<pb::UpdateBookRequest>) -> Result<Response<pb::Book>, Status> {
+        let user = actor(&request);
+        let request = request.into_inner();
+        let id = parse_id(&request.id)?;
+        let book_dto = UpdateBookDto {
+            titulo: request.titulo,
+            autor: request.autor,
+            autor_id: request.autor_id,
+            editorial: request.editorial,
+            editorial_id: request.editorial_id,
+            anio: request.anio,
+            descripcion: request.descripcion,
+            numero_pagina: request.numero_pagina,
+            isbn: request.isbn,
+            copias: request.copias,
+        };
+        // Valida los datos antes de llegar al repositorio
+        book_dto.validate().map_err(AppError::from)?;
+
+        // Con `version`, la escritura es condicional igual que con `If-Match` en la API REST
+        let updated_book = self.db.update_book(id, book_dto, request.version, &user).await?;
+        Ok(Response::new(updated_book.into()))
+    }
+
+    async fn delete_book(
+        &self,
+        request: Request<pb::DeleteBookRequest>,
+    ) -> Result<Response<pb::DeleteBookResponse>, Status> {
+        let user = actor(&request);
+        let id = parse_id(&request.get_ref().id)?;
+
+        self.db.delete_book(id, request.get_ref().version, &user).await?;
+        Ok(Response::new(pb::DeleteBookResponse {}))
+    }
+}
+
+impl From<Book> for pb::Book {
+    fn from(book: Book) -> Self {
+        pb::Book {
+            id: book.id.map(|id| id.to_hex()).unwrap_or_default(),
+            titulo: book.titulo,
+            autor: book.autor,
+            editorial: book.editorial,
+            anio: book.anio,
+            descripcion: book.descripcion,
+            numero_pagina: book.numero_pagina,
+            isbn: book.isbn,
+            isbn10: book.isbn10,
+            autor_id: book.autor_ref.map(|author| author.id.to_hex()),
+            editorial_id: book.editorial_ref.map(|publisher| publisher.id.to_hex()),
+            copias: book.copias,
+            prestados: book.prestados,
+            apartados: book.apartados,
+            version: book.version,
+        }
+    }
+}
+
+// Convierte el ID recibido en un ObjectId de MongoDB
+fn parse_id(id: &str) -> Result<ObjectId, AppError> {
+    ObjectId::parse_str(id).map_err(|_| AppError::InvalidIDError("ID inválido".to_string()))
+}
+
+// Usuario que realiza la llamada, tomado de los metadatos `x-user` (equivalente al encabezado `X-User`, tampoco se verifica)
+fn actor<T>(request: &Request<T>) -> String {
+    let user = request
+        .metadata()
+        .get(history::USER_HEADER.to_ascii_lowercase().as_str())
+        .and_then(|value| value.to_str().ok());
+    history::user_or_anonymous(user)
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::Serialize;
+use tokio::time::{timeout, Instant};
+
+// Dependencia externa que se verifica antes de considerar el servicio listo
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    // Nombre con el que aparece en el reporte (`mongodb`, `portadas`, ...)
+    fn name(&self) -> &'static str;
+
+    // Comprueba que la dependencia responde; el error se muestra en el reporte
+    async fn check(&self) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up, // La dependencia respondió a tiempo.
+    Down, // La dependencia falló o superó el tiempo de espera.
+}
+
+// Resultado de verificar una dependencia
+#[derive(Debug, Serialize)]
+pub struct DependencyReport {
+    pub status: CheckStatus,
+    pub latency_ms: u64, // Tiempo que tardó la verificación.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Reporte de disponibilidad con el detalle de cada dependencia
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: CheckStatus, // `up` solo si todas las dependencias están disponibles.
+    pub checks: BTreeMap<&'static str, DependencyReport>,
+}
+
+// Dependencias del servicio y tiempo máximo que se espera a cada una
+pub struct Readiness {
+    checks: Vec<Arc<dyn HealthCheck>>,
+    timeout: Duration,
+}
+
+impl Readiness {
+    pub fn new(checks: Vec<Arc<dyn HealthCheck>>, timeout: Duration) -> Self {
+        Readiness { checks, timeout }
+    }
+
+    // Verifica todas las dependencias en paralelo
+    pub async fn report(&self) -> ReadinessReport {
+        let results = join_all(self.checks.iter().map(|check| self.run(check.as_ref()))).await;
+        let status = if results.iter().all(|(_, report)| report.status == CheckStatus::Up) {
+            CheckStatus::Up
+        } else {
+            CheckStatus::Down
+        };
+        ReadinessReport {
+            status,
+            checks: results.into_iter().collect(),
+        }
+    }
+
+    async fn run(&self, check: &dyn HealthCheck) -> (&'static str, DependencyReport) {
+        let started = Instant::now();
+        let result = match timeout(self.timeout, check.check()).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("Sin respuesta después de {} ms", self.timeout.as_millis())),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+        if let Err(err) = &result {
+            log::warn!("La dependencia {} no está disponible: {}", check.name(), err);
+        }
+        let report = DependencyReport {
+            status: if result.is_ok() { CheckStatus::Up } else { CheckStatus::Down },
+            latency_ms,
+            error: result.err(),
+        };
+        (check.name(), report)
+    }
+}
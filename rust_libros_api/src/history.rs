@@ -0,0 +1,71 @@
+use actix_web::HttpRequest;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde_json::{Map, Value};
+
+use crate::model::{Book, BookHistoryEntry, FieldChange, HistoryAction};
+
+// Encabezado con el usuario que realiza el cambio. La API no tiene autenticación propia, así que el valor
+// lo declara el cliente sin verificarse: el usuario del historial es informativo, no una prueba de autoría.
+pub const USER_HEADER: &str = "X-User";
+const ANONYMOUS_USER: &str = "anonimo";
+
+// Usuario que realiza la solicitud, tomado del encabezado `X-User`.
+pub fn actor(req: &HttpRequest) -> String {
+    user_or_anonymous(req.headers().get(USER_HEADER).and_then(|value| value.to_str().ok()))
+}
+
+// Usuario indicado por el cliente, o `anonimo` si no lo indicó (también lo usa el servicio gRPC).
+pub fn user_or_anonymous(user: Option<&str>) -> String {
+    user.map(str::trim)
+        .filter(|user| !user.is_empty())
+        .unwrap_or(ANONYMOUS_USER)
+        .to_string()
+}
+
+// Entrada del historial para un cambio del libro. El repositorio la guarda junto con el cambio (en la misma
+// transacción o con el mismo bloqueo), así que no puede quedar un cambio sin su entrada ni al revés.
+pub fn entry(book_id: ObjectId, action: HistoryAction, user: &str, before: Option<&Book>, after: &Book) -> BookHistoryEntry {
+    BookHistoryEntry {
+        id: None,
+        book_id,
+        action,
+        user: user.to_string(),
+        timestamp: DateTime::now(),
+        changes: diff(before, after),
+        snapshot: after.clone(),
+    }
+}
+
+// Campos que cambiaron entre dos versiones del libro, con su valor anterior y nuevo.
+pub fn diff(before: Option<&Book>, after: &Book) -> Vec<FieldChange> {
+    let before = before.map(fields).unwrap_or_default();
+    let after = fields(after);
+
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old = before.get(name).cloned().unwrap_or(Value::Null);
+            let new = after.get(name).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: name.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+// Campos del libro como mapa JSON, sin el `_id`.
+fn fields(book: &Book) -> Map<String, Value> {
+    match serde_json::to_value(book) {
+        Ok(Value::Object(mut map)) => {
+            map.remove("_id");
+            map
+        }
+        _ => Map::new(),
+    }
+}
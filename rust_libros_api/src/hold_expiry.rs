@@ -0,0 +1,23 @@
+use std::{sync::Arc, time::Duration};
+
+use mongodb::bson::DateTime;
+
+use crate::repository::loan_repository::LoanRepository;
+
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(15 * 60); // Cada cuánto se revisan las reservas listas.
+
+// Tarea periódica que vence las reservas cuya copia apartada no se retiró a tiempo
+pub fn spawn_hold_expiry_job(repo: Arc<dyn LoanRepository>) {
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(EXPIRY_INTERVAL);
+        loop {
+            ticker.tick().await; // El primer tick es inmediato: revisa al iniciar el servidor
+
+            match repo.expire_holds(DateTime::now()).await {
+                Ok(0) => log::debug!("Reservas: ninguna reserva vencida"),
+                Ok(count) => log::info!("Reservas: {} reservas vencidas", count),
+                Err(err) => log::error!("Error al vencer las reservas: {}", err),
+            }
+        }
+    });
+}
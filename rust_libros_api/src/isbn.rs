@@ -0,0 +1,84 @@
+use crate::error::{AppError, FieldError};
+
+// Normaliza un ISBN-10 o ISBN-13 (con o sin guiones/espacios) a su forma ISBN-13.
+// Devuelve `None` si el formato o el dígito de control no son válidos.
+pub fn normalize(raw: &str) -> Option<String> {
+    let compact: String = raw
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    match compact.len() {
+        10 if is_valid_isbn10(&compact) => to_isbn13(&compact),
+        13 if is_valid_isbn13(&compact) => Some(compact),
+        _ => None,
+    }
+}
+
+// Igual que `normalize`, pero devuelve un error de validación sobre el campo `isbn`.
+pub fn parse(raw: &str) -> Result<String, AppError> {
+    normalize(raw).ok_or_else(|| {
+        AppError::ValidationError(vec![FieldError {
+            field: "isbn".to_string(),
+            code: "isbn".to_string(),
+            message: "El ISBN no es válido".to_string(),
+        }])
+    })
+}
+
+// Convierte un ISBN-10 válido en ISBN-13 (prefijo 978 y nuevo dígito de control).
+pub fn to_isbn13(isbn10: &str) -> Option<String> {
+    if !is_valid_isbn10(isbn10) {
+        return None;
+    }
+    let body = format!("978{}", &isbn10[..9]);
+    let check = isbn13_check_digit(&body)?;
+    Some(format!("{}{}", body, check))
+}
+
+// Convierte un ISBN-13 válido en ISBN-10; solo es posible con el prefijo 978.
+pub fn to_isbn10(isbn13: &str) -> Option<String> {
+    if !is_valid_isbn13(isbn13) || !isbn13.starts_with("978") {
+        return None;
+    }
+    let body = &isbn13[3..12];
+    let check = isbn10_check_digit(body)?;
+    Some(format!("{}{}", body, check))
+}
+
+// Verifica el formato y el dígito de control de un ISBN-10 compacto.
+fn is_valid_isbn10(isbn: &str) -> bool {
+    isbn.len() == 10
+        && isbn[..9].chars().all(|c| c.is_ascii_digit())
+        && isbn10_check_digit(&isbn[..9]) == isbn.chars().last()
+}
+
+// Verifica el formato y el dígito de control de un ISBN-13 compacto.
+fn is_valid_isbn13(isbn: &str) -> bool {
+    isbn.len() == 13
+        && isbn.chars().all(|c| c.is_ascii_digit())
+        && (isbn.starts_with("978") || isbn.starts_with("979"))
+        && isbn13_check_digit(&isbn[..12]) == isbn.chars().last()
+}
+
+// Dígito de control ISBN-10: suma ponderada de 10 a 2 módulo 11 (10 se escribe `X`).
+fn isbn10_check_digit(body: &str) -> Option<char> {
+    let mut sum = 0;
+    for (index, c) in body.chars().enumerate() {
+        sum += c.to_digit(10)? * (10 - index as u32);
+    }
+    match (11 - sum % 11) % 11 {
+        10 => Some('X'),
+        digit => char::from_digit(digit, 10),
+    }
+}
+
+// Dígito de control ISBN-13: pesos alternos 1 y 3 módulo 10.
+fn isbn13_check_digit(body: &str) -> Option<char> {
+    let mut sum = 0;
+    for (index, c) in body.chars().enumerate() {
+        sum += c.to_digit(10)? * if index % 2 == 0 { 1 } else { 3 };
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
@@ -0,0 +1,20 @@
+// Módulos del servicio; `main.rs` arma el servidor y las pruebas de integración usan la misma API.
+pub mod api; // Módulo que contiene la lógica de la API (endpoints).
+pub mod config; // Módulo con la configuración tipada y validada del servicio.
+pub mod covers; // Módulo que valida las portadas y genera sus miniaturas.
+pub mod error; // Módulo para manejar errores personalizados.
+pub mod grpc; // Módulo con el servicio gRPC del catálogo.
+pub mod health; // Módulo con las verificaciones de disponibilidad de las dependencias.
+pub mod history; // Módulo que registra el historial de cambios de los libros.
+pub mod hold_expiry; // Módulo con la tarea que vence las reservas que no se retiraron a tiempo.
+pub mod isbn; // Módulo para validar, normalizar y convertir ISBN.
+pub mod loans; // Módulo con las reglas de préstamos y reservas.
+pub mod metrics; // Módulo con las métricas de Prometheus.
+pub mod model; // Módulo que define los modelos de datos.
+pub mod openapi; // Módulo con el documento OpenAPI de la API.
+pub mod repository; // Módulo que maneja la interacción con la base de datos.
+pub mod purge; // Módulo con la tarea que vacía la papelera periódicamente.
+pub mod search; // Módulo con la relevancia y el resaltado de la búsqueda de texto.
+pub mod shutdown; // Módulo con el apagado ordenado al recibir SIGTERM o Ctrl+C.
+pub mod telemetry; // Módulo con los registros estructurados y el ID de cada solicitud.
+pub mod transfer; // Módulo con los formatos de importación y exportación (CSV y NDJSON).
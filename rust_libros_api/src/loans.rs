@@ -0,0 +1,89 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    model::{Book, HoldStatus, Loan, LoanStatus},
+};
+
+// Reglas de préstamos y reservas compartidas por todos los repositorios.
+
+// Verifica el límite de préstamos del socio y que no tenga ya este libro.
+pub fn check_member_can_borrow(active_loans: &[&Loan], book_id: ObjectId, max_loans: usize) -> Result<(), AppError> {
+    if active_loans.iter().any(|loan| loan.libro_id == book_id) {
+        return Err(AppError::Conflict("El socio ya tiene prestado este libro".to_string()));
+    }
+    if active_loans.len() >= max_loans {
+        return Err(AppError::Conflict(format!(
+            "El socio alcanzó el límite de {} préstamos activos",
+            max_loans
+        )));
+    }
+    Ok(())
+}
+
+// Verifica que un préstamo se pueda renovar en la fecha `now`.
+pub fn check_renewable(loan: &Loan, holds_waiting: bool, max_renewals: u32, now: DateTime) -> Result<(), AppError> {
+    if loan.estado != LoanStatus::Active {
+        return Err(AppError::Conflict("El préstamo ya fue devuelto".to_string()));
+    }
+    if loan.vence_en < now {
+        return Err(AppError::Conflict("Un préstamo vencido no se puede renovar".to_string()));
+    }
+    if loan.renovaciones >= max_renewals {
+        return Err(AppError::Conflict(format!(
+            "El préstamo alcanzó el límite de {} renovaciones",
+            max_renewals
+        )));
+    }
+    if holds_waiting {
+        return Err(AppError::Conflict("Otros socios están esperando este libro".to_string()));
+    }
+    Ok(())
+}
+
+// Verifica que el socio pueda entrar en la cola de reservas del libro.
+pub fn check_can_hold(book: &Book, has_loan: bool, has_hold: bool) -> Result<(), AppError> {
+    if has_loan {
+        return Err(AppError::Conflict("El socio ya tiene prestado este libro".to_string()));
+    }
+    if has_hold {
+        return Err(AppError::Conflict("El socio ya tiene una reserva de este libro".to_string()));
+    }
+    if book.disponibles() > 0 {
+        return Err(AppError::Conflict(
+            "Hay copias disponibles: el libro se puede prestar directamente".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Verifica que la nueva cantidad de copias alcance para las prestadas y apartadas.
+pub fn check_copies_cover_loans(book: &Book, copias: i32) -> Result<(), AppError> {
+    if copias < book.prestados + book.apartados {
+        return Err(AppError::Conflict(format!(
+            "El libro tiene {} copias prestadas o apartadas",
+            book.prestados + book.apartados
+        )));
+    }
+    Ok(())
+}
+
+// Verifica que no haya copias prestadas ni apartadas antes de enviar el libro a la papelera.
+pub fn check_no_copies_out(book: &Book) -> Result<(), AppError> {
+    if book.prestados + book.apartados > 0 {
+        return Err(AppError::Conflict(
+            "El libro tiene copias prestadas o apartadas".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Error cuando todas las copias están prestadas o apartadas.
+pub fn no_copies_available() -> AppError {
+    AppError::Conflict("No hay copias disponibles: se puede reservar el libro".to_string())
+}
+
+// Indica si la reserva sigue en la cola (esperando o con una copia apartada).
+pub fn is_pending(status: HoldStatus) -> bool {
+    matches!(status, HoldStatus::Waiting | HoldStatus::Ready)
+}
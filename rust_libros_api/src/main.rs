@@ -1,46 +1,146 @@
-mod api; // Módulo que contiene la lógica de la API (endpoints).
-mod error; // Módulo para manejar errores personalizados.
-mod model; // Módulo que define los modelos de datos.
-mod repository; // Módulo que maneja la interacción con la base de datos.
-
 use actix_cors::Cors; // Middleware para manejar CORS (Cross-Origin Resource Sharing).
-use actix_web::{middleware::Logger, web, App, HttpServer}; // Librerías principales de Actix Web.
-use api::book_api::{create_book, delete_book, get_all_books, update_book}; // Endpoints de la API.
+use actix_web::{
+    middleware::from_fn,
+    web, App, HttpServer,
+}; // Librerías principales de Actix Web.
 use dotenv::dotenv; // Carga variables de entorno desde un archivo .env.
 use mongodb::{options::ClientOptions, Client}; // Cliente de MongoDB y opciones de configuración.
-use repository::mongodb_repo::MongoRepo; // Repositorio para interactuar con MongoDB.
-use std::env; // Manejo de variables de entorno.
+use rust_mongodb_crud::{
+    api::{
+        self, // Rutas de la API (`/api`).
+        health_api::{live, ready}, // Endpoints de salud para Docker y el proxy.
+    },
+    config::{CliOptions, Config, CoverStorage, StorageBackend}, // Configuración cargada al iniciar.
+    grpc, // Servicio gRPC del catálogo.
+    health::{HealthCheck, Readiness}, // Verificaciones de las dependencias para `/health/ready`.
+    hold_expiry, // Tarea que vence las reservas que no se retiraron a tiempo.
+    metrics, // Métricas de Prometheus.
+    model::{Author, Publisher}, // Autores y editoriales que referencian los libros.
+    openapi::ApiDoc, // Documento OpenAPI generado a partir de los endpoints.
+    purge, // Tarea que vacía la papelera periódicamente.
+    repository::{
+        book_repository::BookRepository, // Contrato común de los repositorios de libros.
+        cover_repository::CoverRepository, // Contrato común de los almacenes de portadas.
+        fs_cover_repo::FsCoverRepo, // Almacén de portadas en el sistema de archivos.
+        loan_repository::LoanRepository, // Contrato común de los repositorios de préstamos y reservas.
+        reference_repository::ReferenceRepository, // Contrato común de los repositorios de autores y editoriales.
+        memory_repo::MemoryRepo, // Repositorio en memoria (sin MongoDB).
+        mongodb_repo::MongoRepo, // Repositorio para interactuar con MongoDB.
+        Repositories, // Un repositorio compartido por todos los contratos.
+    },
+    shutdown, // Apagado ordenado al recibir SIGTERM o Ctrl+C.
+    telemetry, // Registros estructurados y el ID de cada solicitud.
+};
+use std::{env, io, process, sync::Arc}; // Argumentos de la línea de comandos, salida del proceso y referencias compartidas.
+use tokio::sync::oneshot; // Avisa al servidor gRPC que debe detenerse.
+use utoipa::OpenApi; // Genera el documento OpenAPI.
+use utoipa_swagger_ui::SwaggerUi; // Interfaz de Swagger UI incluida en el binario.
 
 #[actix_web::main] // Macro que define el punto de entrada asíncrono para Actix Web.
 async fn main() -> std::io::Result<()> {
     dotenv().ok(); // Carga las variables de entorno desde el archivo .env.
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info")); // Inicializa el logger con un nivel de registro predeterminado.
-
-    // Obtiene la URI de MongoDB y el nombre de la base de datos desde las variables de entorno.
-    let mongo_uri = env::var("MONGO_URI").expect("MONGO_URI no está establecida en .env");
-    let mongo_db_name = env::var("MONGO_DB_NAME").expect("MONGO_DB_NAME no está establecida en .env");
-
-    // Configura las opciones del cliente de MongoDB.
-    let client_options = ClientOptions::parse(&mongo_uri)
-        .await
-        .expect("Error al analizar la URI de MongoDB");
-    let client = Client::with_options(client_options).expect("Error al crear el cliente de MongoDB");
-    let db = client.database(&mongo_db_name); // Obtiene la base de datos especificada.
-    
-    let mongo_repo = MongoRepo::new(db); // Crea una instancia del repositorio de MongoDB.
-    let mongo_data = web::Data::new(mongo_repo); // Envuelve el repositorio en un contenedor seguro para compartir datos.
-
-    // Obtiene la dirección y el puerto del servidor desde las variables de entorno o usa valores predeterminados.
-    let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let server_port = env::var("SERVER_PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse::<u16>()
-        .expect("SERVER_PORT debe ser un número");
-
-    log::info!("Iniciando servidor en http://{}:{}", server_host, server_port); // Registra un mensaje indicando que el servidor está iniciando.
-
-    // Configura y ejecuta el servidor HTTP.
-    HttpServer::new(move || {
+
+    // Lee las opciones de la línea de comandos (`--print-config`, `--config <ruta>`).
+    let options = CliOptions::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+    // Carga y valida toda la configuración una sola vez; si algo falta o es inválido, lista todos los problemas.
+    let config = Config::load(options.config_file.as_deref()).unwrap_or_else(|err| {
+        eprint!("{}", err);
+        process::exit(1);
+    });
+    if options.print_config {
+        print!("{}", config.to_toml()); // Muestra la configuración efectiva (sin secretos) y termina.
+        return Ok(());
+    }
+
+    telemetry::init(config.log_format); // Inicializa los registros estructurados (JSON salvo `log_format = "text"`).
+    log::info!("Configuración cargada: {:?}", config); // La contraseña de MongoDB no se muestra.
+
+    let loan_policy = config.loan_policy(); // Reglas de préstamo: días por préstamo, préstamos activos por socio y renovaciones.
+    let fs_covers = || -> Arc<FsCoverRepo> {
+        log::info!("Guardando las portadas en el directorio {}", config.cover_dir.display());
+        Arc::new(FsCoverRepo::new(&config.cover_dir).expect("No se pudo crear el directorio de portadas"))
+    };
+
+    // Un mismo repositorio implementa los contratos de libros, autores, editoriales y préstamos.
+    // También se reúnen las dependencias externas que verifica `/health/ready`.
+    let mut health_checks: Vec<Arc<dyn HealthCheck>> = Vec::new();
+    let mut mongo_client = None; // Se cierra al apagar el servidor.
+    let (repos, covers) = match config.storage_backend {
+        StorageBackend::Memory => {
+            log::info!("Usando almacenamiento en memoria");
+            // `Config::load` solo permite portadas en el sistema de archivos con este backend.
+            let fs_repo = fs_covers();
+            health_checks.push(fs_repo.clone());
+            let covers: Arc<dyn CoverRepository> = fs_repo;
+            (Repositories::new(Arc::new(MemoryRepo::new().with_loan_policy(loan_policy))), covers) // Los datos se pierden al detener el servidor.
+        }
+        StorageBackend::Mongo => {
+            // `Config::load` exige la URI y el nombre de la base de datos con este backend.
+            let mongo_uri = config.mongo_uri.as_ref().expect("MONGO_URI validada al cargar la configuración");
+            let mongo_db_name = config.mongo_db_name.as_deref().expect("MONGO_DB_NAME validada al cargar la configuración");
+
+            // Configura las opciones del cliente de MongoDB.
+            let client_options = ClientOptions::parse(mongo_uri.expose())
+                .await
+                .expect("Error al analizar la URI de MongoDB");
+            let client = Client::with_options(client_options).expect("Error al crear el cliente de MongoDB");
+            mongo_client = Some(client.clone());
+
+            log::info!("Usando almacenamiento en MongoDB");
+            // Crea una instancia del repositorio sobre la base de datos especificada (el cliente abre las transacciones).
+            let mongo_repo = MongoRepo::new(client, mongo_db_name).with_loan_policy(loan_policy);
+
+            // Crea los índices necesarios; sin los índices únicos (p. ej. el de ISBN) el servidor no arranca.
+            if let Err(err) = mongo_repo.ensure_indexes().await {
+                log::error!("No se pudieron crear los índices únicos de MongoDB: {}", err);
+                process::exit(1);
+            }
+
+            let mongo_repo = Arc::new(mongo_repo);
+            health_checks.push(mongo_repo.clone());
+            let covers: Arc<dyn CoverRepository> = match config.cover_storage {
+                CoverStorage::GridFs => mongo_repo.clone(), // Las portadas se guardan en GridFS, en la misma base de datos.
+                CoverStorage::Fs => {
+                    let fs_repo = fs_covers();
+                    health_checks.push(fs_repo.clone());
+                    fs_repo
+                }
+            };
+            (Repositories::new(mongo_repo), covers)
+        }
+    };
+    // Purga de la papelera: días que se conservan los libros y cada cuántos minutos se revisa.
+    purge::spawn_purge_job(
+        repos.books.clone(),
+        covers.clone(),
+        config.trash_retention(),
+        config.trash_purge_interval(),
+    );
+    hold_expiry::spawn_hold_expiry_job(repos.loans.clone()); // Vence las reservas listas que nadie retiró.
+
+    let grpc_books = repos.books.clone(); // El servicio gRPC comparte el repositorio de libros.
+    let book_data: web::Data<dyn BookRepository> = web::Data::from(repos.books); // Envuelve el repositorio en un contenedor seguro para compartir datos.
+    let author_data: web::Data<dyn ReferenceRepository<Author>> = web::Data::from(repos.authors);
+    let publisher_data: web::Data<dyn ReferenceRepository<Publisher>> = web::Data::from(repos.publishers);
+    let loan_data: web::Data<dyn LoanRepository> = web::Data::from(repos.loans);
+    let cover_data: web::Data<dyn CoverRepository> = web::Data::from(covers);
+
+    let readiness_data = web::Data::new(Readiness::new(health_checks, config.health_check_timeout())); // Cada dependencia tiene un tiempo máximo.
+
+    // Direcciones de los servidores REST y gRPC (`SERVER_HOST` puede ser un nombre como `localhost`).
+    let (http_addr, grpc_addr) = config.server_addrs().await?;
+
+    log::info!("Iniciando servidor en http://{}", http_addr); // Registra un mensaje indicando que el servidor está iniciando.
+    log::info!("Iniciando servidor gRPC en {}", grpc_addr);
+
+    metrics::init(); // Registra las métricas de Prometheus antes de atender solicitudes.
+    let openapi = ApiDoc::openapi(); // El documento se genera una sola vez y se comparte entre los workers.
+
+    // Configura el servidor HTTP.
+    let http_server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin() // Permite solicitudes desde cualquier origen.
             .allow_any_method() // Permite cualquier método HTTP (GET, POST, etc.).
@@ -48,17 +148,53 @@ async fn main() -> std::io::Result<()> {
 
         App::new()
             .wrap(cors) // Aplica el middleware de CORS.
-            .wrap(Logger::default()) // Aplica el middleware de registro de solicitudes.
-            .app_data(mongo_data.clone()) // Comparte el repositorio de MongoDB con las rutas.
+            .wrap(from_fn(metrics::track_requests)) // Cuenta las solicitudes y mide su latencia por ruta.
+            .wrap(from_fn(shutdown::track_in_flight)) // Cuenta las solicitudes en curso para el resumen del apagado.
+            .wrap(from_fn(telemetry::request_context)) // Asigna el `X-Request-Id` y registra cada solicitud (el más externo).
+            .app_data(book_data.clone()) // Comparte el repositorio de libros con las rutas.
+            .app_data(author_data.clone()) // Comparte el repositorio de autores con las rutas.
+            .app_data(publisher_data.clone()) // Comparte el repositorio de editoriales con las rutas.
+            .app_data(loan_data.clone()) // Comparte el repositorio de préstamos con las rutas.
+            .app_data(cover_data.clone()) // Comparte el almacén de portadas con las rutas.
+            .app_data(readiness_data.clone()) // Comparte las dependencias que verifica `/health/ready`.
+            .service(live) // Endpoint de vida (`/health/live`, fuera del prefijo `/api`).
+            .service(ready) // Endpoint de disponibilidad (`/health/ready`).
+            .service(metrics::metrics) // Endpoint de métricas para Prometheus (`/metrics`).
             .service(
-                web::scope("/api") // Define un prefijo para las rutas de la API.
-                    .service(get_all_books) // Endpoint para obtener todos los libros.
-                    .service(create_book) // Endpoint para crear un libro.
-                    .service(update_book) // Endpoint para actualizar un libro.
-                    .service(delete_book), // Endpoint para eliminar un libro.
+                // Swagger UI en `/api/docs` y el documento en `/api/openapi.json` (antes del scope `/api`).
+                SwaggerUi::new("/api/docs/{_:.*}").url("/api/openapi.json", openapi.clone()),
             )
+            .service(web::scope("/api").configure(api::routes)) // Rutas de la API bajo el prefijo `/api`.
     })
-    .bind((server_host, server_port))? // Asocia el servidor a la dirección y puerto especificados.
-    .run() // Ejecuta el servidor.
-    .await // Espera a que el servidor termine de ejecutarse.
-}
\ No newline at end of file
+    .disable_signals() // Las señales se manejan en `shutdown` para esperar las solicitudes, detener gRPC y cerrar MongoDB.
+    .bind(http_addr)? // Asocia el servidor a la dirección y puerto especificados.
+    .run(); // Crea el servidor (se ejecuta al esperarlo).
+
+    // Ejecuta ambos servidores hasta recibir SIGTERM o Ctrl+C; si gRPC falla, también se detiene el servidor HTTP.
+    let http_handle = http_server.handle();
+    let (stop_grpc, grpc_stopped) = oneshot::channel::<()>();
+    let grpc_server = async {
+        let shutdown = async {
+            let _ = grpc_stopped.await;
+        };
+        let result = grpc::serve(grpc_addr, grpc_books, shutdown).await;
+        if let Err(err) = &result {
+            log::error!("El servidor gRPC se detuvo por un error: {}", err);
+            http_handle.stop(true).await;
+        }
+        result
+    };
+    let http_server = shutdown::serve_until_signal(http_server, config.shutdown_timeout(), move || {
+        let _ = stop_grpc.send(()); // gRPC deja de aceptar llamadas al mismo tiempo que HTTP.
+    });
+    let (summary, grpc_result) = tokio::join!(http_server, grpc_server);
+
+    // Cierra las conexiones con MongoDB cuando ya no quedan solicitudes en curso.
+    if let Some(client) = mongo_client {
+        client.shutdown().await;
+        log::info!("Conexión con MongoDB cerrada");
+    }
+    let summary = summary?;
+    summary.log();
+    grpc_result.map_err(io::Error::other)
+}
@@ -1,9 +1,22 @@
-use mongodb::bson::oid::ObjectId; // Importa el tipo ObjectId de la biblioteca de MongoDB para manejar identificadores únicos.
-use serde::{Deserialize, Serialize}; // Importa las macros Serialize y Deserialize para serialización y deserialización.
+use mongodb::bson::{oid::ObjectId, DateTime}; // Importa los tipos ObjectId y DateTime de MongoDB para identificadores únicos y fechas.
+use serde::{de::DeserializeOwned, Deserialize, Serialize}; // Importa las macros Serialize y Deserialize para serialización y deserialización.
 
-#[derive(Debug, Serialize, Deserialize)] // Deriva las implementaciones de Debug, Serialize y Deserialize para la estructura.
+use std::collections::BTreeMap; // Mapa ordenado para los fragmentos resaltados de la búsqueda.
+
+use chrono::{Datelike, Utc}; // Fecha actual, usada para validar el año de publicación.
+use validator::{Validate, ValidationError}; // Validación declarativa de los DTOs.
+use utoipa::{IntoParams, ToSchema}; // Esquemas y parámetros del documento OpenAPI.
+
+use crate::{
+    error::AppError, // Errores de la aplicación.
+    isbn, // Utilidades de ISBN (usadas al validar).
+    openapi::{DateTimeJson, ObjectIdJson}, // Forma JSON de los tipos de BSON en el documento OpenAPI.
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)] // Deriva las implementaciones de Debug, Clone, Serialize, Deserialize y ToSchema (documento OpenAPI) para la estructura.
 pub struct Book {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")] // Renombra el campo `id` como `_id` al serializar y omite si es None.
+    #[schema(value_type = Option<ObjectIdJson>)]
     pub id: Option<ObjectId>, // Identificador único opcional del libro (usado en MongoDB).
     pub titulo: String, // Título del libro.
     pub autor: String, // Autor del libro.
@@ -11,24 +24,849 @@ pub struct Book {
     pub anio: i32, // Año de publicación del libro.
     pub descripcion: String, // Descripción del libro.
     pub numero_pagina: i32, // Número de páginas del libro.
+    #[serde(default, skip_serializing_if = "Option::is_none")] // Los libros antiguos no tienen ISBN.
+    pub isbn: Option<String>, // ISBN-13 normalizado (sin guiones), único en el catálogo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isbn10: Option<String>, // ISBN-10 equivalente (solo para ISBN con prefijo 978).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub deleted_at: Option<DateTime>, // Fecha en que se envió a la papelera (`None` si está activo).
+    #[serde(default, skip_serializing_if = "Option::is_none")] // Los libros antiguos solo tienen el autor como texto.
+    pub autor_ref: Option<AuthorSummary>, // Autor referenciado (su nombre se copia en `autor`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editorial_ref: Option<PublisherSummary>, // Editorial referenciada (su nombre se copia en `editorial`).
+    #[serde(default = "default_copias")] // Los libros antiguos tienen una sola copia.
+    pub copias: i32, // Cantidad total de copias físicas.
+    #[serde(default)]
+    pub prestados: i32, // Copias prestadas en este momento.
+    #[serde(default)]
+    pub apartados: i32, // Copias devueltas y apartadas para la primera reserva de la cola.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub portada: Option<Cover>, // Datos de la imagen de portada (los bytes están en el almacén de portadas).
+    #[serde(default)] // Los libros antiguos empiezan en la versión 0.
+    pub version: i64, // Aumenta con cada escritura; las escrituras con `If-Match` exigen que no haya cambiado.
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Cover {
+    pub content_type: String, // Tipo de la imagen original (`image/jpeg`, `image/png` o `image/webp`).
+    pub bytes: u64, // Tamaño de la imagen original.
+    pub ancho: u32, // Ancho en píxeles de la imagen original.
+    pub alto: u32, // Alto en píxeles de la imagen original.
+    pub hash: String, // Huella del contenido, usada como ETag de cada tamaño.
+    #[schema(value_type = DateTimeJson)]
+    pub actualizada_en: DateTime, // Fecha en que se subió la portada.
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverSize {
+    #[default]
+    Original, // La imagen tal como se subió.
+    Medium, // Miniatura de 400x600 para la ficha del libro.
+    Thumb, // Miniatura de 160x240 para los listados.
+}
+
+impl CoverSize {
+    // Tamaños generados a partir de la imagen original.
+    pub const THUMBNAILS: [CoverSize; 2] = [CoverSize::Medium, CoverSize::Thumb];
+    // Todos los tamaños que se guardan de cada portada.
+    pub const ALL: [CoverSize; 3] = [CoverSize::Original, CoverSize::Medium, CoverSize::Thumb];
+
+    // Dimensiones fijas (ancho, alto) de la miniatura; `None` para la original.
+    pub fn dimensions(self) -> Option<(u32, u32)> {
+        match self {
+            CoverSize::Original => None,
+            CoverSize::Medium => Some((400, 600)),
+            CoverSize::Thumb => Some((160, 240)),
+        }
+    }
+
+    // Nombre del tamaño, igual al valor del parámetro `size`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CoverSize::Original => "original",
+            CoverSize::Medium => "medium",
+            CoverSize::Thumb => "thumb",
+        }
+    }
+
+    // Clave con la que se guarda este tamaño de la portada del libro. Incluye la huella de la imagen, así que subir
+    // otra portada no reemplaza las imágenes que el libro todavía usa.
+    pub fn key(self, book_id: ObjectId, cover: &Cover) -> String {
+        format!("{}_{}_{}", book_id.to_hex(), cover.hash, self.name())
+    }
+
+    // Clave de las portadas subidas antes de que la clave incluyera la huella.
+    pub fn legacy_key(self, book_id: ObjectId) -> String {
+        match self {
+            CoverSize::Original => book_id.to_hex(),
+            size => format!("{}_{}", book_id.to_hex(), size.name()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)] // Se deserializa desde la query string de la portada.
+#[into_params(parameter_in = Query)]
+pub struct CoverQuery {
+    #[serde(default)]
+    pub size: CoverSize, // Tamaño solicitado (`original` por defecto).
+}
+
+// Cantidad de copias de los libros que no la indican.
+fn default_copias() -> i32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Author {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
+    pub id: Option<ObjectId>, // Identificador único del autor.
+    pub nombre: String, // Nombre del autor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nacionalidad: Option<String>, // Nacionalidad del autor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub biografia: Option<String>, // Biografía breve del autor.
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuthorSummary {
+    #[serde(rename = "_id")]
+    #[schema(value_type = ObjectIdJson)]
+    pub id: ObjectId, // Identificador del autor.
+    pub nombre: String, // Nombre del autor.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Publisher {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
+    pub id: Option<ObjectId>, // Identificador único de la editorial.
+    pub nombre: String, // Nombre de la editorial.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pais: Option<String>, // País de la editorial.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sitio_web: Option<String>, // Sitio web de la editorial.
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PublisherSummary {
+    #[serde(rename = "_id")]
+    #[schema(value_type = ObjectIdJson)]
+    pub id: ObjectId, // Identificador de la editorial.
+    pub nombre: String, // Nombre de la editorial.
+}
+
+// Autor o editorial registrado: los libros lo referencian por ID y guardan una copia de su nombre.
+pub trait BookReference: Clone + Serialize + DeserializeOwned + Send + Sync + Unpin + 'static {
+    type CreateDto: Clone + DeserializeOwned + Validate + Send + Sync + 'static; // Datos para registrarlo.
+    type UpdateDto: Clone + DeserializeOwned + Validate + Send + Sync + 'static; // Datos para modificarlo.
+
+    const FIELD: &'static str; // Campo del libro con el nombre (`autor` o `editorial`).
+    const ID_FIELD: &'static str; // Campo de los DTO de libro con el ID (`autor_id` o `editorial_id`).
+    const REF_FIELD: &'static str; // Campo del libro con el resumen (`autor_ref` o `editorial_ref`).
+    const NOT_FOUND: &'static str; // Mensaje cuando el ID indicado no existe.
+    const DUPLICATE: &'static str; // Mensaje cuando ya hay otro registro con el mismo nombre.
+
+    // Registro creado a partir del DTO recibido.
+    fn from_dto(id: Option<ObjectId>, dto: Self::CreateDto) -> Self;
+
+    // Registro con solo el nombre (se crea al escribir en un libro un nombre que no está registrado).
+    fn named(id: Option<ObjectId>, nombre: String) -> Self;
+
+    // Aplica los campos presentes en el DTO.
+    fn apply(&mut self, dto: Self::UpdateDto);
+
+    fn id(&self) -> Option<ObjectId>;
+
+    fn nombre(&self) -> &str;
+
+    // Mensaje cuando no se puede eliminar porque `references` libros todavía lo referencian.
+    fn in_use(references: u64) -> String;
+
+    // ID del registro que referencia el libro, si referencia alguno.
+    fn referenced_id(book: &Book) -> Option<ObjectId>;
+
+    // Nombre que tiene el libro en el campo `FIELD`.
+    fn book_name(book: &Book) -> &str;
+
+    // Asigna el registro al libro y copia su nombre en el campo `FIELD`.
+    fn link(&self, book: &mut Book);
+
+    // Limita el listado de libros a los que referencian el registro con el ID indicado.
+    fn filter_books(filter: &mut BookFilter, id: ObjectId);
 }
 
-#[derive(Debug, Serialize, Deserialize)] // Deriva las implementaciones de Debug, Serialize y Deserialize para la estructura.
+impl BookReference for Author {
+    type CreateDto = CreateAuthorDto;
+    type UpdateDto = UpdateAuthorDto;
+
+    const FIELD: &'static str = "autor";
+    const ID_FIELD: &'static str = "autor_id";
+    const REF_FIELD: &'static str = "autor_ref";
+    const NOT_FOUND: &'static str = "El autor no existe";
+    const DUPLICATE: &'static str = "Ya existe un autor con ese nombre";
+
+    fn from_dto(id: Option<ObjectId>, dto: CreateAuthorDto) -> Self {
+        Author {
+            id,
+            nombre: dto.nombre,
+            nacionalidad: dto.nacionalidad,
+            biografia: dto.biografia,
+        }
+    }
+
+    fn named(id: Option<ObjectId>, nombre: String) -> Self {
+        Author {
+            id,
+            nombre,
+            nacionalidad: None,
+            biografia: None,
+        }
+    }
+
+    fn apply(&mut self, dto: UpdateAuthorDto) {
+        if let Some(nombre) = dto.nombre {
+            self.nombre = nombre;
+        }
+        if let Some(nacionalidad) = dto.nacionalidad {
+            self.nacionalidad = Some(nacionalidad);
+        }
+        if let Some(biografia) = dto.biografia {
+            self.biografia = Some(biografia);
+        }
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn nombre(&self) -> &str {
+        &self.nombre
+    }
+
+    fn in_use(references: u64) -> String {
+        format!("El autor está referenciado por {} libro(s)", references)
+    }
+
+    fn referenced_id(book: &Book) -> Option<ObjectId> {
+        book.autor_ref.as_ref().map(|author| author.id)
+    }
+
+    fn book_name(book: &Book) -> &str {
+        &book.autor
+    }
+
+    fn link(&self, book: &mut Book) {
+        book.autor = self.nombre.clone();
+        book.autor_ref = self.id.map(|id| AuthorSummary {
+            id,
+            nombre: self.nombre.clone(),
+        });
+    }
+
+    fn filter_books(filter: &mut BookFilter, id: ObjectId) {
+        filter.autor_id = Some(id);
+    }
+}
+
+impl BookReference for Publisher {
+    type CreateDto = CreatePublisherDto;
+    type UpdateDto = UpdatePublisherDto;
+
+    const FIELD: &'static str = "editorial";
+    const ID_FIELD: &'static str = "editorial_id";
+    const REF_FIELD: &'static str = "editorial_ref";
+    const NOT_FOUND: &'static str = "La editorial no existe";
+    const DUPLICATE: &'static str = "Ya existe una editorial con ese nombre";
+
+    fn from_dto(id: Option<ObjectId>, dto: CreatePublisherDto) -> Self {
+        Publisher {
+            id,
+            nombre: dto.nombre,
+            pais: dto.pais,
+            sitio_web: dto.sitio_web,
+        }
+    }
+
+    fn named(id: Option<ObjectId>, nombre: String) -> Self {
+        Publisher {
+            id,
+            nombre,
+            pais: None,
+            sitio_web: None,
+        }
+    }
+
+    fn apply(&mut self, dto: UpdatePublisherDto) {
+        if let Some(nombre) = dto.nombre {
+            self.nombre = nombre;
+        }
+        if let Some(pais) = dto.pais {
+            self.pais = Some(pais);
+        }
+        if let Some(sitio_web) = dto.sitio_web {
+            self.sitio_web = Some(sitio_web);
+        }
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn nombre(&self) -> &str {
+        &self.nombre
+    }
+
+    fn in_use(references: u64) -> String {
+        format!("La editorial está referenciada por {} libro(s)", references)
+    }
+
+    fn referenced_id(book: &Book) -> Option<ObjectId> {
+        book.editorial_ref.as_ref().map(|publisher| publisher.id)
+    }
+
+    fn book_name(book: &Book) -> &str {
+        &book.editorial
+    }
+
+    fn link(&self, book: &mut Book) {
+        book.editorial = self.nombre.clone();
+        book.editorial_ref = self.id.map(|id| PublisherSummary {
+            id,
+            nombre: self.nombre.clone(),
+        });
+    }
+
+    fn filter_books(filter: &mut BookFilter, id: ObjectId) {
+        filter.editorial_id = Some(id);
+    }
+}
+
+// Clave con la que se comparan los nombres de autores y editoriales: sin distinguir mayúsculas ni tildes
+// (la misma regla que la intercalación de los índices de nombre en MongoDB).
+pub fn name_key(nombre: &str) -> String {
+    nombre
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' | 'â' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            other => other,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Create, // El libro fue creado.
+    Update, // Se modificaron campos del libro.
+    Delete, // El libro fue enviado a la papelera.
+    Restore, // El libro salió de la papelera.
+    Revert, // El libro volvió a una versión anterior.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    pub field: String, // Nombre del campo modificado.
+    pub before: serde_json::Value, // Valor anterior (`null` si no existía).
+    pub after: serde_json::Value, // Valor nuevo (`null` si se quitó).
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BookHistoryEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
+    pub id: Option<ObjectId>, // Identificador de la entrada del historial.
+    #[schema(value_type = ObjectIdJson)]
+    pub book_id: ObjectId, // Libro al que pertenece la entrada.
+    pub action: HistoryAction, // Tipo de cambio.
+    pub user: String, // Usuario que declaró el cliente en `X-User` (no se verifica).
+    #[schema(value_type = DateTimeJson)]
+    pub timestamp: DateTime, // Fecha del cambio.
+    pub changes: Vec<FieldChange>, // Campos modificados con sus valores anterior y nuevo.
+    pub snapshot: Book, // Estado completo del libro después del cambio (para revertir).
+}
+
+impl Book {
+    // Asigna el ISBN normalizado (ISBN-13) y su equivalente ISBN-10.
+    pub fn with_isbn(mut self, isbn: Option<String>) -> Result<Self, AppError> {
+        self.isbn = isbn.as_deref().map(isbn::parse).transpose()?;
+        self.isbn10 = self.isbn.as_deref().and_then(isbn::to_isbn10);
+        Ok(self)
+    }
+
+    // Copias que se pueden prestar ahora mismo.
+    pub fn disponibles(&self) -> i32 {
+        self.copias - self.prestados - self.apartados
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)] // Deriva las implementaciones de Debug, Serialize, Deserialize, Validate y ToSchema para la estructura.
+#[allow(clippy::duplicated_attributes)] // Son dos validaciones de estructura distintas, no un atributo repetido.
+#[validate(schema(function = "validate_author_reference", skip_on_field_errors = false))]
+#[validate(schema(function = "validate_publisher_reference", skip_on_field_errors = false))]
 pub struct CreateBookDto {
+    #[validate(custom = "validate_not_blank", length(max = 200, message = "El título no puede superar los 200 caracteres"))]
     pub titulo: String, // Título del libro (requerido para crear un libro).
-    pub autor: String, // Autor del libro (requerido para crear un libro).
-    pub editorial: String, // Editorial del libro (requerido para crear un libro).
+    #[validate(length(max = 120, message = "El autor no puede superar los 120 caracteres"))]
+    #[serde(default)]
+    pub autor: String, // Nombre del autor (requerido si no se indica `autor_id`; se asocia al autor registrado con ese nombre o se registra uno).
+    #[validate(custom = "validate_object_id")]
+    #[serde(default)]
+    pub autor_id: Option<String>, // ID del autor registrado (tiene prioridad sobre `autor`).
+    #[validate(length(max = 120, message = "La editorial no puede superar los 120 caracteres"))]
+    #[serde(default)]
+    pub editorial: String, // Nombre de la editorial (requerido si no se indica `editorial_id`; se asocia a la editorial registrada con ese nombre o se registra una).
+    #[validate(custom = "validate_object_id")]
+    #[serde(default)]
+    pub editorial_id: Option<String>, // ID de la editorial registrada (tiene prioridad sobre `editorial`).
+    #[validate(custom = "validate_anio")]
     pub anio: i32, // Año de publicación del libro (requerido para crear un libro).
+    #[validate(length(max = 5000, message = "La descripción no puede superar los 5000 caracteres"))]
     pub descripcion: String, // Descripción del libro (requerido para crear un libro).
+    #[validate(range(min = 1, max = 100000, message = "El número de páginas debe estar entre 1 y 100000"))]
     pub numero_pagina: i32, // Número de páginas del libro (requerido para crear un libro).
+    #[validate(custom = "validate_isbn")]
+    #[serde(default)]
+    pub isbn: Option<String>, // ISBN-10 o ISBN-13 del libro (opcional).
+    #[validate(range(min = 1, max = 10000, message = "Las copias deben estar entre 1 y 10000"))]
+    #[serde(default)]
+    pub copias: Option<i32>, // Cantidad de copias físicas (1 si no se indica).
 }
 
-#[derive(Debug, Serialize, Deserialize)] // Deriva las implementaciones de Debug, Serialize y Deserialize para la estructura.
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)] // Deriva las implementaciones de Debug, Serialize, Deserialize, Validate y ToSchema para la estructura.
 pub struct UpdateBookDto {
+    #[validate(custom = "validate_not_blank", length(max = 200, message = "El título no puede superar los 200 caracteres"))]
     pub titulo: Option<String>, // Título del libro (opcional para actualizar un libro).
-    pub autor: Option<String>, // Autor del libro (opcional para actualizar un libro).
-    pub editorial: Option<String>, // Editorial del libro (opcional para actualizar un libro).
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "El autor no puede superar los 120 caracteres"))]
+    pub autor: Option<String>, // Nombre del autor (opcional; se asocia al autor registrado con ese nombre o se registra uno).
+    #[validate(custom = "validate_object_id")]
+    pub autor_id: Option<String>, // ID del autor registrado (opcional; tiene prioridad sobre `autor`).
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "La editorial no puede superar los 120 caracteres"))]
+    pub editorial: Option<String>, // Nombre de la editorial (opcional; se asocia a la editorial registrada con ese nombre o se registra una).
+    #[validate(custom = "validate_object_id")]
+    pub editorial_id: Option<String>, // ID de la editorial registrada (opcional; tiene prioridad sobre `editorial`).
+    #[validate(custom = "validate_anio")]
     pub anio: Option<i32>, // Año de publicación del libro (opcional para actualizar un libro).
+    #[validate(length(max = 5000, message = "La descripción no puede superar los 5000 caracteres"))]
     pub descripcion: Option<String>, // Descripción del libro (opcional para actualizar un libro).
+    #[validate(range(min = 1, max = 100000, message = "El número de páginas debe estar entre 1 y 100000"))]
     pub numero_pagina: Option<i32>, // Número de páginas del libro (opcional para actualizar un libro).
-}
\ No newline at end of file
+    #[validate(custom = "validate_isbn")]
+    pub isbn: Option<String>, // ISBN-10 o ISBN-13 del libro (opcional para actualizar un libro).
+    #[validate(range(min = 1, max = 10000, message = "Las copias deben estar entre 1 y 10000"))]
+    pub copias: Option<i32>, // Cantidad de copias físicas (no puede quedar debajo de las prestadas y apartadas).
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateAuthorDto {
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "El nombre no puede superar los 120 caracteres"))]
+    pub nombre: String, // Nombre del autor (requerido).
+    #[validate(length(max = 80, message = "La nacionalidad no puede superar los 80 caracteres"))]
+    pub nacionalidad: Option<String>, // Nacionalidad del autor (opcional).
+    #[validate(length(max = 5000, message = "La biografía no puede superar los 5000 caracteres"))]
+    pub biografia: Option<String>, // Biografía del autor (opcional).
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateAuthorDto {
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "El nombre no puede superar los 120 caracteres"))]
+    pub nombre: Option<String>, // Nombre del autor (se actualiza también en sus libros).
+    #[validate(length(max = 80, message = "La nacionalidad no puede superar los 80 caracteres"))]
+    pub nacionalidad: Option<String>, // Nacionalidad del autor.
+    #[validate(length(max = 5000, message = "La biografía no puede superar los 5000 caracteres"))]
+    pub biografia: Option<String>, // Biografía del autor.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreatePublisherDto {
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "El nombre no puede superar los 120 caracteres"))]
+    pub nombre: String, // Nombre de la editorial (requerido).
+    #[validate(length(max = 80, message = "El país no puede superar los 80 caracteres"))]
+    pub pais: Option<String>, // País de la editorial (opcional).
+    #[validate(url(message = "El sitio web debe ser una URL válida"))]
+    pub sitio_web: Option<String>, // Sitio web de la editorial (opcional).
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdatePublisherDto {
+    #[validate(custom = "validate_not_blank", length(max = 120, message = "El nombre no puede superar los 120 caracteres"))]
+    pub nombre: Option<String>, // Nombre de la editorial (se actualiza también en sus libros).
+    #[validate(length(max = 80, message = "El país no puede superar los 80 caracteres"))]
+    pub pais: Option<String>, // País de la editorial.
+    #[validate(url(message = "El sitio web debe ser una URL válida"))]
+    pub sitio_web: Option<String>, // Sitio web de la editorial.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LoanStatus {
+    Active, // El socio tiene la copia.
+    Returned, // La copia fue devuelta.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Loan {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
+    pub id: Option<ObjectId>, // Identificador del préstamo.
+    #[schema(value_type = ObjectIdJson)]
+    pub libro_id: ObjectId, // Libro prestado.
+    pub socio_id: String, // Socio que tiene el libro.
+    pub estado: LoanStatus, // Estado del préstamo.
+    #[schema(value_type = DateTimeJson)]
+    pub prestado_en: DateTime, // Fecha del préstamo.
+    #[schema(value_type = DateTimeJson)]
+    pub vence_en: DateTime, // Fecha en que se debe devolver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub devuelto_en: Option<DateTime>, // Fecha de devolución.
+    #[serde(default)]
+    pub renovaciones: u32, // Veces que se extendió el vencimiento.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldStatus {
+    Waiting, // En la cola, esperando que se devuelva una copia.
+    Ready, // Hay una copia apartada para el socio.
+    Fulfilled, // El socio retiró la copia apartada.
+    Cancelled, // La reserva se canceló (también al enviar el libro a la papelera).
+    Expired, // El socio no retiró la copia apartada a tiempo.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Hold {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdJson>)]
+    pub id: Option<ObjectId>, // Identificador de la reserva.
+    #[schema(value_type = ObjectIdJson)]
+    pub libro_id: ObjectId, // Libro reservado.
+    pub socio_id: String, // Socio que reservó.
+    pub estado: HoldStatus, // Estado de la reserva.
+    #[schema(value_type = DateTimeJson)]
+    pub creado_en: DateTime, // Fecha de la reserva (define el orden de la cola).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<DateTimeJson>)]
+    pub listo_en: Option<DateTime>, // Fecha en que se apartó una copia para el socio.
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateLoanDto {
+    #[validate(custom = "validate_object_id")]
+    pub libro_id: String, // ID del libro a prestar.
+    #[validate(custom = "validate_not_blank", length(max = 64, message = "El socio no puede superar los 64 caracteres"))]
+    pub socio_id: String, // Identificador del socio.
+}
+
+// Mismos datos que un préstamo: libro y socio.
+pub type CreateHoldDto = CreateLoanDto;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoanPolicy {
+    pub loan_days: i64, // Días que dura un préstamo (y cada renovación).
+    pub max_loans: usize, // Préstamos activos permitidos por socio.
+    pub max_renewals: u32, // Renovaciones permitidas por préstamo.
+    pub hold_days: i64, // Días que una copia apartada espera al socio de la reserva.
+}
+
+impl Default for LoanPolicy {
+    fn default() -> Self {
+        LoanPolicy {
+            loan_days: 14,
+            max_loans: 3,
+            max_renewals: 2,
+            hold_days: 3,
+        }
+    }
+}
+
+impl LoanPolicy {
+    // Fecha de vencimiento a `loan_days` días de `from`.
+    pub fn due_date(&self, from: DateTime) -> DateTime {
+        DateTime::from_millis(from.timestamp_millis() + self.loan_days * 24 * 60 * 60 * 1000)
+    }
+
+    // Las reservas con una copia apartada antes de esta fecha vencieron.
+    pub fn hold_cutoff(&self, now: DateTime) -> DateTime {
+        DateTime::from_millis(now.timestamp_millis() - self.hold_days * 24 * 60 * 60 * 1000)
+    }
+}
+
+// Regla de validación: el libro necesita el nombre del autor o el ID de un autor registrado.
+fn validate_author_reference(dto: &CreateBookDto) -> Result<(), ValidationError> {
+    if dto.autor_id.is_none() {
+        return validate_not_blank(&dto.autor).map_err(|error| with_field(error, "autor"));
+    }
+    Ok(())
+}
+
+// Regla de validación: el libro necesita el nombre de la editorial o el ID de una editorial registrada.
+fn validate_publisher_reference(dto: &CreateBookDto) -> Result<(), ValidationError> {
+    if dto.editorial_id.is_none() {
+        return validate_not_blank(&dto.editorial).map_err(|error| with_field(error, "editorial"));
+    }
+    Ok(())
+}
+
+// Indica a qué campo corresponde un error de validación a nivel de estructura.
+fn with_field(mut error: ValidationError, field: &'static str) -> ValidationError {
+    error.add_param("field".into(), &field);
+    error
+}
+
+// Regla de validación: el texto debe ser un ObjectId de MongoDB (24 caracteres hexadecimales).
+fn validate_object_id(value: &str) -> Result<(), ValidationError> {
+    if ObjectId::parse_str(value).is_err() {
+        let mut error = ValidationError::new("object_id");
+        error.message = Some("El ID no es válido".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+// Regla de validación: el texto no puede estar vacío ni contener solo espacios.
+fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = ValidationError::new("blank");
+        error.message = Some("El campo no puede estar vacío".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+// Regla de validación: el ISBN debe ser un ISBN-10 o ISBN-13 con dígito de control correcto.
+fn validate_isbn(value: &str) -> Result<(), ValidationError> {
+    if isbn::normalize(value).is_none() {
+        let mut error = ValidationError::new("isbn");
+        error.message = Some("El ISBN no es válido".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+// Regla de validación: el año debe ser positivo y no estar en el futuro.
+fn validate_anio(anio: i32) -> Result<(), ValidationError> {
+    let current_year = Utc::now().year();
+    if anio < 1 || anio > current_year {
+        let mut error = ValidationError::new("range");
+        error.message = Some(format!("El año debe estar entre 1 y {}", current_year).into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+// Campos por los que se permite ordenar el listado de libros.
+pub const SORTABLE_FIELDS: [&str; 5] = ["titulo", "autor", "editorial", "anio", "numero_pagina"];
+pub const DEFAULT_PER_PAGE: u64 = 20; // Cantidad de libros por página si no se indica `per_page`.
+pub const MAX_PER_PAGE: u64 = 100; // Límite superior de `per_page` para evitar respuestas enormes.
+
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)] // Se deserializa desde la query string y se serializa para armar los enlaces.
+#[into_params(parameter_in = Query)]
+pub struct BookQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>, // Número de página (empieza en 1).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<u64>, // Cantidad de libros por página.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>, // Cursor: ID del último libro recibido (paginación por cursor).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autor: Option<String>, // Filtra por autor (sin distinguir mayúsculas).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editorial: Option<String>, // Filtra por editorial (sin distinguir mayúsculas).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autor_id: Option<String>, // Filtra por el ID del autor registrado.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editorial_id: Option<String>, // Filtra por el ID de la editorial registrada.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anio_min: Option<i32>, // Año de publicación mínimo (inclusive).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anio_max: Option<i32>, // Año de publicación máximo (inclusive).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paginas_min: Option<i32>, // Número de páginas mínimo (inclusive).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paginas_max: Option<i32>, // Número de páginas máximo (inclusive).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>, // Orden, p. ej. `titulo,-anio` (el `-` indica orden descendente).
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortField {
+    pub field: String, // Nombre del campo (uno de `SORTABLE_FIELDS`).
+    pub descending: bool, // `true` si el orden es descendente.
+}
+
+#[derive(Debug, Default)]
+pub struct BookFilter {
+    pub autor: Option<String>, // Autor exacto (sin distinguir mayúsculas).
+    pub editorial: Option<String>, // Editorial exacta (sin distinguir mayúsculas).
+    pub autor_id: Option<ObjectId>, // Autor registrado.
+    pub editorial_id: Option<ObjectId>, // Editorial registrada.
+    pub anio_min: Option<i32>, // Año mínimo (inclusive).
+    pub anio_max: Option<i32>, // Año máximo (inclusive).
+    pub paginas_min: Option<i32>, // Páginas mínimas (inclusive).
+    pub paginas_max: Option<i32>, // Páginas máximas (inclusive).
+}
+
+#[derive(Debug)]
+pub struct BookListOptions {
+    pub filter: BookFilter, // Filtros a aplicar.
+    pub sort: Vec<SortField>, // Orden a aplicar (vacío = orden de inserción).
+    pub after: Option<ObjectId>, // Cursor: devuelve libros con ID mayor a este.
+    pub skip: u64, // Cantidad de libros a omitir (paginación por página).
+    pub limit: u64, // Cantidad máxima de libros a devolver.
+}
+
+#[derive(Debug)]
+pub struct BookList {
+    pub books: Vec<Book>, // Libros de la página solicitada.
+    pub total: u64, // Total de libros que cumplen los filtros.
+    pub has_more: bool, // `true` si existen más libros después de esta página.
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BookPage {
+    pub data: Vec<Book>, // Libros de la página.
+    pub total: u64, // Total de libros que cumplen los filtros.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>, // Página actual (solo en paginación por página).
+    pub per_page: u64, // Cantidad de libros por página.
+    pub next: Option<String>, // Enlace a la página siguiente, si existe.
+}
+
+#[derive(Debug, Deserialize, IntoParams)] // Se deserializa desde la query string de la búsqueda.
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    pub q: String, // Texto a buscar en el título, el autor y la descripción.
+    pub limit: Option<u64>, // Cantidad máxima de resultados.
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub libro: Book, // Libro encontrado.
+    pub score: f64, // Relevancia del resultado (mayor es más relevante).
+    pub highlights: BTreeMap<String, String>, // Fragmentos con las coincidencias marcadas, por campo.
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchResults {
+    pub query: String, // Texto buscado.
+    pub total: usize, // Cantidad de resultados devueltos.
+    pub data: Vec<SearchHit>, // Resultados ordenados por relevancia.
+}
+
+impl SearchQuery {
+    // Valida el texto buscado y devuelve el límite de resultados a usar.
+    pub fn limit(&self) -> Result<u64, AppError> {
+        if self.q.trim().is_empty() {
+            return Err(AppError::InvalidQueryError("q no puede estar vacío".to_string()));
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_PER_PAGE);
+        if limit == 0 || limit > MAX_PER_PAGE {
+            return Err(AppError::InvalidQueryError(format!(
+                "limit debe estar entre 1 y {}",
+                MAX_PER_PAGE
+            )));
+        }
+        Ok(limit)
+    }
+}
+
+impl BookQuery {
+    // Valida los parámetros y los convierte en opciones para el repositorio.
+    pub fn to_options(&self) -> Result<BookListOptions, AppError> {
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(AppError::InvalidQueryError(format!(
+                "per_page debe estar entre 1 y {}",
+                MAX_PER_PAGE
+            )));
+        }
+
+        let page = self.page.unwrap_or(1);
+        if page == 0 {
+            return Err(AppError::InvalidQueryError("page debe ser mayor a 0".to_string()));
+        }
+        // MongoDB recibe la cantidad a omitir como entero con signo de 64 bits.
+        let skip = (page - 1)
+            .checked_mul(per_page)
+            .filter(|skip| i64::try_from(*skip).is_ok())
+            .ok_or_else(|| AppError::InvalidQueryError("page es demasiado grande".to_string()))?;
+
+        let sort = match &self.sort {
+            Some(sort) => parse_sort(sort)?,
+            None => Vec::new(),
+        };
+
+        // La paginación por cursor sigue el orden de los IDs, por eso no se combina con `page` ni `sort`.
+        let after = match &self.after {
+            Some(after) => {
+                if self.page.is_some() || !sort.is_empty() {
+                    return Err(AppError::InvalidQueryError(
+                        "after no se puede combinar con page ni sort".to_string(),
+                    ));
+                }
+                Some(
+                    ObjectId::parse_str(after)
+                        .map_err(|_| AppError::InvalidIDError("Cursor inválido".to_string()))?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(BookListOptions {
+            filter: BookFilter {
+                autor: self.autor.clone(),
+                editorial: self.editorial.clone(),
+                autor_id: parse_id(self.autor_id.as_deref(), "autor_id")?,
+                editorial_id: parse_id(self.editorial_id.as_deref(), "editorial_id")?,
+                anio_min: self.anio_min,
+                anio_max: self.anio_max,
+                paginas_min: self.paginas_min,
+                paginas_max: self.paginas_max,
+            },
+            sort,
+            after,
+            skip: if after.is_some() { 0 } else { skip },
+            limit: per_page,
+        })
+    }
+}
+
+// Convierte el ID opcional de un filtro en un ObjectId.
+fn parse_id(id: Option<&str>, name: &str) -> Result<Option<ObjectId>, AppError> {
+    id.map(|id| {
+        ObjectId::parse_str(id).map_err(|_| AppError::InvalidIDError(format!("{} inválido", name)))
+    })
+    .transpose()
+}
+
+// Convierte una cadena como `titulo,-anio` en la lista de campos de orden.
+fn parse_sort(sort: &str) -> Result<Vec<SortField>, AppError> {
+    sort.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (field, descending) = match part.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (part, false),
+            };
+            if !SORTABLE_FIELDS.contains(&field) {
+                return Err(AppError::InvalidQueryError(format!(
+                    "No se puede ordenar por '{}'",
+                    field
+                )));
+            }
+            Ok(SortField {
+                field: field.to_string(),
+                descending,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api::{author_api, book_api, cover_api, loan_api, publisher_api, transfer_api},
+    error::ErrorResponse,
+    model::CoverSize,
+    transfer::TransferFormat,
+};
+
+// Documento OpenAPI generado a partir de los endpoints y los modelos.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "API de libros", description = "Catálogo de libros, autores, editoriales, préstamos y reservas."),
+    servers((url = "/api")),
+    paths(
+        book_api::get_all_books,
+        book_api::search_books,
+        book_api::get_book,
+        book_api::get_book_by_isbn,
+        book_api::create_book,
+        book_api::update_book,
+        book_api::delete_book,
+        book_api::get_trash,
+        book_api::restore_book,
+        book_api::get_book_history,
+        book_api::revert_book,
+        transfer_api::import_books,
+        transfer_api::export_books,
+        cover_api::upload_cover,
+        cover_api::get_cover,
+        cover_api::delete_cover,
+        author_api::get_all_authors,
+        author_api::get_author,
+        author_api::get_author_books,
+        author_api::create_author,
+        author_api::update_author,
+        author_api::delete_author,
+        publisher_api::get_all_publishers,
+        publisher_api::get_publisher,
+        publisher_api::get_publisher_books,
+        publisher_api::create_publisher,
+        publisher_api::update_publisher,
+        publisher_api::delete_publisher,
+        loan_api::checkout_book,
+        loan_api::get_overdue_loans,
+        loan_api::get_loan,
+        loan_api::return_loan,
+        loan_api::renew_loan,
+        loan_api::get_member_loans,
+        loan_api::place_hold,
+        loan_api::cancel_hold,
+        loan_api::get_book_holds,
+    ),
+    components(schemas(ErrorResponse, CoverSize, TransferFormat)),
+    tags(
+        (name = "libros", description = "Catálogo, papelera e historial de cambios"),
+        (name = "transferencia", description = "Importación y exportación en CSV o NDJSON"),
+        (name = "portadas", description = "Imágenes de portada y miniaturas"),
+        (name = "autores", description = "Autores referenciados por los libros"),
+        (name = "editoriales", description = "Editoriales referenciadas por los libros"),
+        (name = "prestamos", description = "Préstamos, renovaciones y devoluciones"),
+        (name = "reservas", description = "Cola de reservas de los libros sin copias disponibles"),
+    )
+)]
+pub struct ApiDoc;
+
+// Forma JSON de un ObjectId de MongoDB (`{"$oid": "..."}`).
+#[derive(Serialize, ToSchema)]
+#[schema(as = ObjectId)]
+pub struct ObjectIdJson {
+    #[serde(rename = "$oid")]
+    pub oid: String, // 24 caracteres hexadecimales.
+}
+
+// Forma JSON de una fecha de MongoDB (`{"$date": {"$numberLong": "..."}}`).
+#[derive(Serialize, ToSchema)]
+#[schema(as = DateTime)]
+pub struct DateTimeJson {
+    #[serde(rename = "$date")]
+    pub date: NumberLongJson,
+}
+
+// Milisegundos desde la época Unix, como texto.
+#[derive(Serialize, ToSchema)]
+#[schema(as = NumberLong)]
+pub struct NumberLongJson {
+    #[serde(rename = "$numberLong")]
+    pub number_long: String,
+}
@@ -0,0 +1,41 @@
+use std::{sync::Arc, time::Duration};
+
+use mongodb::bson::DateTime;
+
+use crate::{
+    covers,
+    repository::{book_repository::BookRepository, cover_repository::CoverRepository},
+};
+
+// Tarea periódica que elimina definitivamente los libros que llevan más de `retention` en la papelera, con su
+// historial y las imágenes de su portada
+pub fn spawn_purge_job(
+    repo: Arc<dyn BookRepository>,
+    cover_repo: Arc<dyn CoverRepository>,
+    retention: Duration,
+    interval: Duration,
+) {
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(interval);
+        loop {
+            ticker.tick().await; // El primer tick es inmediato: purga al iniciar el servidor
+
+            // Una retención que no cabe en milisegundos conserva todos los libros
+            let retention_ms = i64::try_from(retention.as_millis()).unwrap_or(i64::MAX);
+            let cutoff = DateTime::from_millis(DateTime::now().timestamp_millis().saturating_sub(retention_ms));
+            match repo.purge_deleted_books(cutoff).await {
+                Ok(purged) if purged.is_empty() => log::debug!("Papelera: no hay libros para purgar"),
+                Ok(purged) => {
+                    // Las imágenes se eliminan después de los libros: si falla, solo quedan archivos huérfanos
+                    for book in &purged {
+                        if let (Some(id), Some(cover)) = (book.id, &book.portada) {
+                            covers::delete_images(cover_repo.as_ref(), id, cover).await;
+                        }
+                    }
+                    log::info!("Papelera: {} libros eliminados definitivamente", purged.len());
+                }
+                Err(err) => log::error!("Error al purgar la papelera: {}", err),
+            }
+        }
+    });
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    model::{
+        Book, BookHistoryEntry, BookList, BookListOptions, Cover, CreateBookDto, SearchHit, UpdateBookDto,
+    },
+};
+
+// Error de una creación en lote: la fila que lo causó (posición en el lote), si fue una fila en particular
+#[derive(Debug)]
+pub struct BatchError {
+    pub index: Option<usize>,
+    pub error: AppError,
+}
+
+// Contrato común para cualquier almacenamiento de libros (MongoDB, memoria, etc.)
+#[async_trait]
+pub trait BookRepository: Send + Sync {
+    // Obtiene una página de libros aplicando filtros, orden y paginación
+    async fn get_all_books(&self, options: &BookListOptions) -> Result<BookList, AppError>;
+
+    // Recorre todos los libros como un flujo, sin cargarlos en memoria (para la exportación)
+    async fn stream_books(&self) -> Result<BoxStream<'static, Result<Book, AppError>>, AppError>;
+
+    // Busca libros por texto y los devuelve ordenados por relevancia
+    async fn search_books(&self, query: &str, limit: u64) -> Result<Vec<SearchHit>, AppError>;
+
+    // Obtiene un libro por su ID
+    async fn get_book(&self, id: ObjectId) -> Result<Book, AppError>;
+
+    // Obtiene un libro por su ISBN-13 normalizado
+    async fn get_book_by_isbn(&self, isbn: &str) -> Result<Book, AppError>;
+
+    // Los métodos que modifican un libro registran el cambio en su historial a nombre de `user`,
+    // en la misma escritura: si no se puede guardar la entrada, tampoco se guarda el cambio
+
+    // Crea un nuevo libro a partir del DTO recibido
+    async fn create_book(&self, book_dto: CreateBookDto, user: &str) -> Result<Book, AppError>;
+
+    // Crea todos los libros del lote o ninguno (importación todo o nada); los demás lectores no ven un lote a medias
+    async fn create_books(&self, book_dtos: Vec<CreateBookDto>, user: &str) -> Result<Vec<Book>, BatchError>;
+
+    // Actualiza los campos presentes en el DTO del libro con el ID indicado
+    // (con `expected_version`, solo si el libro sigue en esa versión; si no, `PreconditionFailed`)
+    async fn update_book(
+        &self,
+        id: ObjectId,
+        book_dto: UpdateBookDto,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError>;
+
+    // Envía a la papelera el libro con el ID indicado (eliminación lógica) y lo devuelve
+    async fn delete_book(&self, id: ObjectId, expected_version: Option<i64>, user: &str) -> Result<Book, AppError>;
+
+    // Obtiene los libros que están en la papelera
+    async fn get_deleted_books(&self) -> Result<Vec<Book>, AppError>;
+
+    // Saca de la papelera el libro con el ID indicado
+    async fn restore_book(&self, id: ObjectId, user: &str) -> Result<Book, AppError>;
+
+    // Elimina definitivamente los libros enviados a la papelera antes de `before`, junto con su historial; devuelve
+    // los libros eliminados, para borrar también sus portadas
+    async fn purge_deleted_books(&self, before: DateTime) -> Result<Vec<Book>, AppError>;
+
+    // Reemplaza los campos editables del libro por los de `version` (para revertir cambios)
+    async fn replace_book(
+        &self,
+        id: ObjectId,
+        version: &Book,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError>;
+
+    // Guarda (o quita, con `None`) los datos de la portada de un libro activo; devuelve el libro actualizado y la
+    // portada que reemplazó, para eliminar sus imágenes
+    async fn set_cover(
+        &self,
+        id: ObjectId,
+        cover: Option<Cover>,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<(Book, Option<Cover>), AppError>;
+
+    // Obtiene el historial de cambios de un libro, del más antiguo al más reciente
+    async fn get_history(&self, book_id: ObjectId) -> Result<Vec<BookHistoryEntry>, AppError>;
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::error::AppError;
+
+// Contrato común para cualquier almacenamiento de imágenes de portada (GridFS, sistema de archivos, etc.)
+#[async_trait]
+pub trait CoverRepository: Send + Sync {
+    // Guarda la imagen con la clave indicada, reemplazando la anterior si existe
+    async fn save_image(&self, key: &str, bytes: Vec<u8>) -> Result<(), AppError>;
+
+    // Obtiene la imagen guardada con la clave indicada
+    async fn load_image(&self, key: &str) -> Result<Vec<u8>, AppError>;
+
+    // Elimina la imagen con la clave indicada (no falla si no existe)
+    async fn delete_image(&self, key: &str) -> Result<(), AppError>;
+}
@@ -0,0 +1,77 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use async_trait::async_trait;
+
+use crate::{error::AppError, health::HealthCheck, repository::cover_repository::CoverRepository};
+
+// Almacén de portadas en un directorio local (un archivo por clave)
+pub struct FsCoverRepo {
+    dir: PathBuf, // Directorio donde se guardan las imágenes
+}
+
+impl FsCoverRepo {
+    // Constructor que crea el directorio si todavía no existe
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(FsCoverRepo { dir })
+    }
+
+    // Ruta del archivo de una clave (las claves se arman con el ID del libro, sin separadores)
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+}
+
+#[async_trait]
+impl CoverRepository for FsCoverRepo {
+    // Método para guardar una imagen: se escribe en un archivo temporal y se renombra
+    async fn save_image(&self, key: &str, bytes: Vec<u8>) -> Result<(), AppError> {
+        let path = self.path(key);
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, bytes).await.map_err(storage_error)?;
+        tokio::fs::rename(&temp_path, &path).await.map_err(storage_error) // El reemplazo es atómico
+    }
+
+    // Método para leer una imagen
+    async fn load_image(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(bytes) => Ok(bytes),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(AppError::NotFoundError),
+            Err(err) => Err(storage_error(err)),
+        }
+    }
+
+    // Método para eliminar una imagen
+    async fn delete_image(&self, key: &str) -> Result<(), AppError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(storage_error(err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Registra el error de E/S y lo convierte en un error interno
+fn storage_error(err: std::io::Error) -> AppError {
+    log::error!("Error en el almacén de portadas: {}", err);
+    AppError::InternalError
+}
+
+#[async_trait]
+impl HealthCheck for FsCoverRepo {
+    fn name(&self) -> &'static str {
+        "portadas"
+    }
+
+    // El directorio debe existir y admitir escritura
+    async fn check(&self) -> Result<(), String> {
+        let metadata = tokio::fs::metadata(&self.dir).await.map_err(|err| err.to_string())?;
+        if !metadata.is_dir() {
+            return Err(format!("{} no es un directorio", self.dir.display()));
+        }
+        if metadata.permissions().readonly() {
+            return Err(format!("{} es de solo lectura", self.dir.display()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    model::{Hold, Loan},
+};
+
+// Contrato común para los préstamos y reservas; las reglas de préstamo se aplican aquí
+#[async_trait]
+pub trait LoanRepository: Send + Sync {
+    // Presta una copia del libro al socio (usa la copia apartada si el socio tenía una reserva lista)
+    async fn checkout(&self, book_id: ObjectId, member_id: &str) -> Result<Loan, AppError>;
+
+    // Registra la devolución; la copia queda apartada para la primera reserva en espera, si la hay
+    async fn return_loan(&self, id: ObjectId) -> Result<Loan, AppError>;
+
+    // Extiende el vencimiento de un préstamo activo que no está vencido ni tiene reservas en espera
+    async fn renew_loan(&self, id: ObjectId) -> Result<Loan, AppError>;
+
+    // Obtiene un préstamo por su ID
+    async fn get_loan(&self, id: ObjectId) -> Result<Loan, AppError>;
+
+    // Obtiene los préstamos activos de un socio, los que vencen antes primero
+    async fn get_member_loans(&self, member_id: &str) -> Result<Vec<Loan>, AppError>;
+
+    // Obtiene los préstamos activos cuyo vencimiento es anterior a `now`
+    async fn get_overdue_loans(&self, now: DateTime) -> Result<Vec<Loan>, AppError>;
+
+    // Pone al socio en la cola de reservas de un libro sin copias disponibles
+    async fn place_hold(&self, book_id: ObjectId, member_id: &str) -> Result<Hold, AppError>;
+
+    // Cancela una reserva; si tenía una copia apartada, pasa a la siguiente de la cola
+    async fn cancel_hold(&self, id: ObjectId) -> Result<Hold, AppError>;
+
+    // Obtiene la cola de reservas pendientes de un libro, en orden de llegada
+    async fn get_book_holds(&self, book_id: ObjectId) -> Result<Vec<Hold>, AppError>;
+
+    // Vence las reservas cuya copia apartada no se retiró a tiempo (según `now`); cada copia pasa a la siguiente
+    // reserva de la cola o vuelve a estar disponible. Devuelve cuántas reservas vencieron
+    async fn expire_holds(&self, now: DateTime) -> Result<u64, AppError>;
+}
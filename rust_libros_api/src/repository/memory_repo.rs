@@ -0,0 +1,845 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeMap,
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::AppError,
+    history, isbn, loans,
+    model::{
+        self, Author, Book, BookFilter, BookHistoryEntry, BookList, BookListOptions, BookReference, Cover,
+        CreateBookDto, HistoryAction, Hold, HoldStatus, Loan, LoanPolicy, LoanStatus, Publisher, SearchHit, SortField,
+        UpdateBookDto,
+    },
+    repository::{
+        book_repository::{BatchError, BookRepository}, check_version, loan_repository::LoanRepository, missing_reference,
+        reference_repository::ReferenceRepository, ReferenceRequest,
+    },
+    search,
+};
+
+// Repositorio en memoria, útil para demos locales y pruebas sin MongoDB
+// Los bloqueos se toman siempre en el orden autores, editoriales, libros, historial, préstamos, reservas para evitar
+// interbloqueos
+#[derive(Default)]
+pub struct MemoryRepo {
+    authors: RwLock<BTreeMap<ObjectId, Author>>,
+    publishers: RwLock<BTreeMap<ObjectId, Publisher>>,
+    // Los ObjectId crecen con el tiempo, así que el BTreeMap conserva el orden de inserción
+    books: RwLock<BTreeMap<ObjectId, Book>>,
+    history: RwLock<Vec<BookHistoryEntry>>, // Entradas en el orden en que se registraron
+    loans: RwLock<BTreeMap<ObjectId, Loan>>,
+    holds: RwLock<BTreeMap<ObjectId, Hold>>, // El orden de los ObjectId es el orden de la cola
+    loan_policy: LoanPolicy, // Reglas de préstamo (duración, límite por socio, renovaciones)
+}
+
+impl MemoryRepo {
+    // Constructor para inicializar un repositorio vacío
+    pub fn new() -> Self {
+        MemoryRepo::default()
+    }
+
+    // Reemplaza las reglas de préstamo por defecto
+    pub fn with_loan_policy(mut self, loan_policy: LoanPolicy) -> Self {
+        self.loan_policy = loan_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl BookRepository for MemoryRepo {
+    // Método para obtener una página de libros aplicando filtros, orden y paginación
+    async fn get_all_books(&self, options: &BookListOptions) -> Result<BookList, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+
+        let mut matching: Vec<&Book> = active(&books)
+            .filter(|book| matches_filter(book, &options.filter))
+            .collect();
+        let total = matching.len() as u64; // Total sin paginar
+
+        // El orden estable conserva el orden de inserción como desempate (igual que `_id` en MongoDB)
+        matching.sort_by(|a, b| compare_books(a, b, &options.sort));
+
+        let page: Vec<Book> = matching
+            .into_iter()
+            .filter(|book| match (options.after, book.id) {
+                (Some(after), Some(id)) => id > after,
+                _ => true,
+            })
+            .skip(options.skip as usize)
+            .take(options.limit as usize + 1) // Un libro extra para saber si hay página siguiente
+            .cloned()
+            .collect();
+
+        let has_more = page.len() as u64 > options.limit;
+        let mut books = page;
+        books.truncate(options.limit as usize);
+
+        Ok(BookList { books, total, has_more })
+    }
+
+    // Método para recorrer todos los libros (copia los libros actuales)
+    async fn stream_books(&self) -> Result<BoxStream<'static, Result<Book, AppError>>, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        let snapshot: Vec<Result<Book, AppError>> = active(&books).cloned().map(Ok).collect();
+        Ok(stream::iter(snapshot).boxed())
+    }
+
+    // Método para buscar libros por texto, ordenados por relevancia
+    async fn search_books(&self, query: &str, limit: u64) -> Result<Vec<SearchHit>, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        Ok(search::rank(active(&books).cloned(), &search::terms(query), limit))
+    }
+
+    // Método para crear un nuevo libro en memoria
+    async fn create_book(&self, book_dto: CreateBookDto, user: &str) -> Result<Book, AppError> {
+        // Las referencias se resuelven con los bloqueos tomados para que no se eliminen ni se dupliquen mientras tanto
+        let mut authors = self.authors.write().map_err(|_| AppError::InternalError)?;
+        let mut publishers = self.publishers.write().map_err(|_| AppError::InternalError)?;
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        insert_new_book(&mut authors, &mut publishers, &mut books, &mut history, book_dto, user)
+    }
+
+    // Método para crear un lote de libros: se crean sobre copias de los datos, que reemplazan a los originales
+    // solo si todos los libros se crearon (con los bloqueos tomados, nadie ve el lote a medias)
+    async fn create_books(&self, book_dtos: Vec<CreateBookDto>, user: &str) -> Result<Vec<Book>, BatchError> {
+        let lock_error = || BatchError { index: None, error: AppError::InternalError };
+        let mut authors = self.authors.write().map_err(|_| lock_error())?;
+        let mut publishers = self.publishers.write().map_err(|_| lock_error())?;
+        let mut books = self.books.write().map_err(|_| lock_error())?;
+        let mut history = self.history.write().map_err(|_| lock_error())?;
+
+        let (mut new_authors, mut new_publishers) = (authors.clone(), publishers.clone());
+        let (mut new_books, mut new_history) = (books.clone(), history.clone());
+        let mut created = Vec::with_capacity(book_dtos.len());
+        for (index, book_dto) in book_dtos.into_iter().enumerate() {
+            let book = insert_new_book(&mut new_authors, &mut new_publishers, &mut new_books, &mut new_history, book_dto, user)
+                .map_err(|error| BatchError { index: Some(index), error })?;
+            created.push(book);
+        }
+
+        (*authors, *publishers, *books, *history) = (new_authors, new_publishers, new_books, new_history);
+        Ok(created) // Devuelve los libros creados, en el orden del lote
+    }
+
+    // Método para obtener un libro por su ID
+    async fn get_book(&self, id: ObjectId) -> Result<Book, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        books
+            .get(&id)
+            .filter(|book| book.deleted_at.is_none()) // Los libros en la papelera no se encuentran
+            .cloned()
+            .ok_or(AppError::NotFoundError)
+    }
+
+    // Método para obtener un libro por su ISBN-13 normalizado
+    async fn get_book_by_isbn(&self, isbn: &str) -> Result<Book, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        books
+            .values()
+            .find(|book| book.deleted_at.is_none() && book.isbn.as_deref() == Some(isbn))
+            .cloned()
+            .ok_or(AppError::NotFoundError)
+    }
+
+    // Método para actualizar un libro por su ID
+    async fn update_book(
+        &self,
+        id: ObjectId,
+        book_dto: UpdateBookDto,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError> {
+        let author_request = ReferenceRequest::from_dto::<Author>(book_dto.autor_id.as_deref(), book_dto.autor.as_deref())?;
+        let publisher_request =
+            ReferenceRequest::from_dto::<Publisher>(book_dto.editorial_id.as_deref(), book_dto.editorial.as_deref())?;
+        let mut authors = self.authors.write().map_err(|_| AppError::InternalError)?;
+        let mut publishers = self.publishers.write().map_err(|_| AppError::InternalError)?;
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        // Las referencias y el ISBN se validan antes de modificar el libro
+        let author = resolve(&authors, &author_request)?;
+        let publisher = resolve(&publishers, &publisher_request)?;
+        let isbn = book_dto.isbn.as_deref().map(isbn::parse).transpose()?;
+        if books.get(&id).is_none_or(|book| book.deleted_at.is_some()) {
+            return Err(AppError::NotFoundError); // No existe o está en la papelera
+        }
+        ensure_unique_isbn(&books, isbn.as_deref(), id)?;
+
+        let book = books.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        check_version(book, expected_version)?; // Se compara con el bloqueo tomado
+        if let Some(copias) = book_dto.copias {
+            loans::check_copies_cover_loans(book, copias)?;
+        }
+        let before = book.clone();
+
+        // Solo se modifican los campos presentes en el DTO
+        if let Some(titulo) = book_dto.titulo {
+            book.titulo = titulo;
+        }
+
+        // El ID del registro tiene prioridad sobre el nombre
+        link(&mut authors, author.as_ref(), book);
+        link(&mut publishers, publisher.as_ref(), book);
+
+        if let Some(anio) = book_dto.anio {
+            book.anio = anio;
+        }
+
+        if let Some(descripcion) = book_dto.descripcion {
+            book.descripcion = descripcion;
+        }
+
+        if let Some(numero_pagina) = book_dto.numero_pagina {
+            book.numero_pagina = numero_pagina;
+        }
+
+        if let Some(isbn) = isbn {
+            book.isbn10 = isbn::to_isbn10(&isbn);
+            book.isbn = Some(isbn);
+        }
+
+        if let Some(copias) = book_dto.copias {
+            book.copias = copias;
+            // Las copias nuevas se apartan para las reservas en espera
+            let now = DateTime::now();
+            while book.disponibles() > 0 && promote_next_hold(&mut holds, id, now) {
+                book.apartados += 1;
+            }
+        }
+        book.version += 1;
+        push_history(&mut history, history::entry(id, HistoryAction::Update, user, Some(&before), book));
+
+        Ok(book.clone()) // Devuelve el libro actualizado
+    }
+
+    // Método para enviar un libro a la papelera (eliminación lógica)
+    async fn delete_book(&self, id: ObjectId, expected_version: Option<i64>, user: &str) -> Result<Book, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+        match books.get_mut(&id) {
+            Some(book) if book.deleted_at.is_none() => {
+                check_version(book, expected_version)?;
+                loans::check_no_copies_out(book)?;
+                let before = book.clone();
+                book.deleted_at = Some(DateTime::now()); // Marca la fecha de eliminación
+                book.version += 1;
+                push_history(&mut history, history::entry(id, HistoryAction::Delete, user, Some(&before), book));
+
+                // Las reservas pendientes se cancelan: un libro en la papelera no se puede prestar
+                for hold in holds.values_mut() {
+                    if hold.libro_id == id && loans::is_pending(hold.estado) {
+                        hold.estado = HoldStatus::Cancelled;
+                    }
+                }
+                Ok(book.clone()) // Devuelve el libro tal como quedó en la papelera
+            }
+            _ => Err(AppError::NotFoundError),
+        }
+    }
+
+    // Método para obtener los libros de la papelera, los eliminados más recientemente primero
+    async fn get_deleted_books(&self) -> Result<Vec<Book>, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        let mut deleted: Vec<Book> = books
+            .values()
+            .filter(|book| book.deleted_at.is_some())
+            .cloned()
+            .collect();
+        deleted.sort_by_key(|book| Reverse(book.deleted_at));
+        Ok(deleted)
+    }
+
+    // Método para sacar un libro de la papelera
+    async fn restore_book(&self, id: ObjectId, user: &str) -> Result<Book, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        match books.get_mut(&id) {
+            Some(book) if book.deleted_at.is_some() => {}
+            _ => return Err(AppError::NotFoundError),
+        }
+        // Mientras estaba en la papelera otro libro pudo registrar el mismo ISBN
+        let isbn = books.get(&id).and_then(|book| book.isbn.clone());
+        ensure_unique_isbn(&books, isbn.as_deref(), id)?;
+
+        let book = books.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        let before = book.clone();
+        book.deleted_at = None;
+        book.version += 1;
+        push_history(&mut history, history::entry(id, HistoryAction::Restore, user, Some(&before), book));
+        Ok(book.clone()) // Devuelve el libro restaurado
+    }
+
+    // Método para eliminar definitivamente los libros enviados a la papelera antes de `before`
+    async fn purge_deleted_books(&self, before: DateTime) -> Result<Vec<Book>, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        let expired: Vec<ObjectId> = books
+            .values()
+            .filter(|book| book.deleted_at.is_some_and(|deleted_at| deleted_at < before))
+            .filter_map(|book| book.id)
+            .collect();
+        let purged: Vec<Book> = expired.iter().filter_map(|id| books.remove(id)).collect();
+        history.retain(|entry| !expired.contains(&entry.book_id));
+        Ok(purged) // Devuelve los libros eliminados
+    }
+
+    // Método para reemplazar los campos editables de un libro por los de otra versión
+    async fn replace_book(
+        &self,
+        id: ObjectId,
+        version: &Book,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError> {
+        let mut authors = self.authors.write().map_err(|_| AppError::InternalError)?;
+        let mut publishers = self.publishers.write().map_err(|_| AppError::InternalError)?;
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+
+        // Las referencias se vuelven a resolver: el autor o la editorial pudieron cambiar de nombre o eliminarse
+        let author = resolve(&authors, &ReferenceRequest::of::<Author>(version))?;
+        let publisher = resolve(&publishers, &ReferenceRequest::of::<Publisher>(version))?;
+
+        match books.get(&id) {
+            Some(book) if book.deleted_at.is_none() => check_version(book, expected_version)?,
+            _ => return Err(AppError::NotFoundError), // No existe o está en la papelera
+        }
+        ensure_unique_isbn(&books, version.isbn.as_deref(), id)?;
+
+        let book = books.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        let before = book.clone();
+        book.titulo = version.titulo.clone();
+        book.autor = version.autor.clone();
+        book.editorial = version.editorial.clone();
+        book.anio = version.anio;
+        book.descripcion = version.descripcion.clone();
+        book.numero_pagina = version.numero_pagina;
+        book.isbn10 = version.isbn.as_deref().and_then(isbn::to_isbn10);
+        book.isbn = version.isbn.clone();
+        book.autor_ref = None;
+        book.editorial_ref = None;
+        link(&mut authors, author.as_ref(), book);
+        link(&mut publishers, publisher.as_ref(), book);
+        book.version += 1;
+        push_history(&mut history, history::entry(id, HistoryAction::Revert, user, Some(&before), book));
+
+        Ok(book.clone()) // Devuelve el libro con los campos de la versión indicada
+    }
+
+    // Método para guardar o quitar los datos de la portada de un libro
+    async fn set_cover(
+        &self,
+        id: ObjectId,
+        cover: Option<Cover>,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<(Book, Option<Cover>), AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+        match books.get_mut(&id) {
+            Some(book) if book.deleted_at.is_none() => {
+                check_version(book, expected_version)?;
+                let before = book.clone();
+                book.portada = cover;
+                book.version += 1;
+                push_history(&mut history, history::entry(id, HistoryAction::Update, user, Some(&before), book));
+                Ok((book.clone(), before.portada)) // Devuelve el libro actualizado y la portada anterior
+            }
+            _ => Err(AppError::NotFoundError),
+        }
+    }
+
+    // Método para obtener el historial de un libro, del cambio más antiguo al más reciente
+    async fn get_history(&self, book_id: ObjectId) -> Result<Vec<BookHistoryEntry>, AppError> {
+        let history = self.history.read().map_err(|_| AppError::InternalError)?;
+        Ok(history
+            .iter()
+            .filter(|entry| entry.book_id == book_id)
+            .cloned()
+            .collect())
+    }
+}
+
+// Colección en memoria de cada tipo de registro que referencian los libros
+pub trait MemoryReference: BookReference {
+    fn entries(repo: &MemoryRepo) -> &RwLock<BTreeMap<ObjectId, Self>>;
+}
+
+impl MemoryReference for Author {
+    fn entries(repo: &MemoryRepo) -> &RwLock<BTreeMap<ObjectId, Self>> {
+        &repo.authors
+    }
+}
+
+impl MemoryReference for Publisher {
+    fn entries(repo: &MemoryRepo) -> &RwLock<BTreeMap<ObjectId, Self>> {
+        &repo.publishers
+    }
+}
+
+#[async_trait]
+impl<E: MemoryReference> ReferenceRepository<E> for MemoryRepo {
+    // Método para obtener todos los registros ordenados por nombre
+    async fn get_all(&self) -> Result<Vec<E>, AppError> {
+        let entries = E::entries(self).read().map_err(|_| AppError::InternalError)?;
+        let mut list: Vec<E> = entries.values().cloned().collect();
+        list.sort_by(|a, b| a.nombre().cmp(b.nombre())); // El orden estable desempata por ID
+        Ok(list)
+    }
+
+    // Método para obtener un registro por su ID
+    async fn get(&self, id: ObjectId) -> Result<E, AppError> {
+        let entries = E::entries(self).read().map_err(|_| AppError::InternalError)?;
+        entries.get(&id).cloned().ok_or(AppError::NotFoundError)
+    }
+
+    // Método para crear un nuevo registro en memoria
+    async fn create(&self, dto: E::CreateDto) -> Result<E, AppError> {
+        let id = ObjectId::new(); // Genera el ID igual que lo haría MongoDB
+        let entry = E::from_dto(Some(id), dto);
+
+        let mut entries = E::entries(self).write().map_err(|_| AppError::InternalError)?;
+        ensure_unique_name(&entries, entry.nombre(), id)?;
+        entries.insert(id, entry.clone());
+        Ok(entry) // Devuelve el registro recién creado
+    }
+
+    // Método para actualizar un registro y copiar su nuevo nombre en los libros que lo referencian
+    async fn update(&self, id: ObjectId, dto: E::UpdateDto, user: &str) -> Result<E, AppError> {
+        let mut entries = E::entries(self).write().map_err(|_| AppError::InternalError)?;
+        let current = entries.get(&id).ok_or(AppError::NotFoundError)?;
+        let mut entry = current.clone();
+        entry.apply(dto);
+
+        if entry.nombre() != current.nombre() {
+            ensure_unique_name(&entries, entry.nombre(), id)?;
+            let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+            let mut history = self.history.write().map_err(|_| AppError::InternalError)?;
+            for (book_id, book) in books.iter_mut().filter(|(_, book)| E::referenced_id(book) == Some(id)) {
+                let before = book.clone();
+                entry.link(book);
+                book.version += 1;
+                push_history(&mut history, history::entry(*book_id, HistoryAction::Update, user, Some(&before), book));
+            }
+        }
+
+        entries.insert(id, entry.clone());
+        Ok(entry) // Devuelve el registro actualizado
+    }
+
+    // Método para eliminar un registro que ningún libro referencia
+    async fn delete(&self, id: ObjectId) -> Result<(), AppError> {
+        let mut entries = E::entries(self).write().map_err(|_| AppError::InternalError)?;
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+
+        // Se cuentan también los libros de la papelera, porque se pueden restaurar
+        let references = books.values().filter(|book| E::referenced_id(book) == Some(id)).count();
+        if references > 0 {
+            return Err(AppError::Conflict(E::in_use(references as u64)));
+        }
+
+        entries.remove(&id).map(|_| ()).ok_or(AppError::NotFoundError)
+    }
+}
+
+// Libros que no están en la papelera
+fn active(books: &BTreeMap<ObjectId, Book>) -> impl Iterator<Item = &Book> {
+    books.values().filter(|book| book.deleted_at.is_none())
+}
+
+#[async_trait]
+impl LoanRepository for MemoryRepo {
+    // Método para prestar una copia de un libro a un socio
+    async fn checkout(&self, book_id: ObjectId, member_id: &str) -> Result<Loan, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut loans = self.loans.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        let book = books
+            .get_mut(&book_id)
+            .filter(|book| book.deleted_at.is_none())
+            .ok_or(AppError::NotFoundError)?;
+
+        // Límite de préstamos por socio y un solo préstamo activo del mismo libro
+        let member_loans: Vec<&Loan> = loans
+            .values()
+            .filter(|loan| loan.estado == LoanStatus::Active && loan.socio_id == member_id)
+            .collect();
+        loans::check_member_can_borrow(&member_loans, book_id, self.loan_policy.max_loans)?;
+
+        // Si el socio tiene una copia apartada la retira; si no, necesita una copia disponible
+        let ready_hold = holds.values_mut().find(|hold| {
+            hold.libro_id == book_id && hold.socio_id == member_id && hold.estado == HoldStatus::Ready
+        });
+        match ready_hold {
+            Some(hold) => {
+                hold.estado = HoldStatus::Fulfilled;
+                book.apartados -= 1;
+            }
+            None if book.disponibles() > 0 => {}
+            None => return Err(loans::no_copies_available()),
+        }
+        book.prestados += 1;
+        book.version += 1; // Las copias prestadas también forman parte de la versión del libro
+
+        let now = DateTime::now();
+        let loan = Loan {
+            id: Some(ObjectId::new()), // Genera el ID igual que lo haría MongoDB
+            libro_id: book_id,
+            socio_id: member_id.to_string(),
+            estado: LoanStatus::Active,
+            prestado_en: now,
+            vence_en: self.loan_policy.due_date(now),
+            devuelto_en: None,
+            renovaciones: 0,
+        };
+        loans.insert(loan.id.unwrap_or_default(), loan.clone());
+        Ok(loan) // Devuelve el préstamo creado
+    }
+
+    // Método para registrar la devolución de un préstamo
+    async fn return_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut loans = self.loans.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        let loan = loans.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        if loan.estado != LoanStatus::Active {
+            return Err(AppError::Conflict("El préstamo ya fue devuelto".to_string()));
+        }
+        loan.estado = LoanStatus::Returned;
+        loan.devuelto_en = Some(DateTime::now());
+
+        // La copia devuelta se aparta para la primera reserva en espera
+        if let Some(book) = books.get_mut(&loan.libro_id) {
+            book.prestados -= 1;
+            book.version += 1;
+            if promote_next_hold(&mut holds, loan.libro_id, loan.devuelto_en.unwrap_or_else(DateTime::now)) {
+                book.apartados += 1;
+            }
+        }
+
+        Ok(loan.clone()) // Devuelve el préstamo devuelto
+    }
+
+    // Método para renovar un préstamo activo
+    async fn renew_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let mut loans = self.loans.write().map_err(|_| AppError::InternalError)?;
+        let holds = self.holds.read().map_err(|_| AppError::InternalError)?;
+
+        let loan = loans.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        let waiting = holds
+            .values()
+            .any(|hold| hold.libro_id == loan.libro_id && hold.estado == HoldStatus::Waiting);
+        loans::check_renewable(loan, waiting, self.loan_policy.max_renewals, DateTime::now())?;
+
+        loan.vence_en = self.loan_policy.due_date(loan.vence_en);
+        loan.renovaciones += 1;
+        Ok(loan.clone()) // Devuelve el préstamo renovado
+    }
+
+    // Método para obtener un préstamo por su ID
+    async fn get_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let loans = self.loans.read().map_err(|_| AppError::InternalError)?;
+        loans.get(&id).cloned().ok_or(AppError::NotFoundError)
+    }
+
+    // Método para obtener los préstamos activos de un socio
+    async fn get_member_loans(&self, member_id: &str) -> Result<Vec<Loan>, AppError> {
+        let loans = self.loans.read().map_err(|_| AppError::InternalError)?;
+        let mut member_loans: Vec<Loan> = loans
+            .values()
+            .filter(|loan| loan.estado == LoanStatus::Active && loan.socio_id == member_id)
+            .cloned()
+            .collect();
+        member_loans.sort_by_key(|loan| loan.vence_en);
+        Ok(member_loans)
+    }
+
+    // Método para obtener los préstamos vencidos
+    async fn get_overdue_loans(&self, now: DateTime) -> Result<Vec<Loan>, AppError> {
+        let loans = self.loans.read().map_err(|_| AppError::InternalError)?;
+        let mut overdue: Vec<Loan> = loans
+            .values()
+            .filter(|loan| loan.estado == LoanStatus::Active && loan.vence_en < now)
+            .cloned()
+            .collect();
+        overdue.sort_by_key(|loan| loan.vence_en);
+        Ok(overdue)
+    }
+
+    // Método para poner a un socio en la cola de reservas de un libro
+    async fn place_hold(&self, book_id: ObjectId, member_id: &str) -> Result<Hold, AppError> {
+        let books = self.books.read().map_err(|_| AppError::InternalError)?;
+        let loans = self.loans.read().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        let book = books
+            .get(&book_id)
+            .filter(|book| book.deleted_at.is_none())
+            .ok_or(AppError::NotFoundError)?;
+        let has_loan = loans.values().any(|loan| {
+            loan.libro_id == book_id && loan.socio_id == member_id && loan.estado == LoanStatus::Active
+        });
+        let has_hold = holds.values().any(|hold| {
+            hold.libro_id == book_id && hold.socio_id == member_id && loans::is_pending(hold.estado)
+        });
+        loans::check_can_hold(book, has_loan, has_hold)?;
+
+        let hold = Hold {
+            id: Some(ObjectId::new()), // Genera el ID igual que lo haría MongoDB
+            libro_id: book_id,
+            socio_id: member_id.to_string(),
+            estado: HoldStatus::Waiting,
+            creado_en: DateTime::now(),
+            listo_en: None,
+        };
+        holds.insert(hold.id.unwrap_or_default(), hold.clone());
+        Ok(hold) // Devuelve la reserva creada
+    }
+
+    // Método para cancelar una reserva
+    async fn cancel_hold(&self, id: ObjectId) -> Result<Hold, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        let hold = holds.get_mut(&id).ok_or(AppError::NotFoundError)?;
+        if !loans::is_pending(hold.estado) {
+            return Err(AppError::Conflict("La reserva ya no está pendiente".to_string()));
+        }
+        let was_ready = hold.estado == HoldStatus::Ready;
+        hold.estado = HoldStatus::Cancelled;
+        let cancelled = hold.clone();
+
+        // La copia apartada pasa a la siguiente reserva de la cola o vuelve a estar disponible
+        if was_ready {
+            release_ready_copy(&mut books, &mut holds, cancelled.libro_id, DateTime::now());
+        }
+
+        Ok(cancelled) // Devuelve la reserva cancelada
+    }
+
+    // Método para obtener la cola de reservas pendientes de un libro
+    async fn get_book_holds(&self, book_id: ObjectId) -> Result<Vec<Hold>, AppError> {
+        let holds = self.holds.read().map_err(|_| AppError::InternalError)?;
+        Ok(holds
+            .values()
+            .filter(|hold| hold.libro_id == book_id && loans::is_pending(hold.estado))
+            .cloned()
+            .collect())
+    }
+
+    // Método para vencer las reservas listas que el socio no retiró a tiempo
+    async fn expire_holds(&self, now: DateTime) -> Result<u64, AppError> {
+        let mut books = self.books.write().map_err(|_| AppError::InternalError)?;
+        let mut holds = self.holds.write().map_err(|_| AppError::InternalError)?;
+
+        let cutoff = self.loan_policy.hold_cutoff(now);
+        let expired: Vec<ObjectId> = holds
+            .iter()
+            .filter(|(_, hold)| {
+                hold.estado == HoldStatus::Ready && hold.listo_en.is_some_and(|listo_en| listo_en < cutoff)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &expired {
+            let Some(hold) = holds.get_mut(id) else { continue };
+            hold.estado = HoldStatus::Expired;
+            let book_id = hold.libro_id;
+            release_ready_copy(&mut books, &mut holds, book_id, now);
+        }
+        Ok(expired.len() as u64) // Devuelve cuántas reservas vencieron
+    }
+}
+
+// Aparta una copia del libro para la primera reserva en espera; indica si había alguna
+fn promote_next_hold(holds: &mut BTreeMap<ObjectId, Hold>, book_id: ObjectId, now: DateTime) -> bool {
+    let next_hold = holds
+        .values_mut()
+        .find(|hold| hold.libro_id == book_id && hold.estado == HoldStatus::Waiting);
+    match next_hold {
+        Some(hold) => {
+            hold.estado = HoldStatus::Ready;
+            hold.listo_en = Some(now);
+            true
+        }
+        None => false,
+    }
+}
+
+// La copia de una reserva lista que se canceló o venció pasa a la siguiente reserva o vuelve a estar disponible
+fn release_ready_copy(
+    books: &mut BTreeMap<ObjectId, Book>,
+    holds: &mut BTreeMap<ObjectId, Hold>,
+    book_id: ObjectId,
+    now: DateTime,
+) {
+    if promote_next_hold(holds, book_id, now) {
+        return;
+    }
+    if let Some(book) = books.get_mut(&book_id) {
+        book.apartados -= 1;
+        book.version += 1;
+    }
+}
+
+// Registro pedido para un libro, por ID o por nombre (sin distinguir mayúsculas ni tildes). Si el nombre no está
+// registrado devuelve un registro nuevo, que `link` guarda cuando se confirma el libro
+fn resolve<E: BookReference>(entries: &BTreeMap<ObjectId, E>, request: &ReferenceRequest) -> Result<Option<E>, AppError> {
+    if let Some(id) = request.id {
+        return match entries.get(&id) {
+            Some(entry) => Ok(Some(entry.clone())),
+            None => Err(missing_reference(E::ID_FIELD, E::NOT_FOUND)),
+        };
+    }
+    Ok(request.nombre.as_deref().map(|nombre| match find_by_name(entries, nombre) {
+        Some(entry) => entry.clone(),
+        None => E::named(Some(ObjectId::new()), nombre.to_string()),
+    }))
+}
+
+// Asigna al libro el registro resuelto y guarda el registro si es nuevo
+fn link<E: BookReference>(entries: &mut BTreeMap<ObjectId, E>, entry: Option<&E>, book: &mut Book) {
+    if let Some(entry) = entry {
+        if let Some(id) = entry.id() {
+            entries.entry(id).or_insert_with(|| entry.clone());
+        }
+        entry.link(book);
+    }
+}
+
+// Registro con el nombre indicado, sin distinguir mayúsculas ni tildes
+fn find_by_name<'a, E: BookReference>(entries: &'a BTreeMap<ObjectId, E>, nombre: &str) -> Option<&'a E> {
+    let key = model::name_key(nombre);
+    entries.values().find(|entry| model::name_key(entry.nombre()) == key)
+}
+
+// Rechaza el nombre si otro registro (distinto de `id`) ya lo tiene, igual que el índice único de MongoDB
+fn ensure_unique_name<E: BookReference>(entries: &BTreeMap<ObjectId, E>, nombre: &str, id: ObjectId) -> Result<(), AppError> {
+    match find_by_name(entries, nombre) {
+        Some(other) if other.id() != Some(id) => Err(AppError::Conflict(E::DUPLICATE.to_string())),
+        _ => Ok(()),
+    }
+}
+
+// Rechaza el ISBN si otro libro activo (distinto de `id`) ya lo tiene, igual que el índice único de MongoDB;
+// los libros en la papelera no reservan su ISBN (restaurarlos vuelve a comprobarlo)
+fn ensure_unique_isbn(books: &BTreeMap<ObjectId, Book>, isbn: Option<&str>, id: ObjectId) -> Result<(), AppError> {
+    let Some(isbn) = isbn else {
+        return Ok(());
+    };
+    if books
+        .iter()
+        .any(|(other_id, book)| *other_id != id && book.deleted_at.is_none() && book.isbn.as_deref() == Some(isbn))
+    {
+        return Err(AppError::Conflict("Ya existe un libro con ese ISBN".to_string()));
+    }
+    Ok(())
+}
+
+// Crea un libro con las referencias resueltas (las que faltan se registran) y su entrada del historial;
+// si falla no modifica nada. Se llama con los bloqueos de autores, editoriales, libros e historial tomados
+fn insert_new_book(
+    authors: &mut BTreeMap<ObjectId, Author>,
+    publishers: &mut BTreeMap<ObjectId, Publisher>,
+    books: &mut BTreeMap<ObjectId, Book>,
+    history: &mut Vec<BookHistoryEntry>,
+    book_dto: CreateBookDto,
+    user: &str,
+) -> Result<Book, AppError> {
+    let id = ObjectId::new(); // Genera el ID igual que lo haría MongoDB
+    let author_request = ReferenceRequest::from_dto::<Author>(book_dto.autor_id.as_deref(), Some(&book_dto.autor))?;
+    let publisher_request =
+        ReferenceRequest::from_dto::<Publisher>(book_dto.editorial_id.as_deref(), Some(&book_dto.editorial))?;
+
+    let mut book = Book {
+        id: Some(id),
+        titulo: book_dto.titulo,
+        autor: book_dto.autor,
+        editorial: book_dto.editorial,
+        anio: book_dto.anio,
+        descripcion: book_dto.descripcion,
+        numero_pagina: book_dto.numero_pagina,
+        isbn: None,
+        isbn10: None,
+        deleted_at: None,
+        autor_ref: None,
+        editorial_ref: None,
+        copias: book_dto.copias.unwrap_or(1),
+        prestados: 0,
+        apartados: 0,
+        portada: None,
+        version: 0,
+    }
+    .with_isbn(book_dto.isbn)?; // Normaliza el ISBN y calcula su forma ISBN-10
+
+    let author = resolve(authors, &author_request)?;
+    let publisher = resolve(publishers, &publisher_request)?;
+    ensure_unique_isbn(books, book.isbn.as_deref(), id)?;
+
+    // El nombre del registro reemplaza al texto recibido
+    link(authors, author.as_ref(), &mut book);
+    link(publishers, publisher.as_ref(), &mut book);
+    books.insert(id, book.clone());
+    push_history(history, history::entry(id, HistoryAction::Create, user, None, &book));
+
+    Ok(book) // Devuelve el libro recién creado
+}
+
+// Agrega una entrada al historial; se llama con el bloqueo de los libros tomado, junto con el cambio
+fn push_history(history: &mut Vec<BookHistoryEntry>, mut entry: BookHistoryEntry) {
+    entry.id = Some(ObjectId::new()); // Genera el ID igual que lo haría MongoDB
+    history.push(entry);
+}
+
+// Indica si un libro cumple con todos los filtros del listado
+fn matches_filter(book: &Book, filter: &BookFilter) -> bool {
+    let text_matches = |value: &str, expected: &Option<String>| {
+        expected
+            .as_ref()
+            .is_none_or(|expected| value.to_lowercase() == expected.to_lowercase())
+    };
+    let in_range = |value: i32, min: Option<i32>, max: Option<i32>| {
+        min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+    };
+
+    let references = |reference: Option<ObjectId>, expected: Option<ObjectId>| {
+        expected.is_none_or(|expected| reference == Some(expected))
+    };
+
+    text_matches(&book.autor, &filter.autor)
+        && text_matches(&book.editorial, &filter.editorial)
+        && references(book.autor_ref.as_ref().map(|author| author.id), filter.autor_id)
+        && references(book.editorial_ref.as_ref().map(|publisher| publisher.id), filter.editorial_id)
+        && in_range(book.anio, filter.anio_min, filter.anio_max)
+        && in_range(book.numero_pagina, filter.paginas_min, filter.paginas_max)
+}
+
+// Compara dos libros según la lista de campos de orden
+fn compare_books(a: &Book, b: &Book, sort: &[SortField]) -> Ordering {
+    for field in sort {
+        let ordering = match field.field.as_str() {
+            "titulo" => a.titulo.cmp(&b.titulo),
+            "autor" => a.autor.cmp(&b.autor),
+            "editorial" => a.editorial.cmp(&b.editorial),
+            "anio" => a.anio.cmp(&b.anio),
+            "numero_pagina" => a.numero_pagina.cmp(&b.numero_pagina),
+            _ => Ordering::Equal,
+        };
+        let ordering = if field.descending { ordering.reverse() } else { ordering };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
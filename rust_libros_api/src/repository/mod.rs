@@ -1 +1,94 @@
-pub mod mongodb_repo;
\ No newline at end of file
+use std::sync::Arc;
+
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    error::{AppError, FieldError},
+    model::{Author, Book, BookReference, Publisher},
+};
+use book_repository::BookRepository;
+use loan_repository::LoanRepository;
+use reference_repository::ReferenceRepository;
+
+pub mod book_repository;
+pub mod cover_repository;
+pub mod fs_cover_repo;
+pub mod loan_repository;
+pub mod memory_repo;
+pub mod mongodb_repo;
+pub mod reference_repository;
+
+// Un mismo almacenamiento visto a través de cada uno de sus contratos
+pub struct Repositories {
+    pub books: Arc<dyn BookRepository>,
+    pub authors: Arc<dyn ReferenceRepository<Author>>,
+    pub publishers: Arc<dyn ReferenceRepository<Publisher>>,
+    pub loans: Arc<dyn LoanRepository>,
+}
+
+impl Repositories {
+    // Comparte el repositorio indicado entre todos los contratos
+    pub fn new<R>(repo: Arc<R>) -> Self
+    where
+        R: BookRepository + ReferenceRepository<Author> + ReferenceRepository<Publisher> + LoanRepository + 'static,
+    {
+        Repositories {
+            books: repo.clone(),
+            authors: repo.clone(),
+            publishers: repo.clone(),
+            loans: repo,
+        }
+    }
+}
+
+// Convierte el ID de una referencia del DTO en un ObjectId
+fn parse_reference(id: &str, field: &str) -> Result<ObjectId, AppError> {
+    ObjectId::parse_str(id).map_err(|_| AppError::InvalidIDError(format!("{} inválido", field)))
+}
+
+// Autor o editorial que se pide para un libro: por ID o, si no hay ID, por nombre
+#[derive(Debug, Clone, Default)]
+struct ReferenceRequest {
+    id: Option<ObjectId>,
+    nombre: Option<String>,
+}
+
+impl ReferenceRequest {
+    // Lee la referencia de los campos del DTO (`autor_id` y `autor`, o `editorial_id` y `editorial`)
+    fn from_dto<E: BookReference>(id: Option<&str>, nombre: Option<&str>) -> Result<Self, AppError> {
+        Ok(ReferenceRequest {
+            id: id.map(|id| parse_reference(id, E::ID_FIELD)).transpose()?,
+            nombre: nombre.map(str::to_string),
+        })
+    }
+
+    // Indica si el DTO no pide cambiar la referencia
+    fn is_empty(&self) -> bool {
+        self.id.is_none() && self.nombre.is_none()
+    }
+
+    // Referencia que tenía el libro indicado (al restaurar una versión anterior)
+    fn of<E: BookReference>(book: &Book) -> Self {
+        ReferenceRequest {
+            id: E::referenced_id(book),
+            nombre: Some(E::book_name(book).to_string()),
+        }
+    }
+}
+
+// Verifica que el libro siga en la versión que el cliente leyó (escrituras con `If-Match`)
+fn check_version(book: &Book, expected_version: Option<i64>) -> Result<(), AppError> {
+    match expected_version {
+        Some(version) if version != book.version => Err(AppError::PreconditionFailed),
+        _ => Ok(()),
+    }
+}
+
+// Error de validación para una referencia a un autor o editorial que no existe
+fn missing_reference(field: &str, message: &str) -> AppError {
+    AppError::ValidationError(vec![FieldError {
+        field: field.to_string(),
+        code: "not_found".to_string(),
+        message: message.to_string(),
+    }])
+}
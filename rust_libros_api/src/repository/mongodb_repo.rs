@@ -1,68 +1,649 @@
-use crate::{error::AppError, model::{Book, CreateBookDto, UpdateBookDto}};
-use futures::stream::TryStreamExt;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    error::AppError,
+    health::HealthCheck,
+    history, isbn, loans, metrics,
+    model::{
+        Author, Book, BookHistoryEntry, BookList, BookListOptions, BookReference, Cover, CreateBookDto, HistoryAction, Hold,
+        HoldStatus, Loan, LoanPolicy, LoanStatus, Publisher, SearchHit, SortField, UpdateBookDto,
+    },
+    repository::{
+        book_repository::{BatchError, BookRepository}, check_version, cover_repository::CoverRepository,
+        loan_repository::LoanRepository, missing_reference, reference_repository::ReferenceRepository,
+        ReferenceRequest,
+    },
+    search,
+};
+use async_trait::async_trait;
+use futures::{
+    future::BoxFuture,
+    stream::{BoxStream, StreamExt, TryStreamExt},
+};
 use mongodb::{
-    bson::{doc, oid::ObjectId, Document},
-    Database,
+    bson::{self, doc, oid::ObjectId, Bson, DateTime, Document},
+    error::{ErrorKind, GridFsErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    options::{
+        Collation, CollationStrength, FindOneAndUpdateOptions, FindOptions, GridFsBucketOptions, IndexOptions,
+        ReturnDocument, UpdateOptions,
+    },
+    Client, ClientSession, Database, GridFsBucket, IndexModel,
 };
 
 // Nombre de la colección en MongoDB
 const COLLECTION_NAME: &str = "books";
+// Nombre de la colección con el historial de cambios
+const HISTORY_COLLECTION_NAME: &str = "books_history";
+// Nombre de la colección de autores
+const AUTHORS_COLLECTION_NAME: &str = "authors";
+// Nombre de la colección de editoriales
+const PUBLISHERS_COLLECTION_NAME: &str = "publishers";
+// Nombre de la colección de préstamos
+const LOANS_COLLECTION_NAME: &str = "loans";
+// Nombre de la colección de reservas
+const HOLDS_COLLECTION_NAME: &str = "holds";
+// Nombre de la colección con un documento por socio, usado para ordenar sus préstamos y reservas
+const MEMBERS_COLLECTION_NAME: &str = "members";
+// Nombre del bucket de GridFS con las imágenes de portada
+const COVERS_BUCKET_NAME: &str = "covers";
+// Nombre del índice de texto usado por la búsqueda
+const TEXT_INDEX_NAME: &str = "books_text";
+// Nombre del índice único de ISBN entre los libros activos
+const ISBN_INDEX_NAME: &str = "books_isbn_active_unique";
+// Índices que ya no se usan y se eliminan al iniciar (el ISBN era único incluso en la papelera)
+const OBSOLETE_INDEX_NAMES: [(&str, &str); 1] = [(COLLECTION_NAME, "books_isbn_unique")];
+// Nombre del índice del historial por libro y fecha
+const HISTORY_INDEX_NAME: &str = "books_history_book";
+// Nombres de los índices de las referencias a autores y editoriales
+const AUTHOR_REF_INDEX_NAME: &str = "books_autor_ref";
+const PUBLISHER_REF_INDEX_NAME: &str = "books_editorial_ref";
+// Un solo autor y una sola editorial por nombre (sin distinguir mayúsculas ni tildes)
+const AUTHORS_NAME_INDEX_NAME: &str = "authors_nombre_unique";
+const PUBLISHERS_NAME_INDEX_NAME: &str = "publishers_nombre_unique";
+// Idioma de la intercalación con la que se comparan los nombres
+const NAME_COLLATION_LOCALE: &str = "es";
+// Nombres de los índices de préstamos (por socio y por vencimiento) y de la cola de reservas
+const LOANS_MEMBER_INDEX_NAME: &str = "loans_member";
+const LOANS_DUE_INDEX_NAME: &str = "loans_due";
+const HOLDS_QUEUE_INDEX_NAME: &str = "holds_queue";
+// Un solo préstamo activo y una sola reserva pendiente por socio y libro
+const LOANS_ACTIVE_INDEX_NAME: &str = "loans_member_book_active_unique";
+const HOLDS_PENDING_INDEX_NAME: &str = "holds_member_book_pending_unique";
+// Código de error de MongoDB cuando no existe un índice (de texto o al eliminarlo)
+const INDEX_NOT_FOUND_CODE: i32 = 27;
+// Código de error de MongoDB cuando no existe la colección
+const NAMESPACE_NOT_FOUND_CODE: i32 = 26;
+// Código de error de MongoDB al violar un índice único
+const DUPLICATE_KEY_CODE: i32 = 11000;
+// Cantidad máxima de candidatos evaluados en memoria cuando no hay índice de texto
+const FALLBACK_CANDIDATES: i64 = 500;
+// Intentos de una transacción que falla por un conflicto de escritura con otra
+const TRANSACTION_ATTEMPTS: u32 = 5;
+
+// Libro por crear, con el autor y la editorial que se resuelven dentro de la transacción
+#[derive(Clone)]
+struct NewBook {
+    book: Book,
+    author_request: ReferenceRequest,
+    publisher_request: ReferenceRequest,
+}
+
+impl NewBook {
+    fn from_dto(book_dto: CreateBookDto) -> Result<Self, AppError> {
+        let author_request = ReferenceRequest::from_dto::<Author>(book_dto.autor_id.as_deref(), Some(&book_dto.autor))?;
+        let publisher_request =
+            ReferenceRequest::from_dto::<Publisher>(book_dto.editorial_id.as_deref(), Some(&book_dto.editorial))?;
+
+        // Crea un nuevo libro a partir del DTO recibido
+        let book = Book {
+            id: None, // El ID será generado automáticamente por MongoDB
+            titulo: book_dto.titulo,
+            autor: book_dto.autor,
+            editorial: book_dto.editorial,
+            anio: book_dto.anio,
+            descripcion: book_dto.descripcion,
+            numero_pagina: book_dto.numero_pagina,
+            isbn: None,
+            isbn10: None,
+            deleted_at: None,
+            autor_ref: None,
+            editorial_ref: None,
+            copias: book_dto.copias.unwrap_or(1),
+            prestados: 0,
+            apartados: 0,
+            portada: None,
+            version: 0,
+        }
+        .with_isbn(book_dto.isbn)?; // Normaliza el ISBN y calcula su forma ISBN-10
+
+        Ok(NewBook { book, author_request, publisher_request })
+    }
+}
 
 // Estructura que representa el repositorio de MongoDB
 #[derive(Clone)]
 pub struct MongoRepo {
+    client: Client, // Necesario para abrir las sesiones de las transacciones
     db: Database, // Base de datos de MongoDB
+    loan_policy: LoanPolicy, // Reglas de préstamo (duración, límite por socio, renovaciones)
 }
 
 impl MongoRepo {
-    // Constructor para inicializar el repositorio con una base de datos
-    pub fn new(db: Database) -> Self {
-        MongoRepo { db }
+    // Constructor para inicializar el repositorio con la base de datos indicada
+    pub fn new(client: Client, db_name: &str) -> Self {
+        let db = client.database(db_name);
+        MongoRepo {
+            client,
+            db,
+            loan_policy: LoanPolicy::default(),
+        }
+    }
+
+    // Bucket de GridFS donde se guardan las imágenes de portada
+    fn covers_bucket(&self) -> GridFsBucket {
+        let options = GridFsBucketOptions::builder().bucket_name(COVERS_BUCKET_NAME.to_string()).build();
+        self.db.gridfs_bucket(options)
     }
 
-    // Método para obtener todos los libros de la colección
-    pub async fn get_all_books(&self) -> Result<Vec<Book>, AppError> {
+    // IDs de los archivos de GridFS guardados con la clave indicada
+    async fn cover_file_ids(&self, key: &str) -> Result<Vec<Bson>, AppError> {
+        let files = self.covers_bucket().find(doc! {"filename": key}, None).await?;
+        Ok(files.map_ok(|file| file.id).try_collect().await?)
+    }
+
+    // Reemplaza las reglas de préstamo por defecto
+    pub fn with_loan_policy(mut self, loan_policy: LoanPolicy) -> Self {
+        self.loan_policy = loan_policy;
+        self
+    }
+
+    // Crea los índices que necesita el repositorio (se ejecuta al iniciar el servidor).
+    // Cada índice se crea por separado: si falla uno de consulta solo se registra y se crean los demás
+    // (sin el de texto, la búsqueda usa el modo en memoria); si falla uno único se devuelve el error,
+    // porque sin él se aceptarían datos duplicados.
+    pub async fn ensure_indexes(&self) -> Result<(), AppError> {
+        for (collection, name) in OBSOLETE_INDEX_NAMES {
+            match self.db.collection::<Document>(collection).drop_index(name, None).await {
+                Ok(()) => log::info!("Índice obsoleto {} eliminado", name),
+                Err(err) if is_missing_index(&err) => {}
+                Err(err) => log::warn!("No se pudo eliminar el índice obsoleto {}: {}", name, err),
+            }
+        }
+
+        let mut unique_failure = None;
+        for (collection, index, unique) in index_models()? {
+            let name = index.options.as_ref().and_then(|options| options.name.clone()).unwrap_or_default();
+            match self.db.collection::<Document>(collection).create_index(index, None).await {
+                Ok(_) => log::debug!("Índice {} listo", name),
+                Err(err) if unique => {
+                    log::error!("No se pudo crear el índice único {}: {}", name, err);
+                    unique_failure.get_or_insert(err);
+                }
+                Err(err) => log::warn!("No se pudo crear el índice {}: {}", name, err),
+            }
+        }
+        match unique_failure {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    // Búsqueda alternativa cuando el índice de texto no está disponible
+    async fn search_books_fallback(&self, query: &str, limit: u64) -> Result<Vec<SearchHit>, AppError> {
         let collection = self.db.collection::<Book>(COLLECTION_NAME);
-        let mut cursor = collection.find(None, None).await?;
-        
+        let terms = search::terms(query);
+
+        // Preselecciona los libros que contienen algún término en algún campo
+        let conditions: Vec<Document> = terms
+            .iter()
+            .flat_map(|term| {
+                search::SEARCH_WEIGHTS.iter().map(move |(field, _)| {
+                    doc! {*field: {"$regex": search::accent_insensitive_pattern(term), "$options": "i"}}
+                })
+            })
+            .collect();
+        let find_options = FindOptions::builder().limit(FALLBACK_CANDIDATES).build();
+        let filter = doc! {"$or": conditions, "deleted_at": null}; // Excluye los libros en la papelera
+        let mut cursor = collection.find(filter, find_options).await?;
+
         let mut books = Vec::new();
         while let Some(book) = cursor.try_next().await? {
-            books.push(book); // Agrega cada libro encontrado al vector
+            books.push(book);
         }
-        
-        Ok(books) // Devuelve la lista de libros
+
+        Ok(search::rank(books, &terms, limit))
     }
 
-    // Método para crear un nuevo libro en la colección
-    pub async fn create_book(&self, book_dto: CreateBookDto) -> Result<Book, AppError> {
+    // Registro pedido para un libro, por ID o por nombre (sin distinguir mayúsculas ni tildes); un nombre que no
+    // está registrado se registra. El registro se escribe dentro de la transacción, así que una eliminación o un
+    // cambio de nombre simultáneo choca aquí y una de las dos transacciones se reintenta
+    async fn reference_in<E: MongoReference>(
+        &self,
+        session: &mut ClientSession,
+        request: &ReferenceRequest,
+    ) -> Result<Option<E>, AppError> {
+        let collection = self.db.collection::<E>(E::COLLECTION);
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After);
+        let (filter, options) = match (request.id, request.nombre.as_deref()) {
+            (Some(id), _) => (doc! {"_id": id}, options.build()),
+            (None, Some(nombre)) => (doc! {"nombre": nombre}, options.upsert(true).collation(name_collation()).build()),
+            (None, None) => return Ok(None),
+        };
+        let update = doc! {"$set": {"referenciado_en": DateTime::now()}};
+        match collection
+            .find_one_and_update_with_session(filter, update, options, session)
+            .await
+            .map_err(|err| map_duplicate_key(err, E::DUPLICATE))?
+        {
+            Some(entry) => Ok(Some(entry)),
+            None => Err(missing_reference(E::ID_FIELD, E::NOT_FOUND)),
+        }
+    }
+
+    // Suma las diferencias indicadas a los contadores de copias prestadas y apartadas del libro
+    async fn adjust_copies_in(
+        &self,
+        session: &mut ClientSession,
+        book_id: ObjectId,
+        prestados: i32,
+        apartados: i32,
+    ) -> Result<(), AppError> {
         let collection = self.db.collection::<Book>(COLLECTION_NAME);
+        let update = doc! {"$inc": {"prestados": prestados, "apartados": apartados, "version": 1}};
+        collection.update_one_with_session(doc! {"_id": book_id}, update, None, session).await?;
+        Ok(())
+    }
 
-        // Crea un nuevo libro a partir del DTO recibido
-        let book = Book {
+    // Aparta una copia para la primera reserva en espera del libro; indica si había alguna
+    async fn promote_next_hold_in(&self, session: &mut ClientSession, book_id: ObjectId) -> Result<bool, AppError> {
+        let collection = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+        let filter = doc! {"libro_id": book_id, "estado": bson::to_bson(&HoldStatus::Waiting)?};
+        let update = doc! {"$set": {"estado": bson::to_bson(&HoldStatus::Ready)?, "listo_en": DateTime::now()}};
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! {"creado_en": 1, "_id": 1}) // La reserva más antigua primero
+            .build();
+        Ok(collection.find_one_and_update_with_session(filter, update, options, session).await?.is_some())
+    }
+
+    // Escribe el documento del socio dentro de la transacción: dos transacciones del mismo socio chocan
+    // aquí y una se reintenta, así que cada una ve los préstamos y reservas que creó la otra
+    async fn lock_member_in(&self, session: &mut ClientSession, member_id: &str) -> Result<(), AppError> {
+        let collection = self.db.collection::<Document>(MEMBERS_COLLECTION_NAME);
+        let options = UpdateOptions::builder().upsert(true).build();
+        let update = doc! {"$set": {"actualizado_en": DateTime::now()}};
+        collection.update_one_with_session(doc! {"_id": member_id}, update, options, session).await?;
+        Ok(())
+    }
+
+    // Libro activo con el ID indicado, leído dentro de la transacción
+    async fn book_in(&self, session: &mut ClientSession, id: ObjectId) -> Result<Book, AppError> {
+        let collection = self.db.collection::<Book>(COLLECTION_NAME);
+        collection
+            .find_one_with_session(doc! {"_id": id, "deleted_at": null}, None, session)
+            .await?
+            .ok_or(AppError::NotFoundError) // Error si el libro no existe o está en la papelera
+    }
+
+    // Inserta un libro nuevo dentro de la transacción (ver `create_book`) y devuelve el libro con su ID
+    async fn create_book_in(&self, session: &mut ClientSession, new_book: NewBook, user: &str) -> Result<Book, AppError> {
+        let NewBook { mut book, author_request, publisher_request } = new_book;
+
+        // El nombre del registro reemplaza al texto recibido
+        if let Some(author) = self.reference_in::<Author>(session, &author_request).await? {
+            author.link(&mut book);
+        }
+        if let Some(publisher) = self.reference_in::<Publisher>(session, &publisher_request).await? {
+            publisher.link(&mut book);
+        }
+
+        // Inserta el libro en la colección (el índice único rechaza ISBN repetidos)
+        let collection = self.db.collection::<Book>(COLLECTION_NAME);
+        let insert_result = collection
+            .insert_one_with_session(&book, None, session)
+            .await
+            .map_err(map_duplicate_isbn)?;
+        let id = insert_result.inserted_id.as_object_id().ok_or(AppError::InternalError)?; // Obtiene el ID generado
+
+        book.id = Some(id);
+        self.append_history_in(session, history::entry(id, HistoryAction::Create, user, None, &book))
+            .await?;
+        Ok(book)
+    }
+
+    // Guarda una entrada del historial dentro de la transacción del cambio que registra
+    async fn append_history_in(&self, session: &mut ClientSession, entry: BookHistoryEntry) -> Result<(), AppError> {
+        let collection = self.db.collection::<BookHistoryEntry>(HISTORY_COLLECTION_NAME);
+        collection.insert_one_with_session(entry, None, session).await?;
+        Ok(())
+    }
+
+    // Presta una copia dentro de la transacción (ver `checkout`)
+    async fn checkout_in(&self, session: &mut ClientSession, book_id: ObjectId, member_id: &str) -> Result<Loan, AppError> {
+        let books = self.db.collection::<Book>(COLLECTION_NAME);
+        let loans_collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+        let holds = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+
+        self.lock_member_in(session, member_id).await?;
+        self.book_in(session, book_id).await?;
+
+        // Límite de préstamos por socio y un solo préstamo activo del mismo libro
+        let filter = doc! {"socio_id": member_id, "estado": bson::to_bson(&LoanStatus::Active)?};
+        let mut cursor = loans_collection.find_with_session(filter, None, session).await?;
+        let member_loans: Vec<Loan> = cursor.stream(session).try_collect().await?;
+        let member_loans: Vec<&Loan> = member_loans.iter().collect();
+        loans::check_member_can_borrow(&member_loans, book_id, self.loan_policy.max_loans)?;
+
+        // Si el socio tiene una copia apartada la retira; si no, ocupa una copia disponible
+        let ready_hold = holds
+            .find_one_and_update_with_session(
+                doc! {"libro_id": book_id, "socio_id": member_id, "estado": bson::to_bson(&HoldStatus::Ready)?},
+                doc! {"$set": {"estado": bson::to_bson(&HoldStatus::Fulfilled)?}},
+                None,
+                session,
+            )
+            .await?;
+        if ready_hold.is_some() {
+            self.adjust_copies_in(session, book_id, 1, -1).await?;
+        } else {
+            // La condición y el incremento son una sola operación: dos préstamos no toman la misma copia
+            let filter = doc! {
+                "_id": book_id,
+                "deleted_at": null,
+                "$expr": {"$lt": [copies_out_expression(), {"$ifNull": ["$copias", 1]}]},
+            };
+            let update = doc! {"$inc": {"prestados": 1, "version": 1}};
+            let update_result = books.update_one_with_session(filter, update, None, session).await?;
+            if update_result.matched_count == 0 {
+                return Err(loans::no_copies_available());
+            }
+        }
+
+        let now = DateTime::now();
+        let mut loan = Loan {
             id: None, // El ID será generado automáticamente por MongoDB
-            titulo: book_dto.titulo,
-            autor: book_dto.autor,
-            editorial: book_dto.editorial,
-            anio: book_dto.anio,
-            descripcion: book_dto.descripcion,
-            numero_pagina: book_dto.numero_pagina,
+            libro_id: book_id,
+            socio_id: member_id.to_string(),
+            estado: LoanStatus::Active,
+            prestado_en: now,
+            vence_en: self.loan_policy.due_date(now),
+            devuelto_en: None,
+            renovaciones: 0,
         };
+        // El índice único de préstamos activos por socio y libro respalda la verificación anterior
+        let insert_result = loans_collection
+            .insert_one_with_session(&loan, None, session)
+            .await
+            .map_err(|err| map_duplicate_key(err, "El socio ya tiene prestado este libro"))?;
+        loan.id = insert_result.inserted_id.as_object_id(); // Obtiene el ID generado
+        Ok(loan)
+    }
+
+    // Registra la devolución dentro de la transacción (ver `return_loan`)
+    async fn return_loan_in(&self, session: &mut ClientSession, id: ObjectId) -> Result<Loan, AppError> {
+        let collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
 
-        // Inserta el libro en la colección
-        let insert_result = collection.insert_one(book, None).await?;
-        let id = match insert_result.inserted_id.as_object_id() {
-            Some(object_id) => object_id, // Obtiene el ID generado
-            None => return Err(AppError::InternalError), // Error si no se genera un ID
+        let filter = doc! {"_id": id, "estado": bson::to_bson(&LoanStatus::Active)?};
+        let update = doc! {"$set": {"estado": bson::to_bson(&LoanStatus::Returned)?, "devuelto_en": DateTime::now()}};
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After) // Devuelve el préstamo ya devuelto
+            .build();
+
+        let loan = match collection.find_one_and_update_with_session(filter, update, options, session).await? {
+            Some(loan) => loan,
+            None => {
+                // Error si el préstamo no existe
+                collection
+                    .find_one_with_session(doc! {"_id": id}, None, session)
+                    .await?
+                    .ok_or(AppError::NotFoundError)?;
+                return Err(AppError::Conflict("El préstamo ya fue devuelto".to_string()));
+            }
         };
 
-        self.get_book(id).await // Devuelve el libro recién creado
+        // La copia devuelta se aparta para la primera reserva en espera o vuelve a estar disponible
+        if self.promote_next_hold_in(session, loan.libro_id).await? {
+            self.adjust_copies_in(session, loan.libro_id, -1, 1).await?;
+        } else {
+            self.adjust_copies_in(session, loan.libro_id, -1, 0).await?;
+        }
+        Ok(loan)
+    }
+
+    // Pone al socio en la cola dentro de la transacción (ver `place_hold`)
+    async fn place_hold_in(&self, session: &mut ClientSession, book_id: ObjectId, member_id: &str) -> Result<Hold, AppError> {
+        let loans_collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+        let holds = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+
+        self.lock_member_in(session, member_id).await?;
+        let book = self.book_in(session, book_id).await?;
+        let has_loan = loans_collection
+            .count_documents_with_session(
+                doc! {"libro_id": book_id, "socio_id": member_id, "estado": bson::to_bson(&LoanStatus::Active)?},
+                None,
+                session,
+            )
+            .await?
+            > 0;
+        let has_hold = holds
+            .count_documents_with_session(
+                doc! {"libro_id": book_id, "socio_id": member_id, "estado": pending_holds()?},
+                None,
+                session,
+            )
+            .await?
+            > 0;
+        loans::check_can_hold(&book, has_loan, has_hold)?;
+
+        let mut hold = Hold {
+            id: None, // El ID será generado automáticamente por MongoDB
+            libro_id: book_id,
+            socio_id: member_id.to_string(),
+            estado: HoldStatus::Waiting,
+            creado_en: DateTime::now(),
+            listo_en: None,
+        };
+        // El índice único de reservas pendientes por socio y libro respalda la verificación anterior
+        let insert_result = holds
+            .insert_one_with_session(&hold, None, session)
+            .await
+            .map_err(|err| map_duplicate_key(err, "El socio ya tiene una reserva de este libro"))?;
+        hold.id = insert_result.inserted_id.as_object_id(); // Obtiene el ID generado
+        Ok(hold)
+    }
+
+    // Cancela la reserva dentro de la transacción (ver `cancel_hold`)
+    async fn cancel_hold_in(&self, session: &mut ClientSession, id: ObjectId) -> Result<Hold, AppError> {
+        let collection = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+
+        let filter = doc! {"_id": id, "estado": pending_holds()?};
+        let update = doc! {"$set": {"estado": bson::to_bson(&HoldStatus::Cancelled)?}};
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::Before) // El estado anterior indica si tenía una copia apartada
+            .build();
+
+        let mut hold = match collection.find_one_and_update_with_session(filter, update, options, session).await? {
+            Some(hold) => hold,
+            None => {
+                // La reserva no existe o ya no está pendiente
+                let exists = collection.find_one_with_session(doc! {"_id": id}, None, session).await?.is_some();
+                return Err(if exists {
+                    AppError::Conflict("La reserva ya no está pendiente".to_string())
+                } else {
+                    AppError::NotFoundError
+                });
+            }
+        };
+
+        // La copia apartada pasa a la siguiente reserva de la cola o vuelve a estar disponible
+        if hold.estado == HoldStatus::Ready && !self.promote_next_hold_in(session, hold.libro_id).await? {
+            self.adjust_copies_in(session, hold.libro_id, 0, -1).await?;
+        }
+
+        hold.estado = HoldStatus::Cancelled;
+        Ok(hold)
+    }
+
+    // Ejecuta `operation` en una transacción y la reintenta completa ante errores transitorios
+    // (p. ej. un conflicto de escritura con otra transacción del mismo socio)
+    async fn run_transaction<T, F>(&self, operation: F) -> Result<T, AppError>
+    where
+        F: for<'a> Fn(&'a MongoRepo, &'a mut ClientSession) -> BoxFuture<'a, Result<T, AppError>>,
+    {
+        let mut session = self.client.start_session(None).await?;
+        let mut attempt = 1;
+        loop {
+            session.start_transaction(None).await?;
+            let result = operation(self, &mut session).await;
+            match finish_transaction(&mut session, result).await {
+                Err(err) if is_transient(&err) && attempt < TRANSACTION_ATTEMPTS => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+}
+
+#[async_trait]
+impl BookRepository for MongoRepo {
+    // Método para obtener una página de libros aplicando filtros, orden y paginación
+    async fn get_all_books(&self, options: &BookListOptions) -> Result<BookList, AppError> {
+        let _timer = metrics::mongo_timer("get_all_books");
+        let collection = self.db.collection::<Book>(COLLECTION_NAME);
+
+        let filter = filter_document(options);
+        let total = collection.count_documents(filter.clone(), None).await?; // Total sin paginar
+
+        // El cursor solo limita la página, no el total
+        let mut page_filter = filter;
+        if let Some(after) = options.after {
+            page_filter.insert("_id", doc! {"$gt": after});
+        }
+
+        // Se pide un libro extra para saber si existe una página siguiente
+        let find_options = FindOptions::builder()
+            .sort(sort_document(&options.sort))
+            .skip(options.skip)
+            .limit((options.limit + 1) as i64)
+            .build();
+
+        let mut cursor = collection.find(page_filter, find_options).await?;
+
+        let mut books = Vec::new();
+        while let Some(book) = cursor.try_next().await? {
+            books.push(book); // Agrega cada libro encontrado al vector
+        }
+
+        let has_more = books.len() as u64 > options.limit;
+        books.truncate(options.limit as usize);
+
+        Ok(BookList { books, total, has_more }) // Devuelve la página de libros
+    }
+
+    // Método para recorrer todos los libros con el cursor de MongoDB
+    async fn stream_books(&self) -> Result<BoxStream<'static, Result<Book, AppError>>, AppError> {
+        let _timer = metrics::mongo_timer("stream_books");
+        let collection = self.db.collection::<Book>(COLLECTION_NAME);
+        let find_options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+        let cursor = collection.find(doc! {"deleted_at": null}, find_options).await?;
+        Ok(cursor.map_err(AppError::from).boxed()) // Cada libro se lee del cursor a medida que se envía
+    }
+
+    // Método para buscar libros con el índice de texto de MongoDB, ordenados por relevancia
+    async fn search_books(&self, query: &str, limit: u64) -> Result<Vec<SearchHit>, AppError> {
+        let _timer = metrics::mongo_timer("search_books");
+        let terms = search::terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new()); // La consulta no contiene palabras buscables
+        }
+
+        let collection = self.db.collection::<Document>(COLLECTION_NAME);
+        let find_options = FindOptions::builder()
+            .projection(doc! {"score": {"$meta": "textScore"}})
+            .sort(doc! {"score": {"$meta": "textScore"}})
+            .limit(limit as i64)
+            .build();
+
+        let mut cursor = match collection.find(doc! {"$text": {"$search": query}, "deleted_at": null}, find_options).await {
+            Ok(cursor) => cursor,
+            Err(err) if is_missing_text_index(&err) => {
+                log::warn!("Índice de texto no disponible, se usa la búsqueda en memoria");
+                return self.search_books_fallback(query, limit).await;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut hits = Vec::new();
+        while let Some(mut document) = cursor.try_next().await? {
+            let score = document.remove("score").and_then(|score| score.as_f64()).unwrap_or(0.0);
+            let book: Book = bson::from_document(document)?;
+            hits.push(SearchHit {
+                highlights: search::highlights(&book, &terms),
+                libro: book,
+                score,
+            });
+        }
+
+        Ok(hits) // Devuelve los resultados ordenados por relevancia
+    }
+
+    // Método para crear un nuevo libro en la colección. Es una transacción: el autor y la editorial se resuelven (o se
+    // registran) y el libro se inserta con su entrada del historial sin que otra solicitud los elimine o los registre
+    // dos veces entre ambos pasos
+    async fn create_book(&self, book_dto: CreateBookDto, user: &str) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("create_book");
+        let new_book = NewBook::from_dto(book_dto)?;
+
+        let user = user.to_string();
+        let created = self
+            .run_transaction(|repo, session| {
+                let (new_book, user) = (new_book.clone(), user.clone());
+                Box::pin(async move { repo.create_book_in(session, new_book, &user).await })
+            })
+            .await?;
+
+        self.get_book(created.id.ok_or(AppError::InternalError)?).await // Devuelve el libro recién creado
+    }
+
+    // Método para crear un lote de libros en una sola transacción: si una fila falla no se guarda ninguna
+    async fn create_books(&self, book_dtos: Vec<CreateBookDto>, user: &str) -> Result<Vec<Book>, BatchError> {
+        let _timer = metrics::mongo_timer("create_books");
+        let mut new_books = Vec::with_capacity(book_dtos.len());
+        for (index, book_dto) in book_dtos.into_iter().enumerate() {
+            new_books.push(NewBook::from_dto(book_dto).map_err(|error| BatchError { index: Some(index), error })?);
+        }
+
+        // Fila que abortó la transacción, para reportarla junto con el error
+        let failed_row = Arc::new(Mutex::new(None));
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (new_books, user, failed_row) = (new_books.clone(), user.clone(), failed_row.clone());
+            Box::pin(async move {
+                let mut created = Vec::with_capacity(new_books.len());
+                for (index, new_book) in new_books.into_iter().enumerate() {
+                    match repo.create_book_in(session, new_book, &user).await {
+                        Ok(book) => created.push(book),
+                        Err(err) => {
+                            *failed_row.lock().map_err(|_| AppError::InternalError)? = Some(index);
+                            return Err(err);
+                        }
+                    }
+                }
+                Ok(created)
+            })
+        })
+        .await
+        .map_err(|error| BatchError {
+            index: failed_row.lock().ok().and_then(|mut row| row.take()),
+            error,
+        })
     }
 
     // Método para obtener un libro por su ID
-    pub async fn get_book(&self, id: ObjectId) -> Result<Book, AppError> {
+    async fn get_book(&self, id: ObjectId) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("get_book");
         let collection = self.db.collection::<Book>(COLLECTION_NAME);
         
-        let filter = doc! {"_id": id}; // Filtro para buscar por ID
+        let filter = doc! {"_id": id, "deleted_at": null}; // Filtro para buscar por ID (fuera de la papelera)
         let book = collection
             .find_one(filter, None)
             .await?
@@ -71,11 +652,36 @@ impl MongoRepo {
         Ok(book) // Devuelve el libro encontrado
     }
 
-    // Método para actualizar un libro por su ID
-    pub async fn update_book(&self, id: ObjectId, book_dto: UpdateBookDto) -> Result<Book, AppError> {
+    // Método para obtener un libro por su ISBN-13 normalizado
+    async fn get_book_by_isbn(&self, isbn: &str) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("get_book_by_isbn");
         let collection = self.db.collection::<Book>(COLLECTION_NAME);
+
+        let filter = doc! {"isbn": isbn, "deleted_at": null}; // Filtro para buscar por ISBN (fuera de la papelera)
+        let book = collection
+            .find_one(filter, None)
+            .await?
+            .ok_or(AppError::NotFoundError)?; // Error si no se encuentra el libro
+
+        Ok(book) // Devuelve el libro encontrado
+    }
+
+    // Método para actualizar un libro por su ID. Es una transacción: el autor y la editorial se resuelven (o se
+    // registran) y el libro se actualiza con su entrada del historial sin que otra solicitud los elimine entre ambos pasos
+    async fn update_book(
+        &self,
+        id: ObjectId,
+        book_dto: UpdateBookDto,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("update_book");
+        let author_request = ReferenceRequest::from_dto::<Author>(book_dto.autor_id.as_deref(), book_dto.autor.as_deref())?;
+        let publisher_request =
+            ReferenceRequest::from_dto::<Publisher>(book_dto.editorial_id.as_deref(), book_dto.editorial.as_deref())?;
         
-        let filter = doc! {"_id": id}; // Filtro para buscar por ID
+        let mut filter = doc! {"_id": id, "deleted_at": null}; // Filtro para buscar por ID (fuera de la papelera)
+        add_version_filter(&mut filter, expected_version); // La escritura solo se aplica sobre la versión que leyó el cliente
         
         let mut update_doc = Document::new(); // Documento para almacenar los campos a actualizar
         
@@ -84,14 +690,6 @@ impl MongoRepo {
             update_doc.insert("titulo", titulo);
         }
         
-        if let Some(autor) = book_dto.autor {
-            update_doc.insert("autor", autor);
-        }
-        
-        if let Some(editorial) = book_dto.editorial {
-            update_doc.insert("editorial", editorial);
-        }
-        
         if let Some(anio) = book_dto.anio {
             update_doc.insert("anio", anio);
         }
@@ -103,37 +701,960 @@ impl MongoRepo {
         if let Some(numero_pagina) = book_dto.numero_pagina {
             update_doc.insert("numero_pagina", numero_pagina);
         }
+
+        if let Some(isbn) = book_dto.isbn {
+            let isbn = isbn::parse(&isbn)?; // Se guarda siempre normalizado
+            update_doc.insert("isbn10", isbn::to_isbn10(&isbn).map_or(Bson::Null, Bson::String));
+            update_doc.insert("isbn", isbn);
+        }
+
+        // Las copias no pueden quedar por debajo de las prestadas y apartadas
+        if let Some(copias) = book_dto.copias {
+            update_doc.insert("copias", copias);
+            filter.insert("$expr", doc! {"$lte": [copies_out_expression(), copias]});
+        }
         
         // Si no hay nada que actualizar, regresamos el libro sin modificar
-        if update_doc.is_empty() {
-            return self.get_book(id).await;
+        if update_doc.is_empty() && author_request.is_empty() && publisher_request.is_empty() {
+            let current = self.get_book(id).await?;
+            check_version(&current, expected_version)?;
+            return Ok(current);
         }
         
-        let update = doc! {"$set": update_doc}; // Documento de actualización
-        
-        let options = mongodb::options::FindOneAndUpdateOptions::builder()
-            .return_document(mongodb::options::ReturnDocument::After) // Devuelve el documento actualizado
+        let copias = book_dto.copias;
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (filter, mut update_doc, user) = (filter.clone(), update_doc.clone(), user.clone());
+            let (author_request, publisher_request) = (author_request.clone(), publisher_request.clone());
+            Box::pin(async move {
+                let current = repo.book_in(session, id).await?; // Versión anterior, para el historial
+
+                // El ID del registro tiene prioridad sobre el nombre
+                if let Some(author) = repo.reference_in::<Author>(session, &author_request).await? {
+                    update_doc.extend(reference_fields(&author)?);
+                }
+                if let Some(publisher) = repo.reference_in::<Publisher>(session, &publisher_request).await? {
+                    update_doc.extend(reference_fields(&publisher)?);
+                }
+
+                let update = doc! {"$set": update_doc, "$inc": {"version": 1}}; // Documento de actualización
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After) // Devuelve el documento actualizado
+                    .build();
+
+                let collection = repo.db.collection::<Book>(COLLECTION_NAME);
+                match collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await
+                    .map_err(map_duplicate_isbn)?
+                {
+                    Some(mut book) => {
+                        // Las copias nuevas se apartan para las reservas en espera
+                        let mut promoted = 0;
+                        while copias.is_some()
+                            && book.disponibles() > promoted
+                            && repo.promote_next_hold_in(session, id).await?
+                        {
+                            promoted += 1;
+                        }
+                        if promoted > 0 {
+                            let update = doc! {"$inc": {"apartados": promoted}};
+                            collection.update_one_with_session(doc! {"_id": id}, update, None, session).await?;
+                            book.apartados += promoted;
+                        }
+
+                        let entry = history::entry(id, HistoryAction::Update, &user, Some(&current), &book);
+                        repo.append_history_in(session, entry).await?;
+                        Ok(book) // Devuelve el libro actualizado
+                    }
+                    None => {
+                        // El libro cambió de versión o no alcanzan las copias para los préstamos actuales
+                        check_version(&current, expected_version)?;
+                        loans::check_copies_cover_loans(&current, copias.unwrap_or(current.copias))?;
+                        Err(AppError::NotFoundError)
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    // Método para enviar un libro a la papelera (eliminación lógica), en una transacción con su entrada del historial
+    async fn delete_book(&self, id: ObjectId, expected_version: Option<i64>, user: &str) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("delete_book");
+
+        // Solo libros fuera de la papelera y sin copias prestadas ni apartadas
+        let mut filter = doc! {
+            "_id": id,
+            "deleted_at": null,
+            "prestados": {"$in": [null, 0]},
+            "apartados": {"$in": [null, 0]},
+        };
+        add_version_filter(&mut filter, expected_version);
+
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (filter, user) = (filter.clone(), user.clone());
+            Box::pin(async move {
+                let current = repo.book_in(session, id).await?; // Versión anterior, para el historial
+
+                let update = doc! {"$set": {"deleted_at": DateTime::now()}, "$inc": {"version": 1}}; // Marca la fecha de eliminación
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After) // Devuelve el documento en la papelera
+                    .build();
+                let collection = repo.db.collection::<Book>(COLLECTION_NAME);
+                let Some(deleted_book) = collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await?
+                else {
+                    // El libro cambió de versión o todavía tiene copias fuera
+                    check_version(&current, expected_version)?;
+                    loans::check_no_copies_out(&current)?;
+                    return Err(AppError::NotFoundError);
+                };
+
+                let entry = history::entry(id, HistoryAction::Delete, &user, Some(&current), &deleted_book);
+                repo.append_history_in(session, entry).await?;
+
+                // Las reservas pendientes se cancelan: un libro en la papelera no se puede prestar
+                let holds = repo.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+                holds
+                    .update_many_with_session(
+                        doc! {"libro_id": id, "estado": pending_holds()?},
+                        doc! {"$set": {"estado": bson::to_bson(&HoldStatus::Cancelled)?}},
+                        None,
+                        session,
+                    )
+                    .await?;
+                Ok(deleted_book) // Devuelve el libro tal como quedó en la papelera
+            })
+        })
+        .await
+    }
+
+    // Método para obtener los libros de la papelera, los eliminados más recientemente primero
+    async fn get_deleted_books(&self) -> Result<Vec<Book>, AppError> {
+        let _timer = metrics::mongo_timer("get_deleted_books");
+        let collection = self.db.collection::<Book>(COLLECTION_NAME);
+
+        let find_options = FindOptions::builder().sort(doc! {"deleted_at": -1}).build();
+        let mut cursor = collection
+            .find(doc! {"deleted_at": {"$ne": null}}, find_options)
+            .await?;
+
+        let mut books = Vec::new();
+        while let Some(book) = cursor.try_next().await? {
+            books.push(book); // Agrega cada libro encontrado al vector
+        }
+
+        Ok(books) // Devuelve los libros de la papelera
+    }
+
+    // Método para sacar un libro de la papelera, en una transacción con su entrada del historial
+    async fn restore_book(&self, id: ObjectId, user: &str) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("restore_book");
+
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let user = user.clone();
+            Box::pin(async move {
+                let collection = repo.db.collection::<Book>(COLLECTION_NAME);
+                let filter = doc! {"_id": id, "deleted_at": {"$ne": null}}; // Solo libros en la papelera
+                let trashed = collection
+                    .find_one_with_session(filter.clone(), None, session)
+                    .await?
+                    .ok_or(AppError::NotFoundError)?; // Error si el libro no está en la papelera
+
+                let update = doc! {"$unset": {"deleted_at": ""}, "$inc": {"version": 1}};
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After) // Devuelve el documento restaurado
+                    .build();
+
+                // El índice único rechaza la restauración si otro libro activo registró el mismo ISBN mientras tanto
+                let restored_book = collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await
+                    .map_err(map_duplicate_isbn)?
+                    .ok_or(AppError::NotFoundError)?;
+
+                let entry = history::entry(id, HistoryAction::Restore, &user, Some(&trashed), &restored_book);
+                repo.append_history_in(session, entry).await?;
+                Ok(restored_book) // Devuelve el libro restaurado
+            })
+        })
+        .await
+    }
+
+    // Método para eliminar definitivamente los libros que están en la papelera desde antes de `before`
+    // El libro y su historial se eliminan en la misma transacción
+    async fn purge_deleted_books(&self, before: DateTime) -> Result<Vec<Book>, AppError> {
+        let _timer = metrics::mongo_timer("purge_deleted_books");
+
+        self.run_transaction(|repo, session| {
+            Box::pin(async move {
+                let books = repo.db.collection::<Book>(COLLECTION_NAME);
+                let filter = doc! {"deleted_at": {"$ne": null, "$lt": before}};
+                let mut cursor = books.find_with_session(filter, None, session).await?;
+                let purged: Vec<Book> = cursor.stream(session).try_collect().await?;
+                let ids: Vec<ObjectId> = purged.iter().filter_map(|book| book.id).collect();
+                if ids.is_empty() {
+                    return Ok(purged);
+                }
+
+                books
+                    .delete_many_with_session(doc! {"_id": {"$in": &ids}}, None, session)
+                    .await?;
+                repo.db
+                    .collection::<BookHistoryEntry>(HISTORY_COLLECTION_NAME)
+                    .delete_many_with_session(doc! {"book_id": {"$in": &ids}}, None, session)
+                    .await?;
+                Ok(purged) // Devuelve los libros eliminados
+            })
+        })
+        .await
+    }
+
+    // Método para reemplazar los campos editables de un libro por los de otra versión. Es una transacción, igual
+    // que `update_book`
+    async fn replace_book(
+        &self,
+        id: ObjectId,
+        version: &Book,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<Book, AppError> {
+        let _timer = metrics::mongo_timer("replace_book");
+
+        let mut filter = doc! {"_id": id, "deleted_at": null}; // Filtro para buscar por ID (fuera de la papelera)
+        add_version_filter(&mut filter, expected_version);
+        let mut set = doc! {
+            "titulo": &version.titulo,
+            "anio": version.anio,
+            "descripcion": &version.descripcion,
+            "numero_pagina": version.numero_pagina,
+        };
+        let mut unset = Document::new();
+
+        // El ISBN se quita si la versión no lo tenía
+        match &version.isbn {
+            Some(isbn) => {
+                set.insert("isbn", isbn);
+                set.insert("isbn10", isbn::to_isbn10(isbn).map_or(Bson::Null, Bson::String));
+            }
+            None => {
+                unset.insert("isbn", "");
+                unset.insert("isbn10", "");
+            }
+        }
+
+        // Las referencias se vuelven a resolver: el autor o la editorial pudieron cambiar de nombre o eliminarse
+        let author_request = ReferenceRequest::of::<Author>(version);
+        let publisher_request = ReferenceRequest::of::<Publisher>(version);
+
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (filter, mut set, unset, user) = (filter.clone(), set.clone(), unset.clone(), user.clone());
+            let (author_request, publisher_request) = (author_request.clone(), publisher_request.clone());
+            Box::pin(async move {
+                let current = repo.book_in(session, id).await?; // Versión anterior, para el historial
+
+                if let Some(author) = repo.reference_in::<Author>(session, &author_request).await? {
+                    set.extend(reference_fields(&author)?);
+                }
+                if let Some(publisher) = repo.reference_in::<Publisher>(session, &publisher_request).await? {
+                    set.extend(reference_fields(&publisher)?);
+                }
+
+                let mut update = doc! {"$set": set, "$inc": {"version": 1}};
+                if !unset.is_empty() {
+                    update.insert("$unset", unset);
+                }
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After) // Devuelve el documento actualizado
+                    .build();
+
+                let collection = repo.db.collection::<Book>(COLLECTION_NAME);
+                match collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await
+                    .map_err(map_duplicate_isbn)?
+                {
+                    Some(book) => {
+                        let entry = history::entry(id, HistoryAction::Revert, &user, Some(&current), &book);
+                        repo.append_history_in(session, entry).await?;
+                        Ok(book) // Devuelve el libro con los campos de la versión indicada
+                    }
+                    None => {
+                        // El libro cambió de versión
+                        check_version(&current, expected_version)?;
+                        Err(AppError::NotFoundError)
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    // Método para guardar o quitar los datos de la portada de un libro, en una transacción con su entrada del historial
+    async fn set_cover(
+        &self,
+        id: ObjectId,
+        cover: Option<Cover>,
+        expected_version: Option<i64>,
+        user: &str,
+    ) -> Result<(Book, Option<Cover>), AppError> {
+        let _timer = metrics::mongo_timer("set_cover");
+
+        let mut filter = doc! {"_id": id, "deleted_at": null}; // Solo libros activos
+        add_version_filter(&mut filter, expected_version);
+        let update = match cover {
+            Some(cover) => doc! {"$set": {"portada": bson::to_bson(&cover)?}, "$inc": {"version": 1}},
+            None => doc! {"$unset": {"portada": ""}, "$inc": {"version": 1}},
+        };
+
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (filter, update, user) = (filter.clone(), update.clone(), user.clone());
+            Box::pin(async move {
+                let current = repo.book_in(session, id).await?; // Versión anterior, para el historial
+
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After) // Devuelve el documento actualizado
+                    .build();
+                let collection = repo.db.collection::<Book>(COLLECTION_NAME);
+                let Some(book) = collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await?
+                else {
+                    // El libro cambió de versión
+                    check_version(&current, expected_version)?;
+                    return Err(AppError::NotFoundError);
+                };
+
+                let entry = history::entry(id, HistoryAction::Update, &user, Some(&current), &book);
+                repo.append_history_in(session, entry).await?;
+                Ok((book, current.portada))
+            })
+        })
+        .await
+    }
+
+    // Método para obtener el historial de un libro, del cambio más antiguo al más reciente
+    async fn get_history(&self, book_id: ObjectId) -> Result<Vec<BookHistoryEntry>, AppError> {
+        let _timer = metrics::mongo_timer("get_history");
+        let collection = self.db.collection::<BookHistoryEntry>(HISTORY_COLLECTION_NAME);
+
+        let find_options = FindOptions::builder().sort(doc! {"timestamp": 1, "_id": 1}).build();
+        let mut cursor = collection.find(doc! {"book_id": book_id}, find_options).await?;
+
+        let mut entries = Vec::new();
+        while let Some(entry) = cursor.try_next().await? {
+            entries.push(entry); // Agrega cada entrada encontrada al vector
+        }
+
+        Ok(entries) // Devuelve el historial del libro
+    }
+}
+
+#[async_trait]
+impl LoanRepository for MongoRepo {
+    // Método para prestar una copia de un libro a un socio. Es una transacción: el límite por socio se
+    // verifica y el préstamo se crea sin que otro préstamo del mismo socio se cuele entre ambos pasos
+    async fn checkout(&self, book_id: ObjectId, member_id: &str) -> Result<Loan, AppError> {
+        let _timer = metrics::mongo_timer("checkout");
+        self.run_transaction(|repo, session| {
+            let member_id = member_id.to_string();
+            Box::pin(async move { repo.checkout_in(session, book_id, &member_id).await })
+        })
+        .await
+    }
+
+    // Método para registrar la devolución de un préstamo; la devolución y el cambio de las copias son una transacción
+    async fn return_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let _timer = metrics::mongo_timer("return_loan");
+        self.run_transaction(|repo, session| Box::pin(repo.return_loan_in(session, id))).await
+    }
+
+    // Método para renovar un préstamo activo
+    async fn renew_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let _timer = metrics::mongo_timer("renew_loan");
+        let collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+        let holds = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+
+        let loan = self.get_loan(id).await?;
+        let waiting = holds
+            .count_documents(
+                doc! {"libro_id": loan.libro_id, "estado": bson::to_bson(&HoldStatus::Waiting)?},
+                None,
+            )
+            .await?
+            > 0;
+        loans::check_renewable(&loan, waiting, self.loan_policy.max_renewals, DateTime::now())?;
+
+        // El filtro por renovaciones evita que dos renovaciones simultáneas se sumen
+        let filter = doc! {
+            "_id": id,
+            "estado": bson::to_bson(&LoanStatus::Active)?,
+            "renovaciones": loan.renovaciones,
+        };
+        let update = doc! {
+            "$set": {"vence_en": self.loan_policy.due_date(loan.vence_en)},
+            "$inc": {"renovaciones": 1},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After) // Devuelve el préstamo renovado
             .build();
-        
-        let updated_book = collection
+
+        collection
             .find_one_and_update(filter, update, options)
             .await?
-            .ok_or(AppError::NotFoundError)?; // Error si no se encuentra el libro
-        
-        Ok(updated_book) // Devuelve el libro actualizado
+            .ok_or_else(|| AppError::Conflict("El préstamo cambió durante la renovación".to_string()))
     }
 
-    // Método para eliminar un libro por su ID
-    pub async fn delete_book(&self, id: ObjectId) -> Result<(), AppError> {
-        let collection = self.db.collection::<Book>(COLLECTION_NAME);
-        
-        let filter = doc! {"_id": id}; // Filtro para buscar por ID
-        let delete_result = collection.delete_one(filter, None).await?;
-        
-        if delete_result.deleted_count == 0 {
-            return Err(AppError::NotFoundError); // Error si no se elimina ningún libro
+    // Método para obtener un préstamo por su ID
+    async fn get_loan(&self, id: ObjectId) -> Result<Loan, AppError> {
+        let _timer = metrics::mongo_timer("get_loan");
+        let collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+        collection
+            .find_one(doc! {"_id": id}, None)
+            .await?
+            .ok_or(AppError::NotFoundError) // Error si no se encuentra el préstamo
+    }
+
+    // Método para obtener los préstamos activos de un socio
+    async fn get_member_loans(&self, member_id: &str) -> Result<Vec<Loan>, AppError> {
+        let _timer = metrics::mongo_timer("get_member_loans");
+        let collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+
+        let filter = doc! {"socio_id": member_id, "estado": bson::to_bson(&LoanStatus::Active)?};
+        let find_options = FindOptions::builder().sort(doc! {"vence_en": 1}).build();
+        let mut cursor = collection.find(filter, find_options).await?;
+
+        let mut member_loans = Vec::new();
+        while let Some(loan) = cursor.try_next().await? {
+            member_loans.push(loan); // Agrega cada préstamo encontrado al vector
+        }
+
+        Ok(member_loans) // Devuelve los préstamos del socio
+    }
+
+    // Método para obtener los préstamos vencidos
+    async fn get_overdue_loans(&self, now: DateTime) -> Result<Vec<Loan>, AppError> {
+        let _timer = metrics::mongo_timer("get_overdue_loans");
+        let collection = self.db.collection::<Loan>(LOANS_COLLECTION_NAME);
+
+        let filter = doc! {"estado": bson::to_bson(&LoanStatus::Active)?, "vence_en": {"$lt": now}};
+        let find_options = FindOptions::builder().sort(doc! {"vence_en": 1}).build();
+        let mut cursor = collection.find(filter, find_options).await?;
+
+        let mut overdue = Vec::new();
+        while let Some(loan) = cursor.try_next().await? {
+            overdue.push(loan); // Agrega cada préstamo vencido al vector
+        }
+
+        Ok(overdue) // Devuelve los préstamos vencidos
+    }
+
+    // Método para poner a un socio en la cola de reservas de un libro (en una transacción, como `checkout`)
+    async fn place_hold(&self, book_id: ObjectId, member_id: &str) -> Result<Hold, AppError> {
+        let _timer = metrics::mongo_timer("place_hold");
+        self.run_transaction(|repo, session| {
+            let member_id = member_id.to_string();
+            Box::pin(async move { repo.place_hold_in(session, book_id, &member_id).await })
+        })
+        .await
+    }
+
+    // Método para cancelar una reserva; la cancelación y el paso de la copia apartada son una transacción
+    async fn cancel_hold(&self, id: ObjectId) -> Result<Hold, AppError> {
+        let _timer = metrics::mongo_timer("cancel_hold");
+        self.run_transaction(|repo, session| Box::pin(repo.cancel_hold_in(session, id))).await
+    }
+
+    // Método para obtener la cola de reservas pendientes de un libro
+    async fn get_book_holds(&self, book_id: ObjectId) -> Result<Vec<Hold>, AppError> {
+        let _timer = metrics::mongo_timer("get_book_holds");
+        let collection = self.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+
+        let filter = doc! {"libro_id": book_id, "estado": pending_holds()?};
+        let find_options = FindOptions::builder().sort(doc! {"creado_en": 1, "_id": 1}).build();
+        let mut cursor = collection.find(filter, find_options).await?;
+
+        let mut queue = Vec::new();
+        while let Some(hold) = cursor.try_next().await? {
+            queue.push(hold); // Agrega cada reserva encontrada al vector
+        }
+
+        Ok(queue) // Devuelve la cola de reservas
+    }
+
+    // Método para vencer las reservas listas que el socio no retiró a tiempo. Es una transacción: cada reserva
+    // vence y su copia pasa a la siguiente de la cola sin que un préstamo o una cancelación se cuele entre ambos pasos
+    async fn expire_holds(&self, now: DateTime) -> Result<u64, AppError> {
+        let _timer = metrics::mongo_timer("expire_holds");
+        let cutoff = self.loan_policy.hold_cutoff(now);
+        self.run_transaction(|repo, session| {
+            Box::pin(async move {
+                let holds = repo.db.collection::<Hold>(HOLDS_COLLECTION_NAME);
+                let filter = doc! {"estado": bson::to_bson(&HoldStatus::Ready)?, "listo_en": {"$lt": cutoff}};
+                let mut cursor = holds.find_with_session(filter, None, session).await?;
+                let expired: Vec<Hold> = cursor.stream(session).try_collect().await?;
+
+                for hold in &expired {
+                    let update = doc! {"$set": {"estado": bson::to_bson(&HoldStatus::Expired)?}};
+                    holds.update_one_with_session(doc! {"_id": hold.id}, update, None, session).await?;
+                    // La copia apartada pasa a la siguiente reserva de la cola o vuelve a estar disponible
+                    if !repo.promote_next_hold_in(session, hold.libro_id).await? {
+                        repo.adjust_copies_in(session, hold.libro_id, 0, -1).await?;
+                    }
+                }
+                Ok(expired.len() as u64) // Devuelve cuántas reservas vencieron
+            })
+        })
+        .await
+    }
+}
+
+// Colección de MongoDB de cada tipo de registro que referencian los libros
+pub trait MongoReference: BookReference {
+    const COLLECTION: &'static str;
+    const METRIC_NAME: &'static str; // Nombre en las métricas de operaciones (`get_author`, `get_all_authors`, ...)
+}
+
+impl MongoReference for Author {
+    const COLLECTION: &'static str = AUTHORS_COLLECTION_NAME;
+    const METRIC_NAME: &'static str = "author";
+}
+
+impl MongoReference for Publisher {
+    const COLLECTION: &'static str = PUBLISHERS_COLLECTION_NAME;
+    const METRIC_NAME: &'static str = "publisher";
+}
+
+#[async_trait]
+impl<E: MongoReference> ReferenceRepository<E> for MongoRepo {
+    // Método para obtener todos los registros ordenados por nombre
+    async fn get_all(&self) -> Result<Vec<E>, AppError> {
+        let _timer = metrics::mongo_timer(&format!("get_all_{}s", E::METRIC_NAME));
+        let collection = self.db.collection::<E>(E::COLLECTION);
+
+        let find_options = FindOptions::builder().sort(doc! {"nombre": 1, "_id": 1}).build();
+        let mut cursor = collection.find(None, find_options).await?;
+
+        let mut entries = Vec::new();
+        while let Some(entry) = cursor.try_next().await? {
+            entries.push(entry); // Agrega cada registro encontrado al vector
+        }
+
+        Ok(entries) // Devuelve la lista de registros
+    }
+
+    // Método para obtener un registro por su ID
+    async fn get(&self, id: ObjectId) -> Result<E, AppError> {
+        let _timer = metrics::mongo_timer(&format!("get_{}", E::METRIC_NAME));
+        let collection = self.db.collection::<E>(E::COLLECTION);
+        collection
+            .find_one(doc! {"_id": id}, None)
+            .await?
+            .ok_or(AppError::NotFoundError) // Error si no se encuentra el registro
+    }
+
+    // Método para crear un nuevo registro en la colección (el índice único rechaza nombres repetidos)
+    async fn create(&self, dto: E::CreateDto) -> Result<E, AppError> {
+        let _timer = metrics::mongo_timer(&format!("create_{}", E::METRIC_NAME));
+        let collection = self.db.collection::<E>(E::COLLECTION);
+
+        let entry = E::from_dto(None, dto); // El ID será generado automáticamente por MongoDB
+        let insert_result = collection
+            .insert_one(&entry, None)
+            .await
+            .map_err(|err| map_duplicate_key(err, E::DUPLICATE))?;
+        let id = insert_result.inserted_id.as_object_id().ok_or(AppError::InternalError)?; // Obtiene el ID generado
+        ReferenceRepository::<E>::get(self, id).await // Devuelve el registro recién creado
+    }
+
+    // Método para actualizar un registro y copiar su nuevo nombre en los libros que lo referencian. Es una
+    // transacción: el registro y sus libros cambian juntos, y un libro que se crea mientras tanto choca con el
+    // registro y se reintenta con el nombre nuevo
+    async fn update(&self, id: ObjectId, dto: E::UpdateDto, user: &str) -> Result<E, AppError> {
+        let _timer = metrics::mongo_timer(&format!("update_{}", E::METRIC_NAME));
+        let user = user.to_string();
+        self.run_transaction(|repo, session| {
+            let (dto, user) = (dto.clone(), user.clone());
+            Box::pin(async move {
+                let collection = repo.db.collection::<E>(E::COLLECTION);
+                let current = collection
+                    .find_one_with_session(doc! {"_id": id}, None, session)
+                    .await?
+                    .ok_or(AppError::NotFoundError)?; // Error si no se encuentra el registro
+                let mut entry = current.clone();
+                entry.apply(dto);
+
+                // Se reemplaza el documento aunque no cambie nada, para que choque con las transacciones de los libros
+                collection
+                    .replace_one_with_session(doc! {"_id": id}, &entry, None, session)
+                    .await
+                    .map_err(|err| map_duplicate_key(err, E::DUPLICATE))?;
+
+                if entry.nombre() != current.nombre() {
+                    // Cada libro se actualiza por separado para registrar el cambio de nombre en su historial
+                    let mut filter = Document::new();
+                    filter.insert(format!("{}._id", E::REF_FIELD), id);
+                    let books = repo.db.collection::<Book>(COLLECTION_NAME);
+                    let mut cursor = books.find_with_session(filter, None, session).await?;
+                    let affected: Vec<Book> = cursor.stream(session).try_collect().await?;
+
+                    let mut set = Document::new();
+                    set.insert(E::FIELD, entry.nombre());
+                    set.insert(format!("{}.nombre", E::REF_FIELD), entry.nombre());
+                    let update = doc! {"$set": set, "$inc": {"version": 1}};
+                    let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+                    for before in affected {
+                        let book_id = before.id.ok_or(AppError::InternalError)?;
+                        let after = books
+                            .find_one_and_update_with_session(doc! {"_id": book_id}, update.clone(), options.clone(), session)
+                            .await?
+                            .ok_or(AppError::InternalError)?;
+                        let change = history::entry(book_id, HistoryAction::Update, &user, Some(&before), &after);
+                        repo.append_history_in(session, change).await?;
+                    }
+                }
+
+                Ok(entry) // Devuelve el registro actualizado
+            })
+        })
+        .await
+    }
+
+    // Método para eliminar un registro que ningún libro referencia. Es una transacción: si un libro lo referencia
+    // mientras tanto, choca con la eliminación y una de las dos se reintenta
+    async fn delete(&self, id: ObjectId) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer(&format!("delete_{}", E::METRIC_NAME));
+        self.run_transaction(|repo, session| {
+            Box::pin(async move {
+                let collection = repo.db.collection::<E>(E::COLLECTION);
+                let delete_result = collection.delete_one_with_session(doc! {"_id": id}, None, session).await?;
+                if delete_result.deleted_count == 0 {
+                    return Err(AppError::NotFoundError); // Error si no se encuentra el registro
+                }
+
+                // Se cuentan también los libros de la papelera, porque se pueden restaurar
+                let mut filter = Document::new();
+                filter.insert(format!("{}._id", E::REF_FIELD), id);
+                let books = repo.db.collection::<Book>(COLLECTION_NAME);
+                let references = books.count_documents_with_session(filter, None, session).await?;
+                if references > 0 {
+                    return Err(AppError::Conflict(E::in_use(references))); // La transacción se aborta
+                }
+
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+// Construye el filtro de MongoDB a partir de los filtros del listado
+fn filter_document(options: &BookListOptions) -> Document {
+    let filter = &options.filter;
+    let mut doc = doc! {"deleted_at": null}; // Los libros en la papelera no se listan
+
+    // Coincidencia exacta sin distinguir mayúsculas
+    if let Some(autor) = &filter.autor {
+        doc.insert("autor", exact_ignore_case(autor));
+    }
+
+    if let Some(editorial) = &filter.editorial {
+        doc.insert("editorial", exact_ignore_case(editorial));
+    }
+
+    if let Some(autor_id) = filter.autor_id {
+        doc.insert("autor_ref._id", autor_id);
+    }
+
+    if let Some(editorial_id) = filter.editorial_id {
+        doc.insert("editorial_ref._id", editorial_id);
+    }
+
+    if let Some(range) = range_document(filter.anio_min, filter.anio_max) {
+        doc.insert("anio", range);
+    }
+
+    if let Some(range) = range_document(filter.paginas_min, filter.paginas_max) {
+        doc.insert("numero_pagina", range);
+    }
+
+    doc
+}
+
+// Expresión regular que coincide con el texto completo sin distinguir mayúsculas
+fn exact_ignore_case(value: &str) -> Document {
+    doc! {"$regex": format!("^{}$", escape_regex(value)), "$options": "i"}
+}
+
+// Escapa los caracteres especiales de una expresión regular
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl HealthCheck for MongoRepo {
+    fn name(&self) -> &'static str {
+        "mongodb"
+    }
+
+    // Comando `ping` contra la base de datos configurada
+    async fn check(&self) -> Result<(), String> {
+        self.db
+            .run_command(doc! { "ping": 1 }, None)
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[async_trait]
+impl CoverRepository for MongoRepo {
+    // Método para guardar una imagen en GridFS y eliminar la versión anterior con la misma clave
+    async fn save_image(&self, key: &str, bytes: Vec<u8>) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer("save_image");
+        let bucket = self.covers_bucket();
+        let previous = self.cover_file_ids(key).await?;
+
+        bucket
+            .upload_from_futures_0_3_reader(key, futures::io::Cursor::new(bytes), None)
+            .await?;
+        for id in previous {
+            bucket.delete(id).await?;
+        }
+        Ok(())
+    }
+
+    // Método para leer la versión más reciente de una imagen desde GridFS
+    async fn load_image(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let _timer = metrics::mongo_timer("load_image");
+        let mut bytes = futures::io::Cursor::new(Vec::new());
+        match self
+            .covers_bucket()
+            .download_to_futures_0_3_writer_by_name(key, &mut bytes, None)
+            .await
+        {
+            Ok(()) => Ok(bytes.into_inner()),
+            // `GridFs` no se puede desestructurar como tupla porque está marcado `#[non_exhaustive]`
+            Err(err) if matches!(*err.kind, ErrorKind::GridFs { 0: GridFsErrorKind::FileNotFound { .. }, .. }) => {
+                Err(AppError::NotFoundError)
+            }
+            Err(err) => Err(err.into()),
         }
-        
-        Ok(()) // Devuelve éxito si se elimina el libro
     }
-}
\ No newline at end of file
+
+    // Método para eliminar todas las versiones de una imagen de GridFS
+    async fn delete_image(&self, key: &str) -> Result<(), AppError> {
+        let _timer = metrics::mongo_timer("delete_image");
+        let bucket = self.covers_bucket();
+        for id in self.cover_file_ids(key).await? {
+            bucket.delete(id).await?;
+        }
+        Ok(())
+    }
+}
+
+// Índices del repositorio: colección, definición y si garantiza unicidad
+fn index_models() -> Result<Vec<(&'static str, IndexModel, bool)>, AppError> {
+    let index = |keys: Document, options: IndexOptions| IndexModel::builder().keys(keys).options(options).build();
+    let named = |name: &str| IndexOptions::builder().name(name.to_string()).build();
+
+    // Índice de texto ponderado sobre título, autor y descripción
+    let mut keys = Document::new();
+    let mut weights = Document::new();
+    for (field, weight) in search::SEARCH_WEIGHTS {
+        keys.insert(field, "text");
+        weights.insert(field, weight);
+    }
+    let text_options = IndexOptions::builder()
+        .name(TEXT_INDEX_NAME.to_string())
+        .weights(weights)
+        .default_language("spanish".to_string())
+        .build();
+
+    // Índice único de ISBN que solo incluye libros con ISBN. Los activos no tienen `deleted_at` (se indexa
+    // como null) y chocan entre sí; los de la papelera tienen cada uno su fecha y no reservan el ISBN
+    let isbn_options = IndexOptions::builder()
+        .name(ISBN_INDEX_NAME.to_string())
+        .unique(true)
+        .partial_filter_expression(doc! {"isbn": {"$type": "string"}})
+        .build();
+
+    // Índices para listar los libros de un autor o editorial y comprobar si todavía se usan
+    let reference_options = |name: &str| IndexOptions::builder().name(name.to_string()).sparse(true).build();
+
+    // Índices únicos de nombre que comparan sin distinguir mayúsculas ni tildes
+    let unique_name = |name: &str| {
+        IndexOptions::builder()
+            .name(name.to_string())
+            .unique(true)
+            .collation(name_collation())
+            .build()
+    };
+
+    // Índices únicos parciales: solo cuentan los préstamos activos y las reservas pendientes
+    let unique_where = |name: &str, filter: Document| {
+        IndexOptions::builder()
+            .name(name.to_string())
+            .unique(true)
+            .partial_filter_expression(filter)
+            .build()
+    };
+    let active_loans = doc! {"estado": bson::to_bson(&LoanStatus::Active)?};
+    let pending = doc! {"estado": pending_holds()?}; // `$in` en el filtro parcial requiere MongoDB 6.0
+
+    Ok(vec![
+        (COLLECTION_NAME, index(keys, text_options), false),
+        (COLLECTION_NAME, index(doc! {"isbn": 1, "deleted_at": 1}, isbn_options), true),
+        (COLLECTION_NAME, index(doc! {"autor_ref._id": 1}, reference_options(AUTHOR_REF_INDEX_NAME)), false),
+        (COLLECTION_NAME, index(doc! {"editorial_ref._id": 1}, reference_options(PUBLISHER_REF_INDEX_NAME)), false),
+        (AUTHORS_COLLECTION_NAME, index(doc! {"nombre": 1}, unique_name(AUTHORS_NAME_INDEX_NAME)), true),
+        (PUBLISHERS_COLLECTION_NAME, index(doc! {"nombre": 1}, unique_name(PUBLISHERS_NAME_INDEX_NAME)), true),
+        // Historial de un libro en orden
+        (HISTORY_COLLECTION_NAME, index(doc! {"book_id": 1, "timestamp": 1}, named(HISTORY_INDEX_NAME)), false),
+        // Préstamos por socio (límites) y por vencimiento (listado de vencidos)
+        (LOANS_COLLECTION_NAME, index(doc! {"socio_id": 1, "estado": 1}, named(LOANS_MEMBER_INDEX_NAME)), false),
+        (LOANS_COLLECTION_NAME, index(doc! {"estado": 1, "vence_en": 1}, named(LOANS_DUE_INDEX_NAME)), false),
+        // Cola de reservas de cada libro
+        (
+            HOLDS_COLLECTION_NAME,
+            index(doc! {"libro_id": 1, "estado": 1, "creado_en": 1}, named(HOLDS_QUEUE_INDEX_NAME)),
+            false,
+        ),
+        (
+            LOANS_COLLECTION_NAME,
+            index(doc! {"socio_id": 1, "libro_id": 1}, unique_where(LOANS_ACTIVE_INDEX_NAME, active_loans)),
+            true,
+        ),
+        (
+            HOLDS_COLLECTION_NAME,
+            index(doc! {"socio_id": 1, "libro_id": 1}, unique_where(HOLDS_PENDING_INDEX_NAME, pending)),
+            true,
+        ),
+    ])
+}
+
+// Indica si el error se debe a que no existe el índice de texto
+fn is_missing_text_index(err: &mongodb::error::Error) -> bool {
+    matches!(&*err.kind, ErrorKind::Command(command) if command.code == INDEX_NOT_FOUND_CODE)
+}
+
+// Indica si el índice (o su colección) no existe al intentar eliminarlo
+fn is_missing_index(err: &mongodb::error::Error) -> bool {
+    matches!(&*err.kind, ErrorKind::Command(command)
+        if command.code == INDEX_NOT_FOUND_CODE || command.code == NAMESPACE_NOT_FOUND_CODE)
+}
+
+// Convierte la violación del índice único de ISBN en un error de conflicto
+fn map_duplicate_isbn(err: mongodb::error::Error) -> AppError {
+    map_duplicate_key(err, "Ya existe un libro con ese ISBN")
+}
+
+// Convierte la violación de un índice único en un conflicto con el mensaje indicado
+fn map_duplicate_key(err: mongodb::error::Error, message: &str) -> AppError {
+    let code = match &*err.kind {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => Some(write_error.code),
+        ErrorKind::Command(command) => Some(command.code),
+        _ => None,
+    };
+    if code == Some(DUPLICATE_KEY_CODE) {
+        AppError::Conflict(message.to_string())
+    } else {
+        err.into()
+    }
+}
+
+// Intercalación de los nombres de autores y editoriales: sin distinguir mayúsculas ni tildes
+fn name_collation() -> Collation {
+    Collation::builder()
+        .locale(NAME_COLLATION_LOCALE)
+        .strength(CollationStrength::Primary)
+        .build()
+}
+
+// Campos del libro con el nombre y el resumen del registro que referencia
+fn reference_fields<E: BookReference>(entry: &E) -> Result<Document, AppError> {
+    let id = entry.id().ok_or(AppError::InternalError)?;
+    let mut fields = Document::new();
+    fields.insert(E::FIELD, entry.nombre());
+    fields.insert(E::REF_FIELD, doc! {"_id": id, "nombre": entry.nombre()});
+    Ok(fields)
+}
+
+// Confirma la transacción si `result` es correcto o la aborta si no
+async fn finish_transaction<T>(session: &mut ClientSession, result: Result<T, AppError>) -> Result<T, AppError> {
+    let value = match result {
+        Ok(value) => value,
+        Err(err) => {
+            // MongoDB puede haberla abortado ya; el error original es el que importa
+            let _ = session.abort_transaction().await;
+            return Err(err);
+        }
+    };
+    loop {
+        match session.commit_transaction().await {
+            Ok(()) => return Ok(value),
+            // Confirmar otra vez es seguro: MongoDB no aplica la transacción dos veces
+            Err(err) if err.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+// Indica si la transacción falló por un conflicto pasajero y se puede repetir completa
+fn is_transient(err: &AppError) -> bool {
+    matches!(err, AppError::MongoError(err) if err.contains_label(TRANSIENT_TRANSACTION_ERROR))
+}
+
+// Construye un rango `$gte`/`$lte` si se indicó algún límite
+fn range_document(min: Option<i32>, max: Option<i32>) -> Option<Document> {
+    let mut range = Document::new();
+    if let Some(min) = min {
+        range.insert("$gte", min);
+    }
+    if let Some(max) = max {
+        range.insert("$lte", max);
+    }
+    (!range.is_empty()).then_some(range)
+}
+
+// Condiciona la escritura a la versión del libro (los documentos sin el campo están en la versión 0)
+fn add_version_filter(filter: &mut Document, expected_version: Option<i64>) {
+    match expected_version {
+        Some(0) => filter.insert("version", doc! {"$in": [0, null]}),
+        Some(version) => filter.insert("version", version),
+        None => return,
+    };
+}
+
+// Expresión de agregación con las copias prestadas más las apartadas de un libro
+fn copies_out_expression() -> Document {
+    doc! {"$add": [{"$ifNull": ["$prestados", 0]}, {"$ifNull": ["$apartados", 0]}]}
+}
+
+// Condición de las reservas que siguen en la cola (esperando o con una copia apartada)
+fn pending_holds() -> Result<Document, AppError> {
+    Ok(doc! {"$in": [bson::to_bson(&HoldStatus::Waiting)?, bson::to_bson(&HoldStatus::Ready)?]})
+}
+
+// Construye el documento de orden; `_id` desempata para que la paginación sea estable
+fn sort_document(sort: &[SortField]) -> Document {
+    let mut doc = Document::new();
+    for field in sort {
+        doc.insert(field.field.clone(), Bson::Int32(if field.descending { -1 } else { 1 }));
+    }
+    doc.insert("_id", 1);
+    doc
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+
+use crate::{error::AppError, model::BookReference};
+
+// Contrato común para el almacenamiento de autores y editoriales
+#[async_trait]
+pub trait ReferenceRepository<E: BookReference>: Send + Sync {
+    // Obtiene todos los registros ordenados por nombre
+    async fn get_all(&self) -> Result<Vec<E>, AppError>;
+
+    // Obtiene un registro por su ID
+    async fn get(&self, id: ObjectId) -> Result<E, AppError>;
+
+    // Crea un registro; falla con un conflicto si ya hay otro con el mismo nombre (sin distinguir mayúsculas ni tildes)
+    async fn create(&self, dto: E::CreateDto) -> Result<E, AppError>;
+
+    // Actualiza el registro; si cambia el nombre, también se actualiza en sus libros, cada uno con una entrada
+    // en su historial a nombre de `user`
+    async fn update(&self, id: ObjectId, dto: E::UpdateDto, user: &str) -> Result<E, AppError>;
+
+    // Elimina el registro; falla con un conflicto si algún libro todavía lo referencia
+    async fn delete(&self, id: ObjectId) -> Result<(), AppError>;
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use crate::model::{Book, SearchHit};
+
+// Pesos de cada campo en la búsqueda (los mismos que usa el índice de texto de MongoDB).
+pub const SEARCH_WEIGHTS: [(&str, i32); 3] = [("titulo", 10), ("autor", 5), ("descripcion", 1)];
+
+const SNIPPET_WORDS_BEFORE: usize = 8; // Palabras de contexto antes de la primera coincidencia.
+const SNIPPET_WORDS: usize = 30; // Cantidad máxima de palabras en un fragmento resaltado.
+const MIN_PREFIX_LEN: usize = 3; // Longitud mínima de un término para coincidir como prefijo.
+
+// Divide la consulta en términos normalizados (minúsculas y sin tildes), sin repetidos.
+pub fn terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for (start, end) in word_ranges(query) {
+        let term = normalize(&query[start..end]);
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+// Ordena los libros por relevancia y devuelve como máximo `limit` resultados.
+pub fn rank(books: impl IntoIterator<Item = Book>, terms: &[String], limit: u64) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = books
+        .into_iter()
+        .filter_map(|book| {
+            let score = score(&book, terms);
+            (score > 0.0).then(|| SearchHit {
+                highlights: highlights(&book, terms),
+                libro: book,
+                score,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit as usize);
+    hits
+}
+
+// Calcula la relevancia de un libro: cada coincidencia suma el peso de su campo.
+pub fn score(book: &Book, terms: &[String]) -> f64 {
+    SEARCH_WEIGHTS
+        .iter()
+        .map(|(field, weight)| {
+            let text = field_text(book, field);
+            let matches: f64 = word_ranges(text)
+                .into_iter()
+                .map(|(start, end)| match_weight(&normalize(&text[start..end]), terms))
+                .sum();
+            matches * f64::from(*weight)
+        })
+        .sum()
+}
+
+// Genera un fragmento con las coincidencias marcadas con `<mark>` por cada campo que coincide.
+pub fn highlights(book: &Book, terms: &[String]) -> BTreeMap<String, String> {
+    SEARCH_WEIGHTS
+        .iter()
+        .filter_map(|(field, _)| {
+            snippet(field_text(book, field), terms).map(|snippet| (field.to_string(), snippet))
+        })
+        .collect()
+}
+
+// Expresión regular que encuentra el término normalizado aunque el texto tenga tildes.
+pub fn accent_insensitive_pattern(term: &str) -> String {
+    term.chars()
+        .map(|c| match c {
+            'a' => "[aáàäâ]".to_string(),
+            'e' => "[eéèëê]".to_string(),
+            'i' => "[iíìïî]".to_string(),
+            'o' => "[oóòöô]".to_string(),
+            'u' => "[uúùüû]".to_string(),
+            'n' => "[nñ]".to_string(),
+            c if c.is_alphanumeric() => c.to_string(),
+            c => format!("\\{}", c),
+        })
+        .collect()
+}
+
+// Texto del campo indicado del libro.
+fn field_text<'a>(book: &'a Book, field: &str) -> &'a str {
+    match field {
+        "titulo" => &book.titulo,
+        "autor" => &book.autor,
+        _ => &book.descripcion,
+    }
+}
+
+// Una palabra idéntica a un término vale 1; si el término es prefijo de la palabra vale 0.5.
+fn match_weight(word: &str, terms: &[String]) -> f64 {
+    terms
+        .iter()
+        .map(|term| {
+            if word == term {
+                1.0
+            } else if term.chars().count() >= MIN_PREFIX_LEN && word.starts_with(term.as_str()) {
+                0.5
+            } else {
+                0.0
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+// Arma el fragmento alrededor de la primera coincidencia, escapando el HTML del texto original.
+fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let words = word_ranges(text);
+    let matched: Vec<bool> = words
+        .iter()
+        .map(|&(start, end)| match_weight(&normalize(&text[start..end]), terms) > 0.0)
+        .collect();
+    let first = matched.iter().position(|&m| m)?;
+
+    let from = first.saturating_sub(SNIPPET_WORDS_BEFORE);
+    let to = (from + SNIPPET_WORDS).min(words.len());
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = words[from].0;
+    for index in from..to {
+        let (start, end) = words[index];
+        snippet.push_str(&escape_html(&text[cursor..start]));
+        if matched[index] {
+            snippet.push_str("<mark>");
+            snippet.push_str(&escape_html(&text[start..end]));
+            snippet.push_str("</mark>");
+        } else {
+            snippet.push_str(&escape_html(&text[start..end]));
+        }
+        cursor = end;
+    }
+    if to < words.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+// Posiciones (en bytes) de cada palabra alfanumérica del texto.
+fn word_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(s)) => {
+                ranges.push((s, index));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push((s, text.len()));
+    }
+    ranges
+}
+
+// Pasa a minúsculas y quita las tildes más comunes del español.
+fn normalize(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' | 'â' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ñ' => 'n',
+            other => other,
+        })
+        .collect()
+}
+
+// Escapa los caracteres especiales de HTML para que el fragmento sea seguro de mostrar.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,175 @@
+use std::{
+    error::Error as StdError,
+    io,
+    pin::Pin,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Server, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::Bytes,
+    Error,
+};
+
+// Cada cuánto se revisa si ya terminaron las solicitudes en curso durante el apagado.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Solicitudes en curso y cómo terminaron, para el resumen del apagado.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static ABORTED: AtomicU64 = AtomicU64::new(0);
+
+// Cuenta una solicitud mientras se atiende; si se descarta sin terminar
+// (se venció el plazo del apagado) se cuenta como cancelada.
+struct InFlightGuard {
+    finished: bool,
+}
+
+impl InFlightGuard {
+    fn start() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { finished: false }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        let outcome = if self.finished { &COMPLETED } else { &ABORTED };
+        outcome.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// Cuerpo de la respuesta que mantiene la solicitud en curso hasta terminar de enviarse
+// (las exportaciones se envían por partes).
+struct TrackedBody {
+    body: BoxBody,
+    _guard: InFlightGuard,
+}
+
+impl MessageBody for TrackedBody {
+    type Error = Box<dyn StdError>;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Pin::new(&mut self.get_mut().body).poll_next(cx)
+    }
+}
+
+// Middleware que lleva la cuenta de las solicitudes en curso
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut guard = InFlightGuard::start();
+    let response = next.call(req).await;
+    guard.finished = true; // Ya hay respuesta (o error); si se corta el envío del cuerpo no se cuenta como cancelada.
+    Ok(response?.map_body(|_, body| TrackedBody { body: body.boxed(), _guard: guard }))
+}
+
+// Espera SIGTERM (`docker stop`, redespliegues) o Ctrl+C
+async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("No se pudo escuchar SIGTERM");
+        tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+// Resumen del apagado del servidor HTTP
+pub struct ShutdownSummary {
+    signal: &'static str,
+    in_flight: usize, // Solicitudes en curso al recibir la señal.
+    completed: u64, // Solicitudes que terminaron durante el apagado.
+    aborted: u64, // Solicitudes canceladas al vencerse el plazo del apagado.
+    started: Instant,
+}
+
+impl ShutdownSummary {
+    // Registra el resumen; se llama después de cerrar las demás conexiones para incluir su duración
+    pub fn log(&self) {
+        log::info!(
+            "Apagado completo (señal: {}, solicitudes en curso: {}, completadas: {}, canceladas: {}, duración: {} ms)",
+            self.signal,
+            self.in_flight,
+            self.completed,
+            self.aborted,
+            self.started.elapsed().as_millis()
+        );
+    }
+}
+
+// Atiende solicitudes hasta recibir una señal; luego deja de aceptar conexiones y espera a las
+// solicitudes en curso durante `timeout` como máximo (después se cancelan).
+// `on_stop` avisa a los demás servidores (gRPC) para que se detengan al mismo tiempo.
+pub async fn serve_until_signal(
+    mut server: Server,
+    timeout: Duration,
+    on_stop: impl FnOnce(),
+) -> io::Result<ShutdownSummary> {
+    let handle = server.handle();
+    let signal = tokio::select! {
+        result = &mut server => {
+            on_stop();
+            result?;
+            log::warn!("El servidor HTTP se detuvo sin recibir una señal");
+            let started = Instant::now();
+            return Ok(ShutdownSummary { signal: "ninguna", in_flight: 0, completed: 0, aborted: 0, started });
+        }
+        signal = wait_for_signal() => signal,
+    };
+
+    let in_flight = IN_FLIGHT.load(Ordering::SeqCst);
+    log::info!(
+        "Señal {} recibida: se dejan de aceptar conexiones y se esperan {} solicitudes en curso",
+        signal,
+        in_flight
+    );
+    on_stop();
+    let (completed, aborted) = (COMPLETED.load(Ordering::SeqCst), ABORTED.load(Ordering::SeqCst));
+    let started = Instant::now();
+
+    // No se usa `stop(true)`: en actix-server un worker puede terminar antes de recibir la orden
+    // de detenerse (cuando el hilo que acepta conexiones se cierra primero) y descartar sus
+    // solicitudes en curso. Por eso se pausa la aceptación y la espera se hace aquí.
+    let drain = async {
+        handle.pause().await;
+        let drained = tokio::time::timeout(timeout, async {
+            while IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+                tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            log::warn!("Se venció el plazo del apagado; se cancelan las solicitudes en curso");
+        }
+        handle.stop(false).await;
+    };
+    let ((), result) = tokio::join!(drain, server); // El servidor debe seguir atendiendo mientras se detiene.
+    result?;
+
+    Ok(ShutdownSummary {
+        signal,
+        in_flight,
+        completed: COMPLETED.load(Ordering::SeqCst) - completed,
+        aborted: ABORTED.load(Ordering::SeqCst) - aborted,
+        started,
+    })
+}
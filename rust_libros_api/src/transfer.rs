@@ -0,0 +1,358 @@
+use csv_core::{ReadRecordResult, Reader};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    error::{AppError, FieldError},
+    model::{Book, CreateBookDto},
+};
+
+// Columnas del CSV exportado (la importación ignora `_id` e `isbn10`).
+pub const CSV_COLUMNS: [&str; 12] = [
+    "_id",
+    "titulo",
+    "autor",
+    "autor_id",
+    "editorial",
+    "editorial_id",
+    "anio",
+    "descripcion",
+    "numero_pagina",
+    "isbn",
+    "isbn10",
+    "copias",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    Csv, // Valores separados por comas con fila de encabezados.
+    Ndjson, // Un objeto JSON por línea.
+}
+
+impl TransferFormat {
+    // Determina el formato a partir del `Content-Type` de la carga.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next().unwrap_or("").trim() {
+            "text/csv" => Some(TransferFormat::Csv),
+            "application/x-ndjson" | "application/jsonl" | "application/x-jsonlines" => {
+                Some(TransferFormat::Ndjson)
+            }
+            _ => None,
+        }
+    }
+
+    // `Content-Type` con el que se sirve la exportación.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "text/csv; charset=utf-8",
+            TransferFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    // Extensión del archivo descargado.
+    pub fn extension(self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "csv",
+            TransferFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+// Tamaño máximo de una fila (una línea NDJSON o un registro CSV) y campos por registro CSV,
+// para que una carga sin saltos de línea no acumule todo en memoria.
+pub const MAX_ROW_BYTES: usize = 64 * 1024;
+const MAX_CSV_FIELDS: usize = 256;
+
+// Resultado de interpretar una fila: el libro a crear o los errores de sus campos.
+pub type ParsedRow = (usize, Result<CreateBookDto, Vec<FieldError>>);
+
+// Decodifica filas a medida que llegan los fragmentos de la carga, sin acumularla completa.
+pub struct RowDecoder {
+    format: TransferFormat,
+    row: usize, // Número de la última fila de datos leída (empieza en 1).
+    pending: Vec<u8>, // NDJSON: bytes de la línea que todavía no terminó.
+    csv: Reader, // CSV: analizador incremental.
+    output: Vec<u8>, // CSV: bytes de los campos del registro actual.
+    output_len: usize,
+    ends: Vec<usize>, // CSV: posiciones de fin de cada campo del registro actual.
+    ends_len: usize,
+    headers: Option<Vec<String>>, // CSV: encabezados leídos de la primera fila.
+}
+
+impl RowDecoder {
+    pub fn new(format: TransferFormat) -> Self {
+        RowDecoder {
+            format,
+            row: 0,
+            pending: Vec::new(),
+            csv: Reader::new(),
+            output: vec![0; 1024],
+            output_len: 0,
+            ends: vec![0; 16],
+            ends_len: 0,
+            headers: None,
+        }
+    }
+
+    // Procesa un fragmento de la carga y devuelve las filas que quedaron completas.
+    // Si la carga no se puede leer, el error es el último elemento: las filas anteriores siguen siendo válidas.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Result<ParsedRow, AppError>> {
+        let mut rows = Vec::new();
+        let result = match self.format {
+            TransferFormat::Ndjson => {
+                self.pending.extend_from_slice(chunk);
+                self.ndjson_rows(&mut rows)
+            }
+            TransferFormat::Csv => self.csv_rows(chunk, &mut rows),
+        };
+        rows.into_iter().map(Ok).chain(result.err().map(Err)).collect()
+    }
+
+    // Procesa lo que quede al terminar la carga (una última fila sin salto de línea).
+    pub fn finish(&mut self) -> Vec<Result<ParsedRow, AppError>> {
+        let mut rows = Vec::new();
+        let result = match self.format {
+            TransferFormat::Ndjson => {
+                let line = std::mem::take(&mut self.pending);
+                rows.extend(self.ndjson_row(&line));
+                Ok(())
+            }
+            TransferFormat::Csv => {
+                // Una entrada vacía indica fin de archivo
+                self.csv_rows(&[], &mut rows).and_then(|_| match self.headers {
+                    Some(_) => Ok(()),
+                    None => Err(AppError::InvalidPayloadError(
+                        "El CSV no contiene una fila de encabezados".to_string(),
+                    )),
+                })
+            }
+        };
+        rows.into_iter().map(Ok).chain(result.err().map(Err)).collect()
+    }
+
+    // Interpreta las líneas NDJSON completas y deja pendiente la que sigue abierta.
+    fn ndjson_rows(&mut self, rows: &mut Vec<ParsedRow>) -> Result<(), AppError> {
+        while let Some(position) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=position).collect();
+            self.check_row_size(line.len())?;
+            rows.extend(self.ndjson_row(&line));
+        }
+        self.check_row_size(self.pending.len()) // La línea que sigue abierta
+    }
+
+    // Interpreta una línea NDJSON; las líneas vacías se ignoran.
+    fn ndjson_row(&mut self, line: &[u8]) -> Option<ParsedRow> {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+        self.row += 1;
+        let result = serde_json::from_slice::<CreateBookDto>(line).map_err(|err| {
+            vec![FieldError {
+                field: "_".to_string(),
+                code: "json".to_string(),
+                message: format!("JSON inválido: {}", err),
+            }]
+        });
+        Some((self.row, result.and_then(validate)))
+    }
+
+    // Alimenta el analizador CSV y agrega los registros completos a `rows`.
+    fn csv_rows(&mut self, mut input: &[u8], rows: &mut Vec<ParsedRow>) -> Result<(), AppError> {
+        loop {
+            let (result, read, written, ended) = self.csv.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[read..];
+            self.output_len += written;
+            self.ends_len += ended;
+
+            match result {
+                ReadRecordResult::InputEmpty | ReadRecordResult::End => return Ok(()),
+                ReadRecordResult::OutputFull => {
+                    self.check_row_size(self.output.len() + 1)?;
+                    self.output.resize((self.output.len() * 2).min(MAX_ROW_BYTES), 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    if self.ends.len() >= MAX_CSV_FIELDS {
+                        return Err(AppError::PayloadTooLarge(format!(
+                            "La fila {} tiene más de {} campos",
+                            self.row + 1,
+                            MAX_CSV_FIELDS
+                        )));
+                    }
+                    self.ends.resize((self.ends.len() * 2).min(MAX_CSV_FIELDS), 0);
+                }
+                ReadRecordResult::Record => {
+                    let fields = self.take_record()?;
+                    match &self.headers {
+                        None => {
+                            // Las hojas de cálculo suelen anteponer un BOM al primer encabezado
+                            let headers = fields
+                                .iter()
+                                .map(|field| field.trim_start_matches('\u{feff}').trim().to_string())
+                                .collect();
+                            self.headers = Some(headers);
+                        }
+                        Some(headers) => {
+                            self.row += 1;
+                            rows.push((self.row, csv_dto(headers, fields).and_then(validate)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Rechaza la carga si la fila siguiente supera el tamaño máximo.
+    fn check_row_size(&self, bytes: usize) -> Result<(), AppError> {
+        if bytes > MAX_ROW_BYTES {
+            return Err(AppError::PayloadTooLarge(format!(
+                "La fila {} supera los {} KB",
+                self.row + 1,
+                MAX_ROW_BYTES / 1024
+            )));
+        }
+        Ok(())
+    }
+
+    // Extrae los campos del registro CSV actual y reinicia los búferes.
+    fn take_record(&mut self) -> Result<Vec<String>, AppError> {
+        let mut fields = Vec::with_capacity(self.ends_len);
+        let mut start = 0;
+        for &end in &self.ends[..self.ends_len] {
+            let field = std::str::from_utf8(&self.output[start..end]).map_err(|_| {
+                AppError::InvalidPayloadError("El CSV debe estar codificado en UTF-8".to_string())
+            })?;
+            fields.push(field.to_string());
+            start = end;
+        }
+        self.output_len = 0;
+        self.ends_len = 0;
+        Ok(fields)
+    }
+}
+
+// Construye el DTO a partir de una fila CSV usando los encabezados como nombres de campo.
+fn csv_dto(headers: &[String], fields: Vec<String>) -> Result<CreateBookDto, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let value = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .and_then(|index| fields.get(index))
+            .map(|value| value.trim().to_string())
+    };
+    let text = |name: &str, errors: &mut Vec<FieldError>| {
+        value(name).unwrap_or_else(|| {
+            errors.push(field_error(name, "required", "La columna es obligatoria"));
+            String::new()
+        })
+    };
+    let number = |name: &str, raw: String, errors: &mut Vec<FieldError>| {
+        raw.parse::<i32>().unwrap_or_else(|_| {
+            errors.push(field_error(name, "number", "Debe ser un número entero"));
+            0
+        })
+    };
+
+    // Con el ID de un autor o editorial registrado, la columna de texto deja de ser obligatoria
+    let reference = |name: &str| value(name).filter(|id| !id.is_empty());
+    let autor_id = reference("autor_id");
+    let editorial_id = reference("editorial_id");
+
+    let titulo = text("titulo", &mut errors);
+    let autor = match autor_id {
+        Some(_) => value("autor").unwrap_or_default(),
+        None => text("autor", &mut errors),
+    };
+    let editorial = match editorial_id {
+        Some(_) => value("editorial").unwrap_or_default(),
+        None => text("editorial", &mut errors),
+    };
+    let anio = text("anio", &mut errors);
+    let descripcion = text("descripcion", &mut errors);
+    let numero_pagina = text("numero_pagina", &mut errors);
+
+    let dto = CreateBookDto {
+        anio: number("anio", anio, &mut errors),
+        numero_pagina: number("numero_pagina", numero_pagina, &mut errors),
+        titulo,
+        autor,
+        autor_id,
+        editorial,
+        editorial_id,
+        descripcion,
+        isbn: value("isbn").filter(|isbn| !isbn.is_empty()),
+        copias: value("copias")
+            .filter(|copias| !copias.is_empty())
+            .map(|copias| number("copias", copias, &mut errors)),
+    };
+
+    if errors.is_empty() {
+        Ok(dto)
+    } else {
+        Err(errors)
+    }
+}
+
+// Aplica las reglas de validación del DTO y devuelve los errores por campo.
+fn validate(dto: CreateBookDto) -> Result<CreateBookDto, Vec<FieldError>> {
+    match dto.validate() {
+        Ok(()) => Ok(dto),
+        Err(errors) => match AppError::from(errors) {
+            AppError::ValidationError(errors) => Err(errors),
+            _ => Err(Vec::new()),
+        },
+    }
+}
+
+fn field_error(field: &str, code: &str, message: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        code: code.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// Fila de encabezados del CSV exportado.
+pub fn csv_header() -> Result<Vec<u8>, AppError> {
+    write_csv_record(CSV_COLUMNS.iter().map(|column| column.to_string()))
+}
+
+// Serializa un libro en el formato indicado, terminando en salto de línea.
+pub fn encode_book(format: TransferFormat, book: &Book) -> Result<Vec<u8>, AppError> {
+    match format {
+        TransferFormat::Ndjson => {
+            let mut line = serde_json::to_vec(book).map_err(|_| AppError::InternalError)?;
+            line.push(b'\n');
+            Ok(line)
+        }
+        TransferFormat::Csv => write_csv_record([
+            book.id.map(|id| id.to_hex()).unwrap_or_default(),
+            book.titulo.clone(),
+            book.autor.clone(),
+            book.autor_ref.as_ref().map(|author| author.id.to_hex()).unwrap_or_default(),
+            book.editorial.clone(),
+            book.editorial_ref.as_ref().map(|publisher| publisher.id.to_hex()).unwrap_or_default(),
+            book.anio.to_string(),
+            book.descripcion.clone(),
+            book.numero_pagina.to_string(),
+            book.isbn.clone().unwrap_or_default(),
+            book.isbn10.clone().unwrap_or_default(),
+            book.copias.to_string(),
+        ]),
+    }
+}
+
+// Escribe un registro CSV con el escape de comillas y separadores que corresponda.
+fn write_csv_record(fields: impl IntoIterator<Item = String>) -> Result<Vec<u8>, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .map_err(|_| AppError::InternalError)?;
+    writer.into_inner().map_err(|_| AppError::InternalError)
+}
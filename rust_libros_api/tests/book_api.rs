@@ -0,0 +1,719 @@
+// Pruebas de integración de los endpoints de libros, con el repositorio en memoria (sin MongoDB).
+
+use std::sync::Arc;
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH},
+        StatusCode,
+    },
+    middleware::from_fn,
+    test, web, App, Error,
+};
+use mongodb::bson::DateTime;
+use rust_mongodb_crud::{
+    api,
+    error::AppError,
+    history::USER_HEADER,
+    model::HistoryAction,
+    repository::{book_repository::BookRepository, memory_repo::MemoryRepo, Repositories},
+    telemetry,
+};
+use serde_json::{json, Value};
+
+// ID con formato válido que no corresponde a ningún libro
+const MISSING_ID: &str = "65f1a2b3c4d5e6f708192a3b";
+
+// Aplicación con las mismas rutas que el servidor, sobre un repositorio en memoria vacío
+async fn app() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let repos = Repositories::new(Arc::new(MemoryRepo::new()));
+    let book_data: web::Data<dyn BookRepository> = web::Data::from(repos.books);
+    test::init_service(
+        App::new()
+            .wrap(from_fn(telemetry::request_context)) // Las respuestas de error incluyen el `request_id`.
+            .app_data(book_data)
+            .app_data(web::Data::from(repos.authors))
+            .app_data(web::Data::from(repos.publishers))
+            .app_data(web::Data::from(repos.loans))
+            .service(web::scope("/api").configure(api::routes)),
+    )
+    .await
+}
+
+// Datos mínimos de un libro válido
+fn book(titulo: &str) -> Value {
+    json!({
+        "titulo": titulo,
+        "autor": "Gabriel García Márquez",
+        "editorial": "Sudamericana",
+        "anio": 1967,
+        "descripcion": "Novela sobre la familia Buendía",
+        "numero_pagina": 471,
+    })
+}
+
+// Crea un libro y devuelve su cuerpo JSON
+async fn create(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    body: Value,
+) -> Value {
+    let req = test::TestRequest::post().uri("/api/libro").set_json(body).to_request();
+    let resp = test::call_service(app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    test::read_body_json(resp).await
+}
+
+fn id_of(book: &Value) -> &str {
+    book["_id"]["$oid"].as_str().expect("el libro no tiene _id")
+}
+
+// Verifica el código de estado y devuelve el cuerpo de la respuesta de error
+async fn expect_error(resp: ServiceResponse<impl MessageBody>, status: StatusCode) -> Value {
+    assert_eq!(resp.status(), status);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], status.to_string());
+    assert!(body["request_id"].is_string(), "falta el request_id en {}", body);
+    body
+}
+
+#[actix_web::test]
+async fn create_book_returns_created_book_with_etag() {
+    let app = app().await;
+    let mut body = book("Cien años de soledad");
+    body["isbn"] = json!("0-306-40615-2");
+
+    let req = test::TestRequest::post().uri("/api/libro").set_json(body).to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(resp.headers().contains_key(ETAG));
+    let created: Value = test::read_body_json(resp).await;
+    assert_eq!(created["titulo"], "Cien años de soledad");
+    assert_eq!(created["isbn"], "9780306406157"); // Se guarda normalizado a ISBN-13
+    assert_eq!(created["isbn10"], "0306406152");
+    assert_eq!(created["copias"], 1);
+}
+
+#[actix_web::test]
+async fn create_book_rejects_invalid_fields() {
+    let app = app().await;
+    let mut body = book("   ");
+    body["numero_pagina"] = json!(0);
+
+    let req = test::TestRequest::post().uri("/api/libro").set_json(body).to_request();
+    let body = expect_error(test::call_service(&app, req).await, StatusCode::UNPROCESSABLE_ENTITY).await;
+
+    let fields: Vec<&str> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["numero_pagina", "titulo"]);
+}
+
+#[actix_web::test]
+async fn create_book_rejects_duplicate_isbn() {
+    let app = app().await;
+    let mut body = book("Cien años de soledad");
+    body["isbn"] = json!("9780306406157");
+    create(&app, body.clone()).await;
+
+    let req = test::TestRequest::post().uri("/api/libro").set_json(body).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await;
+}
+
+#[actix_web::test]
+async fn get_all_books_paginates_and_links_next_page() {
+    let app = app().await;
+    for titulo in ["A", "B", "C"] {
+        create(&app, book(titulo)).await;
+    }
+
+    let req = test::TestRequest::get().uri("/api/libro?per_page=2&sort=titulo").to_request();
+    let page: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["page"], 1);
+    assert_eq!(page["data"].as_array().unwrap().len(), 2);
+    let next = page["next"].as_str().expect("falta el enlace a la siguiente página");
+
+    let req = test::TestRequest::get().uri(next).to_request();
+    let page: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page["page"], 2);
+    assert_eq!(page["data"][0]["titulo"], "C");
+    assert!(page["next"].is_null());
+}
+
+#[actix_web::test]
+async fn get_all_books_rejects_invalid_query() {
+    let app = app().await;
+
+    let req = test::TestRequest::get().uri("/api/libro?per_page=0").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::get().uri("/api/libro?after=no-es-un-id").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    // Una página cuyo desplazamiento no cabe en 64 bits se rechaza en lugar de desbordarse
+    let req = test::TestRequest::get().uri("/api/libro?page=18446744073709551615&per_page=100").to_request();
+    let body = expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+    assert_eq!(body["message"], "Parámetros de consulta inválidos: page es demasiado grande");
+}
+
+#[actix_web::test]
+async fn search_books_returns_relevant_books() {
+    let app = app().await;
+    create(&app, book("Cien años de soledad")).await;
+    create(&app, book("El otoño del patriarca")).await;
+
+    let req = test::TestRequest::get().uri("/api/libro/search?q=soledad").to_request();
+    let results: Value = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(results["query"], "soledad");
+    assert_eq!(results["total"], 1);
+    assert_eq!(results["data"][0]["libro"]["titulo"], "Cien años de soledad");
+}
+
+#[actix_web::test]
+async fn get_book_returns_book_and_honors_if_none_match() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let uri = format!("/api/libro/{}", id_of(&created));
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+    let found: Value = test::read_body_json(resp).await;
+    assert_eq!(found, created);
+
+    let req = test::TestRequest::get().uri(&uri).insert_header((IF_NONE_MATCH, etag)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[actix_web::test]
+async fn get_book_rejects_invalid_and_unknown_ids() {
+    let app = app().await;
+
+    let req = test::TestRequest::get().uri("/api/libro/123").to_request();
+    let body = expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+    assert_eq!(body["message"], "Error de ID inválido: ID inválido");
+
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}", MISSING_ID)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn get_book_by_isbn_accepts_isbn10_and_isbn13() {
+    let app = app().await;
+    let mut body = book("Cien años de soledad");
+    body["isbn"] = json!("978-0-306-40615-7");
+    let created = create(&app, body).await;
+
+    for isbn in ["0306406152", "978-0-306-40615-7"] {
+        let req = test::TestRequest::get().uri(&format!("/api/libro/isbn/{}", isbn)).to_request();
+        let found: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(found["_id"], created["_id"]);
+    }
+
+    let req = test::TestRequest::get().uri("/api/libro/isbn/1234").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::get().uri("/api/libro/isbn/9780131103627").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn get_book_by_isbn_honors_if_none_match() {
+    let app = app().await;
+    let mut body = book("Cien años de soledad");
+    body["isbn"] = json!("9780306406157");
+    create(&app, body).await;
+
+    let uri = "/api/libro/isbn/9780306406157";
+    let resp = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+
+    let req = test::TestRequest::get().uri(uri).insert_header((IF_NONE_MATCH, etag)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[actix_web::test]
+async fn update_book_applies_changes_and_checks_if_match() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let uri = format!("/api/libro/{}", id_of(&created));
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header((IF_MATCH, etag.clone()))
+        .set_json(json!({ "anio": 1968 }))
+        .to_request();
+    let updated: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated["anio"], 1968);
+    assert_eq!(updated["titulo"], "Cien años de soledad");
+
+    // El ETag anterior ya no corresponde a la versión actual
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header((IF_MATCH, etag))
+        .set_json(json!({ "anio": 1969 }))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::PRECONDITION_FAILED).await;
+}
+
+#[actix_web::test]
+async fn concurrent_writes_with_the_same_if_match_apply_only_once() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let uri = format!("/api/libro/{}", id_of(&created));
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+
+    // Dos clientes editan a la vez la versión que leyeron: solo uno de los dos cambios se aplica
+    let put = |anio: i32| {
+        test::TestRequest::put()
+            .uri(&uri)
+            .insert_header((IF_MATCH, etag.clone()))
+            .set_json(json!({ "anio": anio }))
+            .to_request()
+    };
+    let (first, second) = futures::join!(
+        test::call_service(&app, put(1968)),
+        test::call_service(&app, put(1969))
+    );
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::PRECONDITION_FAILED]);
+}
+
+#[actix_web::test]
+async fn repository_rejects_writes_on_a_stale_version() {
+    // Simula dos solicitudes que pasaron la verificación de `If-Match` antes de escribir
+    let repo = MemoryRepo::new();
+    let created = repo
+        .create_book(serde_json::from_value(book("Cien años de soledad")).unwrap(), "ana")
+        .await
+        .unwrap();
+    let id = created.id.unwrap();
+    let read_version = Some(created.version);
+
+    let changes = || serde_json::from_value(json!({ "anio": 1968 })).unwrap();
+    let updated = repo.update_book(id, changes(), read_version, "ana").await.unwrap();
+    assert_eq!(updated.version, created.version + 1);
+
+    // La segunda escritura sobre la misma versión no sobrescribe ni elimina el cambio anterior
+    let err = repo.update_book(id, changes(), read_version, "ana").await.unwrap_err();
+    assert!(matches!(err, AppError::PreconditionFailed), "{:?}", err);
+    let err = repo.delete_book(id, read_version, "ana").await.unwrap_err();
+    assert!(matches!(err, AppError::PreconditionFailed), "{:?}", err);
+    assert_eq!(repo.get_book(id).await.unwrap().anio, 1968);
+
+    // Cada escritura guardada tiene su entrada en el historial; las rechazadas no dejan ninguna
+    let history = repo.get_history(id).await.unwrap();
+    let actions: Vec<_> = history.iter().map(|entry| entry.action).collect();
+    assert_eq!(actions, [HistoryAction::Create, HistoryAction::Update]);
+    assert_eq!(history[1].snapshot.version, updated.version);
+}
+
+#[actix_web::test]
+async fn purge_removes_expired_books_and_their_history() {
+    let repo = MemoryRepo::new();
+    let trashed = repo
+        .create_book(serde_json::from_value(book("Cien años de soledad")).unwrap(), "ana")
+        .await
+        .unwrap();
+    let kept = repo
+        .create_book(serde_json::from_value(book("El otoño del patriarca")).unwrap(), "ana")
+        .await
+        .unwrap();
+    let trashed_id = trashed.id.unwrap();
+    repo.delete_book(trashed_id, None, "ana").await.unwrap();
+
+    // Solo se purgan los libros que entraron en la papelera antes del corte
+    let cutoff = DateTime::from_millis(DateTime::now().timestamp_millis() + 1000);
+    let purged = repo.purge_deleted_books(cutoff).await.unwrap();
+    assert_eq!(purged.iter().map(|book| book.id).collect::<Vec<_>>(), [Some(trashed_id)]);
+
+    assert!(repo.get_history(trashed_id).await.unwrap().is_empty());
+    assert_eq!(repo.get_history(kept.id.unwrap()).await.unwrap().len(), 1);
+    assert!(repo.purge_deleted_books(cutoff).await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn update_book_rejects_invalid_requests() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/libro/{}", id_of(&created)))
+        .set_json(json!({ "numero_pagina": -5 }))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::UNPROCESSABLE_ENTITY).await;
+
+    let req = test::TestRequest::put().uri("/api/libro/xyz").set_json(json!({ "anio": 1968 })).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/libro/{}", MISSING_ID))
+        .set_json(json!({ "anio": 1968 }))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn delete_book_moves_it_to_trash_until_restored() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let id = id_of(&created);
+    let uri = format!("/api/libro/{}", id);
+
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    expect_error(resp, StatusCode::NOT_FOUND).await;
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    expect_error(resp, StatusCode::NOT_FOUND).await;
+
+    let trash: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/libro/trash").to_request()).await;
+    assert_eq!(trash.as_array().unwrap().len(), 1);
+    assert_eq!(trash[0]["_id"], created["_id"]);
+    assert!(trash[0]["deleted_at"].is_object());
+
+    let restore_uri = format!("/api/libro/{}/restore", id);
+    let req = test::TestRequest::post().uri(&restore_uri).to_request();
+    let restored: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(restored.get("deleted_at").is_none());
+
+    // Ya no está en la papelera
+    let req = test::TestRequest::post().uri(&restore_uri).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn trashed_books_release_their_isbn_until_restored() {
+    let app = app().await;
+    let mut body = book("Cien años de soledad");
+    body["isbn"] = json!("9780306406157");
+    let trashed = create(&app, body.clone()).await;
+    let uri = format!("/api/libro/{}", id_of(&trashed));
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    // El ISBN del libro en la papelera se puede volver a registrar y la búsqueda encuentra el activo
+    let replacement = create(&app, body).await;
+    let req = test::TestRequest::get().uri("/api/libro/isbn/9780306406157").to_request();
+    let found: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(found["_id"], replacement["_id"]);
+
+    // Restaurar el primero duplicaría el ISBN
+    let req = test::TestRequest::post().uri(&format!("{}/restore", uri)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await;
+    let trash: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/libro/trash").to_request()).await;
+    assert_eq!(trash[0]["_id"], trashed["_id"]);
+}
+
+#[actix_web::test]
+async fn author_names_resolve_to_one_registered_author() {
+    let app = app().await;
+    let first = create(&app, book("Cien años de soledad")).await;
+    let mut body = book("El otoño del patriarca");
+    body["autor"] = json!("gabriel garcia marquez");
+    let second = create(&app, body).await;
+
+    // El nombre escrito de otra forma se asocia al mismo autor y toma su nombre registrado
+    let author_id = first["autor_ref"]["_id"]["$oid"].as_str().expect("el libro no referencia un autor");
+    assert_eq!(second["autor_ref"], first["autor_ref"]);
+    assert_eq!(second["autor"], "Gabriel García Márquez");
+    let authors: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/autores").to_request()).await;
+    assert_eq!(authors.as_array().map(Vec::len), Some(1));
+
+    // Registrarlo otra vez con otra grafía es un conflicto
+    let req = test::TestRequest::post()
+        .uri("/api/autores")
+        .set_json(json!({"nombre": "GABRIEL GARCÍA MÁRQUEZ"}))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await;
+
+    // Al renombrarlo cambia en sus libros y no se puede eliminar mientras lo referencien
+    let uri = format!("/api/autores/{}", author_id);
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header((USER_HEADER, "ana"))
+        .set_json(json!({"nombre": "Gabo"}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}", id_of(&second))).to_request();
+    let renamed: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(renamed["autor"], "Gabo");
+    assert_eq!(renamed["version"], 1);
+
+    // El cambio de nombre queda en el historial de cada libro
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}/history", id_of(&second))).to_request();
+    let history: Value = test::call_and_read_body_json(&app, req).await;
+    let last = history.as_array().and_then(|entries| entries.last()).cloned().unwrap_or_default();
+    assert_eq!(last["action"], "update");
+    assert_eq!(last["user"], "ana");
+    assert_eq!(last["snapshot"], renamed);
+    assert!(last["changes"].as_array().unwrap().iter().any(|change| change["field"] == "autor"), "{}", last);
+    let req = test::TestRequest::delete().uri(&uri).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await;
+}
+
+#[actix_web::test]
+async fn publisher_routes_manage_publishers_and_their_books() {
+    let app = app().await;
+    let req = test::TestRequest::post()
+        .uri("/api/editoriales")
+        .set_json(json!({"nombre": "Alfaguara", "pais": "España"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let publisher: Value = test::read_body_json(resp).await;
+    let uri = format!("/api/editoriales/{}", id_of(&publisher));
+
+    let mut body = book("Rayuela");
+    body["editorial"] = json!("alfaguara");
+    let created = create(&app, body).await;
+    assert_eq!(created["editorial"], "Alfaguara");
+
+    let req = test::TestRequest::get().uri(&format!("{}/libros", uri)).to_request();
+    let page: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page["total"], 1);
+    assert_eq!(page["data"][0]["_id"], created["_id"]);
+
+    let req = test::TestRequest::put().uri(&uri).set_json(json!({"pais": "México"})).to_request();
+    let updated: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((&updated["nombre"], &updated["pais"]), (&json!("Alfaguara"), &json!("México")));
+    let fetched: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(fetched, updated);
+
+    // El libro no registró otra editorial, y la editorial solo se elimina sin libros que la referencien
+    let req = test::TestRequest::get().uri("/api/editoriales").to_request();
+    let publishers: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(publishers.as_array().map(Vec::len), Some(1));
+    let req = test::TestRequest::delete().uri(&uri).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await;
+    let req = test::TestRequest::delete().uri(&format!("/api/libro/{}", id_of(&created))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    let req = test::TestRequest::delete().uri(&uri).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::CONFLICT).await; // La papelera también cuenta
+}
+
+#[actix_web::test]
+async fn publisher_routes_reject_invalid_requests() {
+    let app = app().await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/editoriales")
+        .set_json(json!({"nombre": "  ", "sitio_web": "no es una url"}))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::UNPROCESSABLE_ENTITY).await;
+
+    let req = test::TestRequest::get().uri("/api/editoriales/xyz").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+    let req = test::TestRequest::get().uri(&format!("/api/editoriales/{}/libros", MISSING_ID)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/editoriales/{}", MISSING_ID))
+        .set_json(json!({"pais": "Chile"}))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+    let req = test::TestRequest::delete().uri(&format!("/api/editoriales/{}", MISSING_ID)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn export_books_streams_csv_and_ndjson() {
+    let app = app().await;
+    let first = create(&app, book("Cien años de soledad")).await;
+    create(&app, book("El otoño del patriarca")).await;
+
+    let req = test::TestRequest::get().uri("/api/libro/export").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/csv"));
+    assert!(resp.headers().get(CONTENT_DISPOSITION).unwrap().to_str().unwrap().contains("libros.csv"));
+    let csv = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert_eq!(csv.lines().count(), 3); // Encabezados y una fila por libro
+    assert!(csv.contains("Cien años de soledad"));
+
+    let req = test::TestRequest::get().uri("/api/libro/export?format=ndjson").to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let books: Vec<Value> = body
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    assert_eq!(books.len(), 2);
+    assert_eq!(books[0]["_id"], first["_id"]);
+}
+
+#[actix_web::test]
+async fn export_books_rejects_unknown_format() {
+    let app = app().await;
+    let req = test::TestRequest::get().uri("/api/libro/export?format=xml").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+// Envía una importación NDJSON con una fila por libro
+async fn import(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    mode: &str,
+    rows: &[Value],
+) -> ServiceResponse<impl MessageBody> {
+    let body: String = rows.iter().map(|row| format!("{}\n", row)).collect();
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/libro/import?format=ndjson&mode={}", mode))
+        .set_payload(body)
+        .to_request();
+    test::call_service(app, req).await
+}
+
+#[actix_web::test]
+async fn failed_all_or_nothing_import_can_be_retried() {
+    let app = app().await;
+    let mut existing = book("El otoño del patriarca");
+    existing["isbn"] = json!("9780306406157");
+    create(&app, existing).await;
+
+    // La segunda fila repite un ISBN ya registrado: no se guarda ninguna, ni el autor nuevo de la primera
+    let mut first = book("Cien años de soledad");
+    first["isbn"] = json!("9781861972712");
+    first["autor"] = json!("Autora Nueva");
+    let mut second = book("Crónica de una muerte anunciada");
+    second["isbn"] = json!("9780306406157");
+    let resp = import(&app, "all_or_nothing", &[first.clone(), second.clone()]).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["imported"], 0);
+    assert_eq!(report["rows"][0]["status"], "skipped");
+    assert_eq!(report["rows"][1]["status"], "rejected");
+
+    assert!(report["rows"][0].get("id").is_none(), "{}", report);
+    let list: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/libro").to_request()).await;
+    assert_eq!(list["total"], 1);
+    let authors: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/autores").to_request()).await;
+    assert_eq!(authors.as_array().map(Vec::len), Some(1));
+
+    // Nada queda en la papelera reservando el ISBN
+    let trash: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/libro/trash").to_request()).await;
+    assert_eq!(trash, json!([]));
+
+    // Corregida la fila, el mismo archivo se importa completo
+    second["isbn"] = json!("9780131103627");
+    let resp = import(&app, "all_or_nothing", &[first, second]).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["imported"], 2);
+    let req = test::TestRequest::get().uri("/api/libro/isbn/9781861972712").to_request();
+    let found: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(found["titulo"], "Cien años de soledad");
+
+    // El intento fallido no deja entradas en el historial
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}/history", id_of(&found))).to_request();
+    let history: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(history[0]["action"], "create");
+}
+
+#[actix_web::test]
+async fn interrupted_import_reports_the_rows_already_saved() {
+    let app = app().await;
+    let oversized = json!({ "titulo": "x".repeat(70 * 1024) });
+    let rows = [book("Cien años de soledad"), oversized, book("El otoño del patriarca")];
+
+    // En modo best_effort la primera fila ya se guardó cuando llega la fila demasiado grande
+    let resp = import(&app, "best_effort", &rows).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["rows"][0]["status"], "imported");
+    assert!(report["error"].as_str().unwrap().contains("La fila 2"), "{}", report);
+
+    // En modo todo o nada no se guarda nada
+    let resp = import(&app, "all_or_nothing", &rows).await;
+    expect_error(resp, StatusCode::PAYLOAD_TOO_LARGE).await;
+    let list: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/api/libro").to_request()).await;
+    assert_eq!(list["total"], 1);
+}
+
+#[actix_web::test]
+async fn delete_and_restore_reject_invalid_ids() {
+    let app = app().await;
+
+    let req = test::TestRequest::delete().uri("/api/libro/abc").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::post().uri("/api/libro/abc/restore").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::post().uri(&format!("/api/libro/{}/restore", MISSING_ID)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn history_records_changes_and_revert_restores_a_version() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let id = id_of(&created);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/libro/{}", id))
+        .insert_header((USER_HEADER, "ana"))
+        .set_json(json!({ "titulo": "Cien años" }))
+        .to_request();
+    test::call_and_read_body_json::<_, _, Value>(&app, req).await;
+
+    let history_uri = format!("/api/libro/{}/history", id);
+    let history: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&history_uri).to_request()).await;
+    let entries = history.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "create");
+    assert_eq!(entries[0]["user"], "anonimo");
+    assert_eq!(entries[1]["action"], "update");
+    assert_eq!(entries[1]["user"], "ana");
+
+    let first_entry = entries[0]["_id"]["$oid"].as_str().unwrap();
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/libro/{}/history/{}/revert", id, first_entry))
+        .to_request();
+    let reverted: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(reverted["titulo"], "Cien años de soledad");
+
+    let history: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri(&history_uri).to_request()).await;
+    assert_eq!(history.as_array().unwrap().last().unwrap()["action"], "revert");
+}
+
+#[actix_web::test]
+async fn history_and_revert_reject_invalid_and_unknown_ids() {
+    let app = app().await;
+    let created = create(&app, book("Cien años de soledad")).await;
+    let id = id_of(&created);
+
+    let req = test::TestRequest::get().uri("/api/libro/abc/history").to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}/history", MISSING_ID)).to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+
+    let req = test::TestRequest::post().uri(&format!("/api/libro/{}/history/abc/revert", id)).to_request();
+    let body = expect_error(test::call_service(&app, req).await, StatusCode::BAD_REQUEST).await;
+    assert_eq!(body["message"], "Error de ID inválido: ID de historial inválido");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/libro/{}/history/{}/revert", id, MISSING_ID))
+        .to_request();
+    expect_error(test::call_service(&app, req).await, StatusCode::NOT_FOUND).await;
+}
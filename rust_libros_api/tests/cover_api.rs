@@ -0,0 +1,211 @@
+// Pruebas de integración de los endpoints de portadas, con el repositorio en memoria y las imágenes en un
+// directorio temporal.
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{
+        header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH},
+        StatusCode,
+    },
+    middleware::from_fn,
+    test, web, App, Error,
+};
+use image::{ImageFormat, Rgb, RgbImage};
+use mongodb::bson::oid::ObjectId;
+use rust_mongodb_crud::{
+    api,
+    repository::{
+        book_repository::BookRepository, cover_repository::CoverRepository, fs_cover_repo::FsCoverRepo,
+        memory_repo::MemoryRepo, Repositories,
+    },
+    telemetry,
+};
+use serde_json::{json, Value};
+
+const BOUNDARY: &str = "portada-de-prueba";
+
+// Aplicación con las mismas rutas que el servidor; devuelve también el directorio de las imágenes
+async fn app() -> (impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("portadas-{}", ObjectId::new().to_hex()));
+    let covers: Arc<dyn CoverRepository> = Arc::new(FsCoverRepo::new(&dir).unwrap());
+    let repos = Repositories::new(Arc::new(MemoryRepo::new()));
+    let book_data: web::Data<dyn BookRepository> = web::Data::from(repos.books);
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(telemetry::request_context))
+            .app_data(book_data)
+            .app_data(web::Data::from(covers))
+            .app_data(web::Data::from(repos.authors))
+            .app_data(web::Data::from(repos.publishers))
+            .app_data(web::Data::from(repos.loans))
+            .service(web::scope("/api").configure(api::routes)),
+    )
+    .await;
+    (app, dir)
+}
+
+// Crea un libro y devuelve su ID y su ETag
+async fn create(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+) -> (String, String) {
+    let body = json!({
+        "titulo": "Rayuela",
+        "autor": "Julio Cortázar",
+        "editorial": "Sudamericana",
+        "anio": 1963,
+        "descripcion": "Novela",
+        "numero_pagina": 600,
+    });
+    let req = test::TestRequest::post().uri("/api/libro").set_json(body).to_request();
+    let resp = test::call_service(app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let etag = resp.headers().get(ETAG).unwrap().to_str().unwrap().to_string();
+    let book: Value = test::read_body_json(resp).await;
+    (book["_id"]["$oid"].as_str().unwrap().to_string(), etag)
+}
+
+// Imagen PNG de un solo color
+fn png(color: [u8; 3]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    RgbImage::from_pixel(40, 60, Rgb(color))
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+// Solicitud de subida con la imagen en el campo `portada` del formulario
+fn upload(id: &str, image: &[u8]) -> test::TestRequest {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"portada\"; filename=\"portada.png\"\r\nContent-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(image);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    test::TestRequest::put()
+        .uri(&format!("/api/libro/{}/portada", id))
+        .insert_header((CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}")))
+        .set_payload(body)
+}
+
+async fn get_cover(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    id: &str,
+) -> ServiceResponse<impl MessageBody> {
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}/portada", id)).to_request();
+    test::call_service(app, req).await
+}
+
+// Cantidad de imágenes guardadas en el directorio
+fn stored_images(dir: &Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[actix_web::test]
+async fn upload_cover_serves_original_and_thumbnails() {
+    let (app, dir) = app().await;
+    let (id, _) = create(&app).await;
+    let image = png([200, 30, 30]);
+
+    let resp = test::call_service(&app, upload(&id, &image).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().contains_key(ETAG));
+    let book: Value = test::read_body_json(resp).await;
+    assert_eq!(book["portada"]["content_type"], "image/png");
+    assert_eq!(book["portada"]["ancho"], 40);
+    assert_eq!(stored_images(&dir), 3); // Original y dos miniaturas
+
+    let resp = get_cover(&app, &id).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "image/png");
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+    assert_eq!(test::read_body(resp).await, image);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/libro/{}/portada?size=thumb", id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "image/jpeg");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/libro/{}/portada", id))
+        .insert_header((IF_NONE_MATCH, etag))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[actix_web::test]
+async fn upload_cover_rejects_invalid_requests() {
+    let (app, _) = app().await;
+    let (id, _) = create(&app).await;
+
+    let resp = test::call_service(&app, upload(&id, b"no es una imagen").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let missing = ObjectId::new().to_hex();
+    let resp = test::call_service(&app, upload(&missing, &png([0, 0, 0])).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // Un libro sin portada responde 404
+    assert_eq!(get_cover(&app, &id).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn upload_cover_with_stale_version_keeps_current_cover() {
+    let (app, dir) = app().await;
+    let (id, stale_etag) = create(&app).await;
+    let current = png([10, 120, 10]);
+    let resp = test::call_service(&app, upload(&id, &current).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = upload(&id, &png([10, 10, 120])).insert_header((IF_MATCH, stale_etag)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_FAILED);
+
+    // La portada anterior se sigue sirviendo y no quedan imágenes huérfanas
+    let resp = get_cover(&app, &id).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(test::read_body(resp).await, current);
+    assert_eq!(stored_images(&dir), 3);
+}
+
+#[actix_web::test]
+async fn replacing_cover_removes_previous_images() {
+    let (app, dir) = app().await;
+    let (id, _) = create(&app).await;
+    let resp = test::call_service(&app, upload(&id, &png([1, 2, 3])).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let replacement = png([250, 250, 0]);
+    let resp = test::call_service(&app, upload(&id, &replacement).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = get_cover(&app, &id).await;
+    assert_eq!(test::read_body(resp).await, replacement);
+    assert_eq!(stored_images(&dir), 3);
+}
+
+#[actix_web::test]
+async fn delete_cover_removes_images() {
+    let (app, dir) = app().await;
+    let (id, _) = create(&app).await;
+    let resp = test::call_service(&app, upload(&id, &png([9, 9, 9])).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let uri = format!("/api/libro/{}/portada", id);
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(stored_images(&dir), 0);
+    assert_eq!(get_cover(&app, &id).await.status(), StatusCode::NOT_FOUND);
+
+    // Sin portada no hay nada que eliminar
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,64 @@
+// Pruebas del servicio gRPC, llamando a sus métodos directamente sobre el repositorio en memoria.
+
+use std::sync::Arc;
+
+use rust_mongodb_crud::{
+    grpc::{
+        pb::{libros_server::Libros, CreateBookRequest, DeleteBookRequest, GetBookRequest, UpdateBookRequest},
+        BookService,
+    },
+    repository::memory_repo::MemoryRepo,
+};
+use tonic::{Code, Request};
+
+// Datos mínimos de un libro válido
+fn create_request(isbn: &str) -> CreateBookRequest {
+    CreateBookRequest {
+        titulo: "Cien años de soledad".to_string(),
+        autor: "Gabriel García Márquez".to_string(),
+        editorial: "Sudamericana".to_string(),
+        anio: 1967,
+        descripcion: "Novela sobre la familia Buendía".to_string(),
+        numero_pagina: 471,
+        isbn: Some(isbn.to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn update_and_delete_check_the_version() {
+    let service = BookService::new(Arc::new(MemoryRepo::new()));
+    let created = service.create_book(Request::new(create_request("9780306406157"))).await.unwrap().into_inner();
+
+    let update = |version| UpdateBookRequest {
+        id: created.id.clone(),
+        anio: Some(1968),
+        version: Some(version),
+        ..Default::default()
+    };
+    let updated = service.update_book(Request::new(update(created.version))).await.unwrap().into_inner();
+    assert_eq!(updated.version, created.version + 1);
+
+    // La versión leída antes del cambio ya no sirve para escribir
+    let status = service.update_book(Request::new(update(created.version))).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    let delete = |version| DeleteBookRequest { id: created.id.clone(), version: Some(version) };
+    let status = service.delete_book(Request::new(delete(created.version))).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    service.delete_book(Request::new(delete(updated.version))).await.unwrap();
+    let status = service
+        .get_book(Request::new(GetBookRequest { id: created.id.clone() }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn duplicate_isbn_is_already_exists() {
+    let service = BookService::new(Arc::new(MemoryRepo::new()));
+    service.create_book(Request::new(create_request("9780306406157"))).await.unwrap();
+
+    let status = service.create_book(Request::new(create_request("978-0-306-40615-7"))).await.unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+}
@@ -0,0 +1,144 @@
+// Pruebas de integración de los endpoints de salud y de métricas, con dependencias simuladas.
+
+use std::{sync::Arc, time::Duration};
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::from_fn,
+    test, web, App, Error,
+};
+use async_trait::async_trait;
+use rust_mongodb_crud::{
+    api::health_api::{live, ready},
+    health::{HealthCheck, Readiness},
+    metrics,
+};
+use serde_json::Value;
+
+// Dependencia que responde siempre igual, o que no responde a tiempo
+enum FakeCheck {
+    Up,
+    Failing,
+    Hanging,
+}
+
+#[async_trait]
+impl HealthCheck for FakeCheck {
+    fn name(&self) -> &'static str {
+        match self {
+            FakeCheck::Up => "mongodb",
+            FakeCheck::Failing => "portadas",
+            FakeCheck::Hanging => "lenta",
+        }
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        match self {
+            FakeCheck::Up => Ok(()),
+            FakeCheck::Failing => Err("sin espacio en disco".to_string()),
+            FakeCheck::Hanging => {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+// Aplicación con los endpoints de salud y métricas del servidor, sobre las dependencias indicadas
+async fn app(
+    checks: Vec<FakeCheck>,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let checks = checks.into_iter().map(|check| Arc::new(check) as Arc<dyn HealthCheck>).collect();
+    metrics::init();
+    test::init_service(
+        App::new()
+            .wrap(from_fn(metrics::track_requests))
+            .app_data(web::Data::new(Readiness::new(checks, Duration::from_millis(50))))
+            .service(live)
+            .service(ready)
+            .service(metrics::metrics),
+    )
+    .await
+}
+
+// Texto de `/metrics`
+async fn scrape(app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>) -> String {
+    let resp = test::call_service(app, test::TestRequest::get().uri("/metrics").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/plain"));
+    String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+}
+
+// Valor del contador de solicitudes de una ruta con un código de estado (0 si todavía no existe)
+fn requests_total(metrics: &str, route: &str, status: &str) -> u64 {
+    let (route, status) = (format!("route=\"{}\"", route), format!("status=\"{}\"", status));
+    metrics
+        .lines()
+        .filter(|line| line.starts_with("http_requests_total{"))
+        .filter(|line| line.contains(&route) && line.contains(&status))
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+        .sum()
+}
+
+#[actix_web::test]
+async fn live_responds_while_the_process_runs() {
+    let app = app(vec![FakeCheck::Failing]).await;
+    let req = test::TestRequest::get().uri("/health/live").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["status"], "up");
+}
+
+#[actix_web::test]
+async fn ready_reports_up_when_every_dependency_responds() {
+    let app = app(vec![FakeCheck::Up]).await;
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/health/ready").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["status"], "up");
+    assert_eq!(report["checks"]["mongodb"]["status"], "up");
+}
+
+#[actix_web::test]
+async fn ready_reports_failing_and_slow_dependencies() {
+    let app = app(vec![FakeCheck::Up, FakeCheck::Failing, FakeCheck::Hanging]).await;
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/health/ready").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["status"], "down");
+    assert_eq!(report["checks"]["mongodb"]["status"], "up");
+    assert_eq!(report["checks"]["portadas"]["error"], "sin espacio en disco");
+    assert_eq!(report["checks"]["lenta"]["status"], "down");
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_by_route_pattern() {
+    let app = app(vec![FakeCheck::Up]).await;
+    let before = requests_total(&scrape(&app).await, "/health/live", "200");
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/health/live").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Las pruebas comparten el registro de métricas y corren en paralelo: otras también pueden sumar a la serie
+    let after = scrape(&app).await;
+    assert!(requests_total(&after, "/health/live", "200") > before);
+    assert!(after.contains("http_request_duration_seconds_bucket"));
+}
+
+#[actix_web::test]
+async fn metrics_group_unknown_routes_under_one_label() {
+    let app = app(vec![FakeCheck::Up]).await;
+    let before = requests_total(&scrape(&app).await, "unmatched", "404");
+
+    // Cada URL desconocida suma a la misma serie en lugar de crear una nueva
+    for uri in ["/no-existe", "/otra/ruta/desconocida"] {
+        let resp = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    let after = scrape(&app).await;
+    assert!(requests_total(&after, "unmatched", "404") >= before + 2);
+    assert!(!after.contains("no-existe"));
+}
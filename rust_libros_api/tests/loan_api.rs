@@ -0,0 +1,364 @@
+// Pruebas de integración de los endpoints de préstamos, con el repositorio en memoria (sin MongoDB).
+
+use std::sync::Arc;
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::StatusCode,
+    test, web, App, Error,
+};
+use mongodb::bson::DateTime;
+use rust_mongodb_crud::{
+    api,
+    model::{HoldStatus, LoanPolicy},
+    repository::{
+        book_repository::BookRepository, loan_repository::LoanRepository, memory_repo::MemoryRepo, Repositories,
+    },
+};
+use serde_json::{json, Value};
+
+// Límite de préstamos activos por socio en las pruebas
+const MAX_LOANS: usize = 2;
+// Milisegundos de un día
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Aplicación con las mismas rutas que el servidor, sobre un repositorio en memoria vacío
+async fn app() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let policy = LoanPolicy {
+        max_loans: MAX_LOANS,
+        ..LoanPolicy::default()
+    };
+    let repos = Repositories::new(Arc::new(MemoryRepo::new().with_loan_policy(policy)));
+    let book_data: web::Data<dyn BookRepository> = web::Data::from(repos.books);
+    test::init_service(
+        App::new()
+            .app_data(book_data)
+            .app_data(web::Data::from(repos.authors))
+            .app_data(web::Data::from(repos.publishers))
+            .app_data(web::Data::from(repos.loans))
+            .service(web::scope("/api").configure(api::routes)),
+    )
+    .await
+}
+
+// Datos mínimos de un libro válido (con una copia)
+fn book(titulo: &str) -> Value {
+    json!({
+        "titulo": titulo,
+        "autor": "Gabriel García Márquez",
+        "editorial": "Sudamericana",
+        "anio": 1967,
+        "descripcion": "Novela sobre la familia Buendía",
+        "numero_pagina": 471,
+    })
+}
+
+// Crea un libro con una copia y devuelve su ID
+async fn create_book(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    titulo: &str,
+) -> String {
+    let req = test::TestRequest::post().uri("/api/libro").set_json(book(titulo)).to_request();
+    let book: Value = test::call_and_read_body_json(app, req).await;
+    book["_id"]["$oid"].as_str().expect("el libro no tiene _id").to_string()
+}
+
+// Solicitud de préstamo de un libro para un socio
+fn checkout(book_id: &str, member_id: &str) -> Request {
+    test::TestRequest::post()
+        .uri("/api/prestamos")
+        .set_json(json!({"libro_id": book_id, "socio_id": member_id}))
+        .to_request()
+}
+
+// Solicitud de reserva de un libro para un socio
+fn place_hold(book_id: &str, member_id: &str) -> Request {
+    test::TestRequest::post()
+        .uri("/api/reservas")
+        .set_json(json!({"libro_id": book_id, "socio_id": member_id}))
+        .to_request()
+}
+
+fn post(uri: &str) -> Request {
+    test::TestRequest::post().uri(uri).to_request()
+}
+
+fn id_of(body: &Value) -> String {
+    body["_id"]["$oid"].as_str().expect("falta el _id").to_string()
+}
+
+// Cola de reservas pendientes de un libro, como (socio, estado)
+async fn queue(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error>,
+    book_id: &str,
+) -> Vec<(String, String)> {
+    let req = test::TestRequest::get().uri(&format!("/api/libro/{}/reservas", book_id)).to_request();
+    let holds: Value = test::call_and_read_body_json(app, req).await;
+    holds
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hold| (hold["socio_id"].as_str().unwrap().to_string(), hold["estado"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+fn entry(member_id: &str, estado: &str) -> (String, String) {
+    (member_id.to_string(), estado.to_string())
+}
+
+#[actix_web::test]
+async fn checkout_stops_at_the_member_loan_limit() {
+    let app = app().await;
+    let mut books = Vec::new();
+    for titulo in ["Cien años de soledad", "El otoño del patriarca", "Del amor y otros demonios"] {
+        books.push(create_book(&app, titulo).await);
+    }
+
+    let mut loans = Vec::new();
+    for book_id in &books[..MAX_LOANS] {
+        let resp = test::call_service(&app, checkout(book_id, "socio-1")).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let loan: Value = test::read_body_json(resp).await;
+        loans.push(loan["_id"]["$oid"].as_str().unwrap().to_string());
+    }
+
+    // Un préstamo más supera el límite del socio, pero no el de otro socio
+    let resp = test::call_service(&app, checkout(&books[MAX_LOANS], "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["message"].as_str().unwrap().contains("límite"), "mensaje inesperado: {}", body);
+    let resp = test::call_service(&app, checkout(&books[MAX_LOANS], "socio-2")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Al devolver un préstamo el socio vuelve a tener cupo
+    let uri = format!("/api/prestamos/{}/devolucion", loans[0]);
+    let resp = test::call_service(&app, test::TestRequest::post().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(&app, checkout(&books[0], "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
+
+#[actix_web::test]
+async fn concurrent_checkouts_cannot_exceed_the_loan_limit() {
+    let app = app().await;
+    let mut books = Vec::new();
+    for titulo in ["Cien años de soledad", "El otoño del patriarca", "Del amor y otros demonios"] {
+        books.push(create_book(&app, titulo).await);
+    }
+    let resp = test::call_service(&app, checkout(&books[0], "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Al socio le queda un solo préstamo: de dos pedidos a la vez solo uno se concede
+    let (first, second) = futures::join!(
+        test::call_service(&app, checkout(&books[1], "socio-1")),
+        test::call_service(&app, checkout(&books[2], "socio-1"))
+    );
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT]);
+
+    let req = test::TestRequest::get().uri("/api/socios/socio-1/prestamos").to_request();
+    let active: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(active.as_array().map(Vec::len), Some(MAX_LOANS));
+}
+
+#[actix_web::test]
+async fn checkout_rejects_a_second_loan_of_the_same_book() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let resp = test::call_service(&app, checkout(&book_id, "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // El mismo socio no puede llevarse el libro dos veces, y otro no encuentra copias disponibles
+    let resp = test::call_service(&app, checkout(&book_id, "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = test::call_service(&app, checkout(&book_id, "socio-2")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn return_loan_sets_the_copy_aside_for_the_first_hold() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let loan: Value = test::call_and_read_body_json(&app, checkout(&book_id, "socio-1")).await;
+
+    // Sin copias disponibles el libro se reserva; la renovación queda bloqueada mientras haya reservas en espera
+    let resp = test::call_service(&app, place_hold(&book_id, "socio-2")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert_eq!(queue(&app, &book_id).await, [entry("socio-2", "waiting")]);
+    let resp = test::call_service(&app, post(&format!("/api/prestamos/{}/renovacion", id_of(&loan)))).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let uri = format!("/api/prestamos/{}/devolucion", id_of(&loan));
+    let resp = test::call_service(&app, post(&uri)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let returned: Value = test::read_body_json(resp).await;
+    assert_eq!(returned["estado"], "returned");
+    assert_eq!(test::call_service(&app, post(&uri)).await.status(), StatusCode::CONFLICT);
+
+    // La copia devuelta espera al socio de la reserva
+    assert_eq!(queue(&app, &book_id).await, [entry("socio-2", "ready")]);
+    let resp = test::call_service(&app, checkout(&book_id, "socio-3")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = test::call_service(&app, checkout(&book_id, "socio-2")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(queue(&app, &book_id).await.is_empty());
+}
+
+#[actix_web::test]
+async fn return_loan_rejects_invalid_ids() {
+    let app = app().await;
+    let resp = test::call_service(&app, post("/api/prestamos/xyz/devolucion")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, post("/api/prestamos/65f1a2b3c4d5e6f708192a3b/devolucion")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn renew_loan_extends_the_due_date_up_to_the_limit() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let loan: Value = test::call_and_read_body_json(&app, checkout(&book_id, "socio-1")).await;
+    let uri = format!("/api/prestamos/{}/renovacion", id_of(&loan));
+
+    let due = |loan: &Value| loan["vence_en"]["$date"]["$numberLong"].as_str().unwrap().parse::<i64>().unwrap();
+    let mut previous_due = due(&loan);
+    for renovaciones in 1..=LoanPolicy::default().max_renewals {
+        let resp = test::call_service(&app, post(&uri)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let renewed: Value = test::read_body_json(resp).await;
+        assert_eq!(renewed["renovaciones"], renovaciones);
+        assert_eq!(due(&renewed), previous_due + LoanPolicy::default().loan_days * DAY_MS);
+        previous_due = due(&renewed);
+    }
+
+    let resp = test::call_service(&app, post(&uri)).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = test::call_service(&app, post("/api/prestamos/65f1a2b3c4d5e6f708192a3b/renovacion")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn overdue_loans_lists_only_loans_past_their_due_date() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let resp = test::call_service(&app, checkout(&book_id, "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Un préstamo recién creado todavía no vence (la ruta no se confunde con `/prestamos/{id}`)
+    let req = test::TestRequest::get().uri("/api/prestamos/vencidos").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let overdue: Value = test::read_body_json(resp).await;
+    assert_eq!(overdue, json!([]));
+
+    // El repositorio compara con la fecha indicada
+    let repo = MemoryRepo::new();
+    let created = repo
+        .create_book(serde_json::from_value(book("Cien años de soledad")).unwrap(), "ana")
+        .await
+        .unwrap();
+    let loan = repo.checkout(created.id.unwrap(), "socio-1").await.unwrap();
+    let later = DateTime::from_millis(loan.vence_en.timestamp_millis() + 1);
+    assert!(repo.get_overdue_loans(DateTime::now()).await.unwrap().is_empty());
+    assert_eq!(repo.get_overdue_loans(later).await.unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn holds_are_rejected_while_copies_are_available() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+
+    let resp = test::call_service(&app, place_hold(&book_id, "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = test::call_service(&app, place_hold("65f1a2b3c4d5e6f708192a3b", "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = test::call_service(&app, place_hold("xyz", "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let req = test::TestRequest::get().uri("/api/libro/xyz/reservas").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn cancelled_ready_hold_passes_the_copy_to_the_next_in_queue() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let loan: Value = test::call_and_read_body_json(&app, checkout(&book_id, "socio-1")).await;
+    let first: Value = test::call_and_read_body_json(&app, place_hold(&book_id, "socio-2")).await;
+    let resp = test::call_service(&app, place_hold(&book_id, "socio-3")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = test::call_service(&app, place_hold(&book_id, "socio-3")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT); // Una sola reserva por socio
+    test::call_service(&app, post(&format!("/api/prestamos/{}/devolucion", id_of(&loan)))).await;
+
+    let uri = format!("/api/reservas/{}", id_of(&first));
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let cancelled: Value = test::read_body_json(resp).await;
+    assert_eq!(cancelled["estado"], "cancelled");
+    assert_eq!(queue(&app, &book_id).await, [entry("socio-3", "ready")]);
+
+    let resp = test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let req = test::TestRequest::delete().uri("/api/reservas/65f1a2b3c4d5e6f708192a3b").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn adding_copies_sets_them_aside_for_waiting_holds() {
+    let app = app().await;
+    let book_id = create_book(&app, "Cien años de soledad").await;
+    let resp = test::call_service(&app, checkout(&book_id, "socio-1")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    for member_id in ["socio-2", "socio-3", "socio-4"] {
+        let resp = test::call_service(&app, place_hold(&book_id, member_id)).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/libro/{}", book_id))
+        .set_json(json!({"copias": 3}))
+        .to_request();
+    let updated: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated["apartados"], 2);
+
+    // Las dos copias nuevas esperan a las reservas más antiguas
+    assert_eq!(
+        queue(&app, &book_id).await,
+        [entry("socio-2", "ready"), entry("socio-3", "ready"), entry("socio-4", "waiting")]
+    );
+}
+
+#[actix_web::test]
+async fn expired_ready_holds_release_their_copy() {
+    let repo = MemoryRepo::new();
+    let created = repo
+        .create_book(serde_json::from_value(book("Cien años de soledad")).unwrap(), "ana")
+        .await
+        .unwrap();
+    let book_id = created.id.unwrap();
+    let loan = repo.checkout(book_id, "socio-1").await.unwrap();
+    repo.place_hold(book_id, "socio-2").await.unwrap();
+    repo.place_hold(book_id, "socio-3").await.unwrap();
+    repo.return_loan(loan.id.unwrap()).await.unwrap();
+
+    // Antes del plazo la reserva lista se conserva
+    assert_eq!(repo.expire_holds(DateTime::now()).await.unwrap(), 0);
+
+    // Vencido el plazo, la copia pasa a la siguiente reserva de la cola
+    let hold_days = LoanPolicy::default().hold_days;
+    let later = DateTime::from_millis(DateTime::now().timestamp_millis() + (hold_days * DAY_MS) + 1000);
+    assert_eq!(repo.expire_holds(later).await.unwrap(), 1);
+    let holds = repo.get_book_holds(book_id).await.unwrap();
+    assert_eq!(holds.len(), 1);
+    assert_eq!((holds[0].socio_id.as_str(), holds[0].estado), ("socio-3", HoldStatus::Ready));
+
+    // Sin más reservas, la copia vuelve a estar disponible
+    let even_later = DateTime::from_millis(later.timestamp_millis() + (hold_days * DAY_MS) + 1000);
+    assert_eq!(repo.expire_holds(even_later).await.unwrap(), 1);
+    assert!(repo.get_book_holds(book_id).await.unwrap().is_empty());
+    assert_eq!(repo.get_book(book_id).await.unwrap().disponibles(), 1);
+}